@@ -32,6 +32,22 @@ fn bench_8digits_tp(c: &mut Criterion) {
     // c.bench("throughput", bencher);
 }
 
+/// same number of words as `bench_8digits_tp` with a different charset on the last position,
+/// to compare masks with the same charset on every position to mixed ones
+fn bench_8mixed_tp(c: &mut Criterion) {
+    let mut group = c.benchmark_group("8mixed_tp");
+    let n_elements = 100_000_000;
+    let item_len = 9;
+    group
+        .bench_function("8mixed_tp", |b| {
+            b.iter(|| run_bench(vec!["-c", "abcdefghij", "?d?d?d?d?d?d?d?1"]))
+        })
+        .throughput(Throughput::Bytes(n_elements * item_len))
+        .sample_size(10)
+        .warm_up_time(Duration::new(1, 0));
+    group.finish();
+}
+
 fn bench_6lower_tp(c: &mut Criterion) {
     let mut group = c.benchmark_group("6lower_tp");
     let n_elements = 308_915_776; // 26 ** 6
@@ -96,6 +112,36 @@ fn bench_wordlists_charset_tp(c: &mut Criterion) {
     group.finish();
 }
 
+/// many masks using the same large wordlist, loaded once for all of them
+fn bench_masks_same_wordlist(c: &mut Criterion) {
+    c.bench_function("masks-same-wordlist", |b| {
+        b.iter(|| {
+            let vocab = wordlist_fname("vocab.txt");
+            let mut args = vec!["-w", vocab.as_str()];
+            args.extend(["?w1?d", "?d?w1", "?w1?s", "?s?w1", "?w1?d?d", "?u?w1"]);
+            run_bench(args)
+        })
+    });
+}
+
+/// a variable length wordlist repeated on 10 positions, each shifting the suffix of the word
+fn bench_wordlist_repeated_tp(c: &mut Criterion) {
+    let mut group = c.benchmark_group("wordlist_repeated_tp");
+    // 3 ** 10 words of 10 words of lengths 5, 4 and 5 and a newline
+    let bytes_size = 10 * 3u64.pow(9) * 14 + 3u64.pow(10);
+    group
+        .bench_function("wordlist_repeated_tp", |b| {
+            b.iter(|| {
+                let w1 = wordlist_fname("wordlist-3words.txt");
+                run_bench(vec!["-w", w1.as_str(), &"?w1".repeat(10)])
+            })
+        })
+        .throughput(Throughput::Bytes(bytes_size))
+        .sample_size(10)
+        .warm_up_time(Duration::new(1, 0));
+    group.finish();
+}
+
 fn wordlist_fname(fname: &str) -> String {
     let mut d = path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     d.extend(vec!["test-resources", fname]);
@@ -113,12 +159,15 @@ criterion_group!(
     bench_5digits,
     bench_4mixed,
     bench_wordlist_simple,
-    bench_wordlist_and_custom_charset
+    bench_wordlist_and_custom_charset,
+    bench_masks_same_wordlist
 );
 criterion_group!(
     benches_throughput,
     bench_8digits_tp,
+    bench_8mixed_tp,
     bench_6lower_tp,
-    bench_wordlists_charset_tp
+    bench_wordlists_charset_tp,
+    bench_wordlist_repeated_tp
 );
 criterion_main!(benches, benches_throughput);
@@ -1,4 +1,9 @@
+use std::io::Read;
 use std::ops::Index;
+use std::path::Path;
+
+use crate::helpers::open_input;
+use crate::BoxResult;
 
 pub struct CharsetSymbol<'a> {
     pub(crate) symbol: char,
@@ -6,7 +11,7 @@ pub struct CharsetSymbol<'a> {
 }
 
 impl<'a> CharsetSymbol<'a> {
-    pub const fn new(symbol: char, chars: &'a [u8]) -> CharsetSymbol {
+    pub const fn new(symbol: char, chars: &'a [u8]) -> CharsetSymbol<'a> {
         CharsetSymbol { symbol, chars }
     }
 }
@@ -71,11 +76,112 @@ pub const SYMBOL2CHARSET: [CharsetSymbol; 6] = [
     ),
 ];
 
+/// expands a custom charset definition into its chars (like hashcat's `-1`):
+/// * `?d`, `?l`, `?u`, `?s`, `?a`, `?b` - expanded to the chars of the builtin charset
+/// * `??` or `\?` - a literal `?`
+/// * `\\` - a literal `\`
+/// * `\xHH` - a single byte by its hex value
+///
+/// any other char is taken literally, so a `?` not followed by a builtin charset symbol
+/// (e.g. `?!` or a trailing `?`) stays a literal `?`. duplicate chars are removed keeping the
+/// first occurrence
+pub fn expand_custom_charset(spec: &[u8]) -> Vec<u8> {
+    let mut chars = expand_custom_charset_with_duplicates(spec);
+
+    // remove duplicates keeping the order
+    let mut seen = [false; 256];
+    chars.retain(|&ch| !std::mem::replace(&mut seen[ch as usize], true));
+    chars
+}
+
+/// returns the number of duplicate chars removed when expanding a custom charset definition
+/// (e.g. 2 for `?dab1a`)
+pub fn custom_charset_duplicates(spec: &[u8]) -> usize {
+    expand_custom_charset_with_duplicates(spec).len() - expand_custom_charset(spec).len()
+}
+
+fn expand_custom_charset_with_duplicates(spec: &[u8]) -> Vec<u8> {
+    let mut chars = Vec::with_capacity(spec.len());
+    let mut i = 0;
+    while i < spec.len() {
+        if let Some(byte) = parse_hex_escape(&spec[i..]) {
+            chars.push(byte);
+            i += 4;
+            continue;
+        }
+
+        match (spec[i], spec.get(i + 1)) {
+            (b'?', Some(b'?')) | (b'\\', Some(b'?')) | (b'\\', Some(b'\\')) => {
+                chars.push(spec[i + 1]);
+                i += 2;
+            }
+            (b'?', Some(&symbol)) if find_symbol(symbol as char).is_some() => {
+                chars.extend_from_slice(find_symbol(symbol as char).unwrap().chars);
+                i += 2;
+            }
+            (ch, _) => {
+                chars.push(ch);
+                i += 1;
+            }
+        }
+    }
+    chars
+}
+
+/// parses a `\xHH` escape at the start of `spec`
+fn parse_hex_escape(spec: &[u8]) -> Option<u8> {
+    if !spec.starts_with(b"\\x") {
+        return None;
+    }
+    let hex = spec.get(2..4)?;
+    if !hex.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()
+}
+
+/// reads a charset file (e.g. hashcat's .hcchr) as a custom charset definition.
+/// non ascii bytes and backslashes are hex escaped so the raw file bytes are preserved
+pub fn read_charset_file<P: AsRef<Path>>(fname: P) -> BoxResult<String> {
+    let mut data = vec![];
+    open_input(fname)?.read_to_end(&mut data)?;
+    if data.last() == Some(&b'\n') {
+        data.pop();
+        if data.last() == Some(&b'\r') {
+            data.pop();
+        }
+    }
+
+    let mut spec = String::with_capacity(data.len());
+    for ch in data {
+        if ch.is_ascii() && ch != b'\\' {
+            spec.push(ch as char);
+        } else {
+            spec.push_str(&format!("\\x{:02x}", ch));
+        }
+    }
+    Ok(spec)
+}
+
+/// returns the builtin charset of `symbol` (e.g. 'd' for digits)
+pub fn find_symbol(symbol: char) -> Option<&'static CharsetSymbol<'static>> {
+    SYMBOL2CHARSET
+        .iter()
+        .find(|charset| charset.symbol == symbol)
+}
+
 #[repr(align(64))]
 pub struct Charset {
+    /// maps each char of the charset to the next one, the last char jumps back to `min_char`.
+    /// a jump to a smaller or equal char marks the wraparound. entries of chars not in the
+    /// charset are 0 and meaningless - 0 may also be a valid jump (e.g. 255 -> 0 on ?b), use
+    /// `contains` for membership
     pub(crate) jmp_table: [u8; 256],
     pub(crate) min_char: u8,
     pub(crate) len: usize,
+    /// membership bitset, as the jmp_table cannot tell apart a char jumping to 0 from a char
+    /// not in the charset
+    members: [u64; 4],
 }
 
 impl Index<usize> for Charset {
@@ -94,22 +200,241 @@ impl Charset {
         // ensure chars are sorted so jmp_table works correctly
         let mut chars = chars.to_owned();
         chars.sort_unstable();
+        let mut members = [0u64; 4];
         for i in 0..chars.len() {
             jmp_table[chars[i] as usize] = chars[(i + 1) % chars.len()];
+            members[chars[i] as usize / 64] |= 1 << (chars[i] % 64);
         }
         Charset {
             jmp_table,
             min_char: chars[0],
             len: chars.len(),
+            members,
         }
     }
 
+    /// creates a charset from a custom charset definition, see `expand_custom_charset`
+    pub fn from_custom(spec: &str) -> Charset {
+        Charset::from_chars(&expand_custom_charset(spec.as_bytes()))
+    }
+
     pub fn from_symbol(symbol: char) -> Charset {
-        for charset in &SYMBOL2CHARSET {
-            if charset.symbol == symbol {
-                return Charset::from_chars(charset.chars);
+        match find_symbol(symbol) {
+            Some(charset) => Charset::from_chars(charset.chars),
+            None => panic!("unknown mask symbol - {}", symbol),
+        }
+    }
+
+    /// creates a builtin charset without the chars of `except`, e.g. consonants for ('l', "aeiou")
+    pub fn from_symbol_except(symbol: char, except: &str) -> Charset {
+        match find_symbol(symbol) {
+            Some(charset) => {
+                let chars: Vec<u8> = charset
+                    .chars
+                    .iter()
+                    .filter(|&&ch| !except.as_bytes().contains(&ch))
+                    .copied()
+                    .collect();
+                Charset::from_chars(&chars)
+            }
+            None => panic!("unknown mask symbol - {}", symbol),
+        }
+    }
+
+    /// returns the chars of `alphabet` not in this charset, or `None` if it has all of them
+    pub fn complement(&self, alphabet: &Charset) -> Option<Charset> {
+        let chars: Vec<u8> = alphabet.iter().filter(|&ch| !self.contains(ch)).collect();
+        (!chars.is_empty()).then(|| Charset::from_chars(&chars))
+    }
+
+    /// iterates the chars of the charset once, in generation order (ascending)
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        std::iter::successors(Some(self.min_char), move |&chr| {
+            let next_chr = self[chr as usize];
+            // the jmp table wraps around to min_char after the last char
+            if chr < next_chr {
+                Some(next_chr)
+            } else {
+                None
             }
+        })
+    }
+
+    /// returns the chars of the charset sorted ascending
+    pub fn chars_sorted(&self) -> Vec<u8> {
+        self.iter().collect()
+    }
+
+    /// returns true if `chr` is one of the chars of the charset
+    #[inline]
+    pub fn contains(&self, chr: u8) -> bool {
+        self.members[chr as usize / 64] & (1 << (chr % 64)) != 0
+    }
+
+    /// returns the number of chars in the charset
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// returns the first char of the charset in generation order
+    #[inline]
+    pub fn min_char(&self) -> u8 {
+        self.min_char
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{custom_charset_duplicates, expand_custom_charset, read_charset_file, Charset};
+    use crate::test_util::wordlist_fname;
+
+    #[test]
+    fn test_charset_iter() {
+        let charset = Charset::from_chars(b"dbca");
+        assert_eq!(charset.iter().collect::<Vec<_>>(), b"abcd");
+        assert_eq!(charset.chars_sorted(), b"abcd");
+        assert_eq!(charset.len(), 4);
+        assert_eq!(charset.min_char(), b'a');
+
+        let charset = Charset::from_chars(b"x");
+        assert_eq!(charset.chars_sorted(), b"x");
+    }
+
+    #[test]
+    fn test_charset_contains() {
+        let charset = Charset::from_chars(b"\x00\xffa");
+        assert!(charset.contains(0));
+        assert!(charset.contains(255));
+        assert!(charset.contains(b'a'));
+        assert!(!charset.contains(1));
+        assert!(!charset.contains(b'b'));
+
+        let charset = Charset::from_chars(b"xy");
+        assert!(!charset.contains(0));
+        assert!(charset.contains(b'x'));
+
+        let bytes = Charset::from_symbol('b');
+        assert!((0..=255).all(|chr| bytes.contains(chr)));
+        let digits = Charset::from_symbol('d');
+        assert_eq!((0..=255).filter(|&chr| digits.contains(chr)).count(), 10);
+    }
+
+    #[test]
+    fn test_charset_complement() {
+        let alphabet = Charset::from_custom("?l?d");
+        let complement = Charset::from_symbol('d').complement(&alphabet).unwrap();
+        assert_eq!(complement.chars_sorted(), b"abcdefghijklmnopqrstuvwxyz");
+
+        // chars outside of the alphabet are ignored
+        let complement = Charset::from_chars(b"aA0").complement(&alphabet).unwrap();
+        assert_eq!(complement.len(), 34);
+        assert!(!complement.contains(b'a') && !complement.contains(b'0'));
+        assert_eq!(complement.min_char(), b'1');
+
+        assert!(Charset::from_symbol('b').complement(&alphabet).is_none());
+        let complement = Charset::from_chars(b"\x00").complement(&Charset::from_symbol('b'));
+        assert_eq!(complement.unwrap().min_char(), 1);
+    }
+
+    #[test]
+    fn test_charset_jmp_table_byte_zero() {
+        let bytes = Charset::from_symbol('b');
+        assert_eq!(bytes.min_char(), 0);
+        assert_eq!(bytes[0], 1);
+        assert_eq!(bytes[254], 255);
+        // wraparound from the last char back to 0
+        assert_eq!(bytes[255], 0);
+        assert_eq!(
+            bytes.iter().collect::<Vec<_>>(),
+            (0..=255).collect::<Vec<u8>>()
+        );
+
+        // a single char charset of 0 wraps to itself
+        let zero = Charset::from_chars(b"\x00");
+        assert_eq!(zero[0], 0);
+        assert_eq!(zero.iter().collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn test_charset_iter_symbols() {
+        let digits = Charset::from_symbol('d');
+        assert_eq!(digits.chars_sorted(), b"0123456789");
+
+        let binary = Charset::from_symbol('b');
+        let chars = binary.chars_sorted();
+        assert_eq!(chars.len(), 256);
+        assert_eq!(chars, (0..=255).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_expand_custom_charset() {
+        let cases: Vec<(&[u8], &[u8])> = vec![
+            (b"abc", b"abc"),
+            (b"?d", b"0123456789"),
+            (b"?dabc", b"0123456789abc"),
+            (b"?l?d?l", b"abcdefghijklmnopqrstuvwxyz0123456789"),
+            (b"xy??", b"xy?"),
+            (b"?!?", b"?!"),
+            (b"\\x00\\xff\\x4", b"\x00\xff\\x4"),
+            (b"aab", b"ab"),
+        ];
+        for (spec, expected) in cases {
+            assert_eq!(expand_custom_charset(spec), expected);
         }
-        panic!("unknown mask symbol - {}", symbol);
+    }
+
+    #[test]
+    fn test_custom_charset_duplicates() {
+        assert_eq!(custom_charset_duplicates(b"abc"), 0);
+        assert_eq!(custom_charset_duplicates(b"aab"), 1);
+        assert_eq!(custom_charset_duplicates(b"?dab1a"), 2);
+        assert_eq!(custom_charset_duplicates(b"?l?d?l"), 26);
+        assert_eq!(custom_charset_duplicates(b"\\x61a"), 1);
+    }
+
+    #[test]
+    fn test_expand_custom_charset_literals() {
+        let cases: Vec<(&[u8], &[u8])> = vec![
+            (b"\\?d", b"?d"),
+            (b"\\\\?d", b"\\0123456789"),
+            (b"?", b"?"),
+            (b"ab?", b"ab?"),
+            (b"?x?1", b"?x1"),
+            (b"\\?\\?", b"?"),
+            (b"\\a", b"\\a"),
+            (b"?d\\", b"0123456789\\"),
+        ];
+        for (spec, expected) in cases {
+            assert_eq!(expand_custom_charset(spec), expected);
+        }
+        assert_eq!(expand_custom_charset(b"?b").len(), 256);
+        assert_eq!(Charset::from_custom("?d?u").len(), 36);
+    }
+
+    #[test]
+    fn test_charset_from_symbol_except() {
+        let charset = Charset::from_symbol_except('d', "0");
+        assert_eq!(charset.chars_sorted(), b"123456789");
+        assert!(!charset.contains(b'0'));
+
+        let charset = Charset::from_symbol_except('l', "aeiou");
+        assert_eq!(charset.len(), 21);
+        assert_eq!(charset.min_char(), b'b');
+    }
+
+    #[test]
+    fn test_read_charset_file() {
+        let spec = read_charset_file(wordlist_fname("charset.hcchr")).unwrap();
+        assert_eq!(spec, "?dab\\x5c\\xe4");
+        assert_eq!(
+            expand_custom_charset(spec.as_bytes()),
+            b"0123456789ab\\\xe4"
+        );
     }
 }
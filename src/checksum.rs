@@ -0,0 +1,255 @@
+use std::cell::RefCell;
+use std::io::{Error, Write};
+use std::rc::Rc;
+
+use sha2::{Digest, Sha256};
+
+const PRIME64_1: u64 = 0x9e37_79b1_85eb_ca87;
+const PRIME64_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const PRIME64_3: u64 = 0x1656_67b1_9e37_79f9;
+const PRIME64_4: u64 = 0x85eb_ca77_c2b2_ae63;
+const PRIME64_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+/// a streaming xxHash64 with seed 0 - a fast non cryptographic hash
+#[derive(Debug, Clone)]
+pub struct Xxh64 {
+    accs: [u64; 4],
+    /// bytes not yet consumed as a full 32 bytes stripe
+    pending: Vec<u8>,
+    total_len: u64,
+}
+
+impl Default for Xxh64 {
+    fn default() -> Self {
+        Xxh64 {
+            accs: [
+                PRIME64_1.wrapping_add(PRIME64_2),
+                PRIME64_2,
+                0,
+                0u64.wrapping_sub(PRIME64_1),
+            ],
+            pending: Vec::with_capacity(32),
+            total_len: 0,
+        }
+    }
+}
+
+#[inline]
+fn xxh64_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(PRIME64_2))
+        .rotate_left(31)
+        .wrapping_mul(PRIME64_1)
+}
+
+#[inline]
+fn read_u64(data: &[u8]) -> u64 {
+    u64::from_le_bytes(data[..8].try_into().unwrap())
+}
+
+impl Xxh64 {
+    pub fn new() -> Xxh64 {
+        Xxh64::default()
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+        if !self.pending.is_empty() {
+            let n = data.len().min(32 - self.pending.len());
+            self.pending.extend_from_slice(&data[..n]);
+            data = &data[n..];
+            if self.pending.len() < 32 {
+                return;
+            }
+            let stripe = std::mem::take(&mut self.pending);
+            self.consume_stripe(&stripe);
+            self.pending = stripe;
+            self.pending.clear();
+        }
+        let mut stripes = data.chunks_exact(32);
+        for stripe in &mut stripes {
+            self.consume_stripe(stripe);
+        }
+        self.pending.extend_from_slice(stripes.remainder());
+    }
+
+    #[inline]
+    fn consume_stripe(&mut self, stripe: &[u8]) {
+        for (i, acc) in self.accs.iter_mut().enumerate() {
+            *acc = xxh64_round(*acc, read_u64(&stripe[i * 8..]));
+        }
+    }
+
+    pub fn digest(&self) -> u64 {
+        let mut hash = if self.total_len >= 32 {
+            let [v1, v2, v3, v4] = self.accs;
+            let mut hash = v1
+                .rotate_left(1)
+                .wrapping_add(v2.rotate_left(7))
+                .wrapping_add(v3.rotate_left(12))
+                .wrapping_add(v4.rotate_left(18));
+            for acc in self.accs {
+                hash = (hash ^ xxh64_round(0, acc))
+                    .wrapping_mul(PRIME64_1)
+                    .wrapping_add(PRIME64_4);
+            }
+            hash
+        } else {
+            PRIME64_5
+        };
+        hash = hash.wrapping_add(self.total_len);
+
+        let mut tail = &self.pending[..];
+        while tail.len() >= 8 {
+            hash ^= xxh64_round(0, read_u64(tail));
+            hash = hash
+                .rotate_left(27)
+                .wrapping_mul(PRIME64_1)
+                .wrapping_add(PRIME64_4);
+            tail = &tail[8..];
+        }
+        if tail.len() >= 4 {
+            let n = u32::from_le_bytes(tail[..4].try_into().unwrap()) as u64;
+            hash ^= n.wrapping_mul(PRIME64_1);
+            hash = hash
+                .rotate_left(23)
+                .wrapping_mul(PRIME64_2)
+                .wrapping_add(PRIME64_3);
+            tail = &tail[4..];
+        }
+        for &ch in tail {
+            hash ^= (ch as u64).wrapping_mul(PRIME64_5);
+            hash = hash.rotate_left(11).wrapping_mul(PRIME64_1);
+        }
+
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(PRIME64_2);
+        hash ^= hash >> 29;
+        hash = hash.wrapping_mul(PRIME64_3);
+        hash ^ (hash >> 32)
+    }
+}
+
+/// a running checksum of a stream of bytes
+#[derive(Debug, Clone)]
+pub enum Checksum {
+    Sha256(Sha256),
+    XxHash64(Xxh64),
+}
+
+impl Checksum {
+    pub fn sha256() -> Checksum {
+        Checksum::Sha256(Sha256::new())
+    }
+
+    pub fn xxhash64() -> Checksum {
+        Checksum::XxHash64(Xxh64::new())
+    }
+
+    /// the name of the checksum algorithm
+    pub fn name(&self) -> &'static str {
+        match self {
+            Checksum::Sha256(_) => "sha256",
+            Checksum::XxHash64(_) => "xxhash64",
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Checksum::Sha256(hasher) => hasher.update(data),
+            Checksum::XxHash64(hasher) => hasher.update(data),
+        }
+    }
+
+    /// returns the hex digest of the bytes so far
+    pub fn hex_digest(&self) -> String {
+        match self {
+            Checksum::Sha256(hasher) => hasher
+                .clone()
+                .finalize()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+            Checksum::XxHash64(hasher) => format!("{:016x}", hasher.digest()),
+        }
+    }
+}
+
+/// a writer passing its input to the inner writer, adding the written bytes to a checksum
+/// shared with the caller
+pub struct ChecksumWriter<W> {
+    inner: W,
+    checksum: Rc<RefCell<Checksum>>,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    pub fn new(inner: W, checksum: Rc<RefCell<Checksum>>) -> ChecksumWriter<W> {
+        ChecksumWriter { inner, checksum }
+    }
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let n = self.inner.write(buf)?;
+        self.checksum.borrow_mut().update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::io::Write;
+    use std::rc::Rc;
+
+    use super::{Checksum, ChecksumWriter, Xxh64};
+
+    fn xxh64(data: &[u8]) -> u64 {
+        let mut hasher = Xxh64::new();
+        hasher.update(data);
+        hasher.digest()
+    }
+
+    #[test]
+    fn test_xxh64() {
+        assert_eq!(xxh64(b""), 0xef46_db37_51d8_e999);
+        assert_eq!(xxh64(b"a"), 0xd24e_c4f1_a98c_6e5b);
+        assert_eq!(xxh64(b"abc"), 0x44bc_2cf5_ad77_0999);
+        assert_eq!(
+            xxh64(b"Nobody inspects the spammish repetition"),
+            0xfbce_a83c_8a37_8bf1
+        );
+
+        // the same digest however the data is split
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let expected = xxh64(&data);
+        for chunk_size in [1, 3, 31, 32, 33, 100] {
+            let mut hasher = Xxh64::new();
+            for chunk in data.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.digest(), expected, "chunks of {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn test_checksum_writer() {
+        let checksum = Rc::new(RefCell::new(Checksum::sha256()));
+        let mut writer = ChecksumWriter::new(vec![], checksum.clone());
+        writer.write_all(b"alpha\nbe").unwrap();
+        writer.write_all(b"ta\ngamma\n").unwrap();
+        assert_eq!(writer.inner, b"alpha\nbeta\ngamma\n");
+        assert_eq!(checksum.borrow().name(), "sha256");
+        assert_eq!(
+            checksum.borrow().hex_digest(),
+            "4fdbc441ea7b546100e086ac1e4fc5ae6749b7314311c99db05be450eca12996"
+        );
+
+        let mut checksum = Checksum::xxhash64();
+        checksum.update(b"abc");
+        assert_eq!(checksum.hex_digest(), "44bc2cf5ad770999");
+    }
+}
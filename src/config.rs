@@ -0,0 +1,190 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml::Value;
+
+use crate::BoxResult;
+
+/// environment variable with the path of the config file
+pub const CONFIG_ENV_VAR: &str = "CRACKEN_CONFIG";
+/// config file looked up in the working directory when `CONFIG_ENV_VAR` is not set
+pub const CONFIG_FILENAME: &str = "cracken.toml";
+
+/// defaults of the generate subcommand read from the `[generate]` table of a toml config file.
+/// keys are named after the command line flags, which take precedence over them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GenerateConfig {
+    pub custom_charsets: Vec<String>,
+    pub wordlists: Vec<String>,
+    pub minlen: Option<usize>,
+    pub maxlen: Option<usize>,
+}
+
+/// returns the path of the config file - `env_path`, the value of `$CRACKEN_CONFIG`, or
+/// `cracken.toml` of `dir` if it exists
+pub fn find_config_file(env_path: Option<OsString>, dir: &Path) -> Option<PathBuf> {
+    match env_path {
+        Some(path) => Some(PathBuf::from(path)),
+        None => Some(dir.join(CONFIG_FILENAME)).filter(|path| path.is_file()),
+    }
+}
+
+fn strings_value(key: &str, value: &Value) -> BoxResult<Vec<String>> {
+    match value.as_array() {
+        Some(values) => values
+            .iter()
+            .map(|v| match v.as_str() {
+                Some(s) => Ok(s.to_owned()),
+                None => bail!("config key {} must be a list of strings", key),
+            })
+            .collect(),
+        None => bail!("config key {} must be a list of strings", key),
+    }
+}
+
+fn usize_value(key: &str, value: &Value) -> BoxResult<usize> {
+    match value.as_integer() {
+        Some(n) if n > 0 => Ok(n as usize),
+        _ => bail!("config key {} must be a positive integer", key),
+    }
+}
+
+impl GenerateConfig {
+    pub fn from_file<P: AsRef<Path>>(fname: P) -> BoxResult<GenerateConfig> {
+        let fname = fname.as_ref();
+        let data = match fs::read_to_string(fname) {
+            Ok(data) => data,
+            Err(e) => bail!("cannot read config file {}: {}", fname.display(), e),
+        };
+        Self::from_toml(&data)
+    }
+
+    pub fn from_toml(data: &str) -> BoxResult<GenerateConfig> {
+        let root: Value = data.parse()?;
+        let mut config = GenerateConfig::default();
+        for (section, table) in root.as_table().unwrap() {
+            if section != "generate" {
+                bail!("unknown config section [{}]", section);
+            }
+            let table = match table.as_table() {
+                Some(table) => table,
+                None => bail!("config section [{}] must be a table", section),
+            };
+            for (key, value) in table {
+                match key.as_str() {
+                    "custom-charset" => config.custom_charsets = strings_value(key, value)?,
+                    "wordlist" => config.wordlists = strings_value(key, value)?,
+                    "minlen" => config.minlen = Some(usize_value(key, value)?),
+                    "maxlen" => config.maxlen = Some(usize_value(key, value)?),
+                    _ => bail!("unknown config key {} in [generate]", key),
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// returns the flags of the config values whose generate arg is not given on the command
+    /// line, by the arg name
+    pub fn missing_flags<F: Fn(&str) -> bool>(&self, is_given: F) -> Vec<String> {
+        let mut flags = vec![];
+        let mut add_flags = |name: &str, long: &str, values: Vec<String>| {
+            if !values.is_empty() && !is_given(name) {
+                for value in values {
+                    flags.push(long.to_owned());
+                    flags.push(value);
+                }
+            }
+        };
+
+        // custom charsets are numbered by their order, so they are overridden all together
+        add_flags(
+            "custom-charset",
+            "--custom-charset",
+            self.custom_charsets.clone(),
+        );
+        add_flags("wordlist", "--wordlist", self.wordlists.clone());
+        add_flags(
+            "min-length",
+            "--minlen",
+            self.minlen.iter().map(usize::to_string).collect(),
+        );
+        add_flags(
+            "max-length",
+            "--maxlen",
+            self.maxlen.iter().map(usize::to_string).collect(),
+        );
+        flags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+
+    use crate::test_util::wordlist_fname;
+
+    use super::{find_config_file, GenerateConfig};
+
+    #[test]
+    fn test_generate_config_from_file() {
+        let config = GenerateConfig::from_file(wordlist_fname("cracken.toml")).unwrap();
+        assert_eq!(
+            config,
+            GenerateConfig {
+                custom_charsets: vec!["abc".to_owned(), "?d".to_owned()],
+                wordlists: vec!["wordlist1.txt".to_owned()],
+                minlen: Some(2),
+                maxlen: Some(3),
+            }
+        );
+
+        assert!(GenerateConfig::from_file(wordlist_fname("cracken-invalid.toml")).is_err());
+        assert!(GenerateConfig::from_file(wordlist_fname("no-such-config.toml")).is_err());
+        assert!(GenerateConfig::from_toml("[generate]\nmask = '?d'").is_err());
+        assert!(GenerateConfig::from_toml("[create]\nminlen = 1").is_err());
+        assert!(GenerateConfig::from_toml("[generate]\nwordlist = 'a.txt'").is_err());
+        assert_eq!(
+            GenerateConfig::from_toml("").unwrap(),
+            GenerateConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_find_config_file() {
+        let dir = wordlist_fname("");
+        assert_eq!(
+            find_config_file(None, &dir),
+            Some(wordlist_fname("cracken.toml"))
+        );
+        assert_eq!(find_config_file(None, &dir.join("wordlist-dir")), None);
+        assert_eq!(
+            find_config_file(Some(OsString::from("other.toml")), &dir),
+            Some(PathBuf::from("other.toml"))
+        );
+    }
+
+    #[test]
+    fn test_generate_config_missing_flags() {
+        let config =
+            GenerateConfig::from_toml("[generate]\ncustom-charset = ['ab', 'cd']\nmaxlen = 4")
+                .unwrap();
+        assert_eq!(
+            config.missing_flags(|_| false),
+            vec![
+                "--custom-charset",
+                "ab",
+                "--custom-charset",
+                "cd",
+                "--maxlen",
+                "4"
+            ]
+        );
+        assert_eq!(config.missing_flags(|_| true), Vec::<String>::new());
+        assert_eq!(
+            config.missing_flags(|name| name == "custom-charset"),
+            vec!["--maxlen", "4"]
+        );
+    }
+}
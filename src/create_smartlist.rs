@@ -1,12 +1,13 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::fs::File;
 use std::hash::Hash;
 use std::io::{BufRead, BufReader};
-use std::iter::FromIterator;
 use std::path::Path;
 
 use aho_corasick::AhoCorasick;
+use indicatif::{ProgressBar, ProgressStyle};
 use itertools::Itertools;
+use regex::Regex;
 use tokenizers::decoders::byte_level::ByteLevel;
 use tokenizers::models::bpe::{BpeTrainerBuilder, BPE};
 use tokenizers::models::unigram::{Unigram, UnigramTrainer};
@@ -18,6 +19,7 @@ use tokenizers::{
     Trainer,
 };
 
+use crate::helpers::{decompress, open_input};
 use crate::BoxResult;
 
 pub const DEFAULT_VOCAB_SIZE: u32 = 50000;
@@ -30,26 +32,47 @@ pub enum SmartlistTokenizer {
     WordPiece,
 }
 
+/// the order of words in the output smartlist
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum SmartlistSortOrder {
+    /// most frequent words first
+    Frequency,
+    Alpha,
+    /// shortest words first, then alphabetically
+    Length,
+}
+
 pub struct SmartlistBuilder<P: AsRef<Path>> {
     infiles: Vec<P>,
-    tokenizers: HashSet<SmartlistTokenizer>,
+    base_vocab: Vec<String>,
+    /// the tokenizers to train, in their run order
+    tokenizers: Vec<SmartlistTokenizer>,
     vocab_max_size: u32,
     min_frequency: u32,
     print_progress: bool,
     numbers_max_size: Option<u32>,
     min_word_len: u32,
+    keep_matching: Option<Regex>,
+    reject: Option<Regex>,
+    casefold_dedup: bool,
+    sort_order: SmartlistSortOrder,
 }
 
 impl<P: AsRef<Path> + Sync> Default for SmartlistBuilder<P> {
     fn default() -> Self {
         SmartlistBuilder {
             infiles: vec![],
-            tokenizers: HashSet::from_iter([SmartlistTokenizer::Unigram]),
+            base_vocab: vec![],
+            tokenizers: vec![SmartlistTokenizer::Unigram],
             vocab_max_size: DEFAULT_VOCAB_SIZE,
             min_frequency: DEFAULT_MIN_FREQUENCY,
             print_progress: true,
             numbers_max_size: None,
             min_word_len: 1,
+            keep_matching: None,
+            reject: None,
+            casefold_dedup: false,
+            sort_order: SmartlistSortOrder::Frequency,
         }
     }
 }
@@ -62,6 +85,12 @@ impl<P: AsRef<Path> + Sync> SmartlistBuilder<P> {
         self.infiles = infiles;
         self
     }
+    /// words of an existing smartlist to merge with the newly trained vocab. all words are
+    /// re-ranked by their frequency in the input files
+    pub fn base_vocab(mut self, base_vocab: Vec<String>) -> Self {
+        self.base_vocab = base_vocab;
+        self
+    }
     pub fn vocab_max_size(mut self, vocab_max_size: u32) -> Self {
         self.vocab_max_size = vocab_max_size;
         self
@@ -70,8 +99,15 @@ impl<P: AsRef<Path> + Sync> SmartlistBuilder<P> {
         self.min_frequency = min_frequency;
         self
     }
+    /// the tokenizers to train, run in the given order. a tokenizer given more than once is
+    /// trained once, at its first position
     pub fn tokenizers(mut self, tokenizers: impl Iterator<Item = SmartlistTokenizer>) -> Self {
-        self.tokenizers = HashSet::from_iter(tokenizers);
+        self.tokenizers.clear();
+        for tokenizer in tokenizers {
+            if !self.tokenizers.contains(&tokenizer) {
+                self.tokenizers.push(tokenizer);
+            }
+        }
         self
     }
     pub fn print_progress(mut self, print_progress: bool) -> Self {
@@ -86,13 +122,38 @@ impl<P: AsRef<Path> + Sync> SmartlistBuilder<P> {
         self.min_word_len = min_word_len;
         self
     }
+    /// keeps only words fully matching the regex (e.g. `[[:alpha:]]+`)
+    pub fn keep_matching(mut self, keep_matching: Option<Regex>) -> Self {
+        self.keep_matching = keep_matching;
+        self
+    }
+    /// removes words matching the regex anywhere (e.g. `^[[:punct:]]+$` for punctuation only
+    /// words)
+    pub fn reject(mut self, reject: Option<Regex>) -> Self {
+        self.reject = reject;
+        self
+    }
+    /// dedups words ignoring case, keeping the most frequent casing (lowercase on ties)
+    pub fn casefold_dedup(mut self, casefold_dedup: bool) -> Self {
+        self.casefold_dedup = casefold_dedup;
+        self
+    }
+    pub fn sort_order(mut self, sort_order: SmartlistSortOrder) -> Self {
+        self.sort_order = sort_order;
+        self
+    }
 
     pub fn build(&self) -> BoxResult<Vec<String>> {
-        let mut vocab = HashSet::with_capacity(self.vocab_max_size as usize);
-        let mut tokenizers_types = self.tokenizers.iter().collect::<Vec<_>>();
-        tokenizers_types.sort_unstable();
-
-        for tokenizer_type in tokenizers_types {
+        let mut vocab = Vec::with_capacity(self.vocab_max_size as usize);
+        let tokenizers_count = self.tokenizers.len();
+        for (i, tokenizer_type) in self.tokenizers.iter().enumerate() {
+            // the tokenizers draw their own training progress bar
+            self.print_stage(&format!(
+                "training {:?} ({}/{})",
+                tokenizer_type,
+                i + 1,
+                tokenizers_count
+            ));
             let v = match tokenizer_type {
                 SmartlistTokenizer::Unigram => self.train_unigram(),
                 SmartlistTokenizer::BPE => self.train_bpe(),
@@ -108,22 +169,47 @@ impl<P: AsRef<Path> + Sync> SmartlistBuilder<P> {
             }
         }
 
-        // dedup words
-        let vocab = vocab.into_iter().unique().collect::<Vec<_>>();
+        // merge with the existing smartlist words
+        vocab.extend(self.base_vocab.iter().cloned());
+
+        // dedup words - sorted so the output does not depend on the tokenizers vocab order
+        vocab.sort_unstable();
+        vocab.dedup();
 
         // sort by frequency of words in original input files
         let mut vocab = self.sort_vocab(vocab)?;
 
         // apply filters
+        let pb = self.progress_bar(None, "filtering");
+        let words_count = vocab.len();
         if self.min_word_len > 0 {
             vocab = remove_shorter_than_len(vocab, self.min_word_len as usize);
         }
         if let Some(numbers_max_size) = self.numbers_max_size {
             vocab = remove_long_numbers(vocab, numbers_max_size as usize);
         }
+        if let Some(re) = &self.keep_matching {
+            vocab = remove_not_matching(vocab, re);
+        }
+        if let Some(re) = &self.reject {
+            vocab = remove_matching(vocab, re);
+        }
+        pb.finish_with_message(&format!(
+            "filtering - kept {} of {} words",
+            vocab.len(),
+            words_count
+        ));
 
-        // truncate to desired maxsize (or less)
+        // truncate to desired maxsize (or less), keeping the most frequent words
         vocab.truncate(self.vocab_max_size as usize);
+
+        match self.sort_order {
+            SmartlistSortOrder::Frequency => {}
+            SmartlistSortOrder::Alpha => vocab.sort_unstable(),
+            SmartlistSortOrder::Length => {
+                vocab.sort_unstable_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+            }
+        }
         Ok(vocab)
     }
 
@@ -153,7 +239,6 @@ impl<P: AsRef<Path> + Sync> SmartlistBuilder<P> {
 
     fn train_wordpiece(&self) -> tokenizers::Result<Vec<String>> {
         let model = WordPiece::default();
-        let model = model;
         let mut tokenizer = self.build_tokenizer(model)?;
 
         let mut trainer = WordPieceTrainerBuilder::new()
@@ -206,39 +291,74 @@ impl<P: AsRef<Path> + Sync> SmartlistBuilder<P> {
         let files: Result<Vec<_>, _> = self
             .infiles
             .iter()
-            .map(|fname| File::open(fname).map(BufReader::new))
+            .map(|fname| open_input(fname).map(BufReader::new))
             .collect();
         let files = files?;
-        let input_data = files
-            .into_iter()
-            .map(|fp| {
-                fp.lines()
-                    .map(|line| line.unwrap_or_else(|_| "".to_string()))
-            })
-            .flatten();
+        let input_data = files.into_iter().flat_map(|fp| {
+            fp.lines()
+                .map(|line| line.unwrap_or_else(|_| "".to_string()))
+        });
 
         tokenizer.train(trainer, input_data)?;
-        let vocab = tokenizer.get_vocab(false).into_keys().collect::<Vec<_>>();
+        // the vocab is a hashmap - sort its keys to keep a deterministic order
+        let vocab = tokenizer
+            .get_vocab(false)
+            .into_keys()
+            .sorted_unstable()
+            .collect::<Vec<_>>();
         Ok(vocab)
     }
 
+    /// prints the name of a build stage without a progress bar of its own
+    fn print_stage(&self, msg: &str) {
+        if self.print_progress {
+            eprintln!("{}", msg);
+        }
+    }
+
+    /// returns a progress bar of a build stage on stderr, counting bytes when `len` is known.
+    /// hidden when progress printing is disabled
+    fn progress_bar(&self, len: Option<u64>, msg: &str) -> ProgressBar {
+        if !self.print_progress {
+            return ProgressBar::hidden();
+        }
+        let pb = match len {
+            Some(len) => {
+                ProgressBar::new(len).with_style(ProgressStyle::default_bar().template(
+                    "{msg} [{elapsed_precise}] {wide_bar} {bytes}/{total_bytes} eta {eta}",
+                ))
+            }
+            None => ProgressBar::new_spinner()
+                .with_style(ProgressStyle::default_spinner().template("{msg} [{elapsed_precise}]")),
+        };
+        pb.set_message(msg);
+        pb
+    }
+
     fn sort_vocab(&self, vocab: Vec<String>) -> BoxResult<Vec<String>> {
         let ac = AhoCorasick::new(vocab.to_vec());
         let mut word2count = vec![0i64; vocab.len()];
 
+        let mut total_bytes = 0;
+        for fname in self.infiles.iter() {
+            total_bytes += fname.as_ref().metadata()?.len();
+        }
+        let pb = self.progress_bar(Some(total_bytes), "counting frequencies");
         let files: Result<Vec<_>, _> = self
             .infiles
             .iter()
-            .map(|fname| File::open(fname).map(BufReader::new))
+            // the progress counts the compressed bytes read, as the total is the files sizes
+            .map(|fname| {
+                File::open(fname)
+                    .and_then(|fp| decompress(pb.wrap_read(fp)))
+                    .map(BufReader::new)
+            })
             .collect();
         let files = files?;
-        let input_data = files
-            .into_iter()
-            .map(|f| {
-                f.lines()
-                    .map(|line| line.unwrap_or_else(|_| "".to_string()))
-            })
-            .flatten();
+        let input_data = files.into_iter().flat_map(|f| {
+            f.lines()
+                .map(|line| line.unwrap_or_else(|_| "".to_string()))
+        });
 
         for line in input_data {
             for mat in ac.find_overlapping_iter(&line) {
@@ -246,16 +366,66 @@ impl<P: AsRef<Path> + Sync> SmartlistBuilder<P> {
                 word2count[word] += 1;
             }
         }
+        pb.finish();
+
+        let (vocab, word2count) = if self.casefold_dedup {
+            casefold_dedup(vocab, word2count)
+        } else {
+            (vocab, word2count)
+        };
 
         Ok(vocab
             .into_iter()
             .enumerate()
-            .sorted_by_key(|(idx, s)| (-(word2count[*idx] as i64), s.to_string()))
+            .sorted_by_key(|(idx, s)| (-word2count[*idx], s.to_string()))
             .map(|(_, s)| s)
             .collect::<Vec<_>>())
     }
 }
 
+/// merges words differing only by case, summing their counts. the representative of each
+/// group is its most frequent casing, or the lowercase word on ties
+fn casefold_dedup(vocab: Vec<String>, counts: Vec<i64>) -> (Vec<String>, Vec<i64>) {
+    let mut lower2idx: HashMap<String, usize> = HashMap::with_capacity(vocab.len());
+    // (representative, its own count, total count of the group)
+    let mut groups: Vec<(String, i64, i64)> = Vec::with_capacity(vocab.len());
+
+    for (word, count) in vocab.into_iter().zip(counts) {
+        let lower = word.to_lowercase();
+        match lower2idx.get(&lower) {
+            Some(&idx) => {
+                let group = &mut groups[idx];
+                group.2 += count;
+                if count > group.1 || (count == group.1 && word == lower) {
+                    group.0 = word;
+                    group.1 = count;
+                }
+            }
+            None => {
+                lower2idx.insert(lower, groups.len());
+                groups.push((word, count, count));
+            }
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(word, _, total)| (word, total))
+        .unzip()
+}
+
+/// reads the words of an existing smartlist file, skipping empty lines
+pub fn read_vocab_file<P: AsRef<Path>>(fname: P) -> BoxResult<Vec<String>> {
+    let fp = BufReader::new(open_input(fname)?);
+    let mut words = vec![];
+    for line in fp.lines() {
+        let line = line?;
+        if !line.is_empty() {
+            words.push(line);
+        }
+    }
+    Ok(words)
+}
+
 pub fn remove_long_numbers(vocab: Vec<String>, max_len: usize) -> Vec<String> {
     vocab
         .into_iter()
@@ -263,6 +433,22 @@ pub fn remove_long_numbers(vocab: Vec<String>, max_len: usize) -> Vec<String> {
         .collect()
 }
 
+/// keeps only words matching `re` from start to end
+pub fn remove_not_matching(vocab: Vec<String>, re: &Regex) -> Vec<String> {
+    vocab
+        .into_iter()
+        .filter(|s| {
+            re.find(s)
+                .is_some_and(|m| m.start() == 0 && m.end() == s.len())
+        })
+        .collect()
+}
+
+/// removes words matching `re` anywhere
+pub fn remove_matching(vocab: Vec<String>, re: &Regex) -> Vec<String> {
+    vocab.into_iter().filter(|s| !re.is_match(s)).collect()
+}
+
 pub fn remove_shorter_than_len(vocab: Vec<String>, min_word_len: usize) -> Vec<String> {
     vocab
         .into_iter()
@@ -272,8 +458,12 @@ pub fn remove_shorter_than_len(vocab: Vec<String>, min_word_len: usize) -> Vec<S
 
 #[cfg(test)]
 mod tests {
-    use crate::create_smartlist::{SmartlistBuilder, SmartlistTokenizer};
+    use crate::create_smartlist::{
+        casefold_dedup, read_vocab_file, remove_matching, remove_not_matching, SmartlistBuilder,
+        SmartlistSortOrder, SmartlistTokenizer,
+    };
     use crate::test_util;
+    use regex::Regex;
 
     #[test]
     fn test_build_vocab() {
@@ -305,4 +495,241 @@ mod tests {
         vocab.sort();
         assert_eq!(vocab, expected_vocab);
     }
+
+    #[test]
+    fn test_build_vocab_deterministic() {
+        let fname = test_util::wordlist_fname("wordlist1.txt");
+        let builder = SmartlistBuilder::new()
+            .infiles(vec![fname.to_str().unwrap()])
+            .vocab_max_size(15)
+            .tokenizers(
+                vec![
+                    SmartlistTokenizer::BPE,
+                    SmartlistTokenizer::WordPiece,
+                    SmartlistTokenizer::Unigram,
+                ]
+                .into_iter(),
+            )
+            .print_progress(false);
+
+        let vocab = builder.build().unwrap();
+        assert_eq!(vocab.len(), 15);
+        for _ in 0..3 {
+            assert_eq!(builder.build().unwrap(), vocab);
+        }
+    }
+
+    #[test]
+    fn test_build_vocab_sort_order() {
+        let fname = test_util::wordlist_fname("wordlist1.txt");
+        let build = |sort_order| {
+            SmartlistBuilder::new()
+                .infiles(vec![fname.to_str().unwrap()])
+                .vocab_max_size(25)
+                .tokenizers(
+                    vec![
+                        SmartlistTokenizer::BPE,
+                        SmartlistTokenizer::WordPiece,
+                        SmartlistTokenizer::Unigram,
+                    ]
+                    .into_iter(),
+                )
+                .print_progress(false)
+                .sort_order(sort_order)
+                .build()
+                .unwrap()
+        };
+
+        let by_frequency = build(SmartlistSortOrder::Frequency);
+        assert_eq!(
+            by_frequency,
+            vec![
+                "1", "12", "123", "2", "3", "11", "12345", "4", "5", "123456", "6", "7", "8", "a",
+                "r", "s", "w", "9", "b", "c", "d", "e", "o", "p", "q",
+            ]
+        );
+
+        // the same most frequent words are selected, only the output order changes
+        let mut expected = by_frequency.clone();
+        expected.sort();
+        assert_eq!(build(SmartlistSortOrder::Alpha), expected);
+
+        expected.sort_by_key(|word| word.len());
+        assert_eq!(
+            build(SmartlistSortOrder::Length),
+            vec![
+                "1", "2", "3", "4", "5", "6", "7", "8", "9", "a", "b", "c", "d", "e", "o", "p",
+                "q", "r", "s", "w", "11", "12", "123", "12345", "123456",
+            ]
+        );
+        assert_eq!(build(SmartlistSortOrder::Length), expected);
+    }
+
+    #[test]
+    fn test_build_vocab_with_base() {
+        let fname = test_util::wordlist_fname("wordlist1.txt");
+        let base_vocab = read_vocab_file(test_util::wordlist_fname("wordlist-3words.txt")).unwrap();
+        assert_eq!(base_vocab, vec!["alpha", "beta", "gamma"]);
+
+        let vocab = SmartlistBuilder::new()
+            .infiles(vec![fname.to_str().unwrap()])
+            .base_vocab(base_vocab)
+            .vocab_max_size(1000)
+            .tokenizers(vec![SmartlistTokenizer::BPE].into_iter())
+            .print_progress(false)
+            .build()
+            .unwrap();
+
+        // base words are kept but ranked last as they never appear in the input file
+        assert_eq!(&vocab[vocab.len() - 3..], ["alpha", "beta", "gamma"]);
+        assert!(vocab.contains(&"123".to_string()));
+
+        let vocab = SmartlistBuilder::new()
+            .infiles(vec![fname.to_str().unwrap()])
+            .base_vocab(vec!["qwerty".to_string(), "zzz".to_string()])
+            .vocab_max_size(3)
+            .tokenizers(vec![SmartlistTokenizer::BPE].into_iter())
+            .print_progress(false)
+            .sort_order(SmartlistSortOrder::Alpha)
+            .build()
+            .unwrap();
+        assert!(!vocab.contains(&"zzz".to_string()));
+    }
+
+    #[test]
+    fn test_build_vocab_keep_matching() {
+        let fname = test_util::wordlist_fname("wordlist1.txt");
+        let vocab = SmartlistBuilder::new()
+            .infiles(vec![fname.to_str().unwrap()])
+            .base_vocab(vec!["abc123".to_string(), "abc".to_string()])
+            .vocab_max_size(1000)
+            .tokenizers(vec![SmartlistTokenizer::BPE].into_iter())
+            .print_progress(false)
+            .keep_matching(Some(Regex::new("[[:alpha:]]+").unwrap()))
+            .build()
+            .unwrap();
+
+        assert!(vocab.contains(&"abc".to_string()));
+        assert!(!vocab.contains(&"abc123".to_string()));
+        assert!(vocab.iter().all(|w| w.chars().all(char::is_alphabetic)));
+    }
+
+    #[test]
+    fn test_remove_not_matching() {
+        let vocab: Vec<_> = ["abc", "abc123", "123", "a-b", "Pass"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let re = Regex::new("[[:alnum:]]+").unwrap();
+        assert_eq!(
+            remove_not_matching(vocab.clone(), &re),
+            vec!["abc", "abc123", "123", "Pass"]
+        );
+        let re = Regex::new("[a-z]+").unwrap();
+        assert_eq!(remove_not_matching(vocab, &re), vec!["abc"]);
+    }
+
+    #[test]
+    fn test_tokenizers_order() {
+        use SmartlistTokenizer::*;
+        assert_eq!(SmartlistBuilder::<&str>::new().tokenizers, vec![Unigram]);
+        for (tokenizers, expected) in [
+            (vec![BPE, Unigram], vec![BPE, Unigram]),
+            (
+                vec![WordPiece, BPE, WordPiece, Unigram, BPE],
+                vec![WordPiece, BPE, Unigram],
+            ),
+            (vec![BPE, BPE], vec![BPE]),
+        ] {
+            let builder = SmartlistBuilder::<&str>::new().tokenizers(tokenizers.into_iter());
+            assert_eq!(builder.tokenizers, expected);
+        }
+    }
+
+    #[test]
+    fn test_remove_matching() {
+        let vocab: Vec<_> = ["abc", "abc123", "123", "!!", "a-b"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let re = Regex::new(r"^\d+$").unwrap();
+        assert_eq!(
+            remove_matching(vocab.clone(), &re),
+            vec!["abc", "abc123", "!!", "a-b"]
+        );
+        let re = Regex::new("[[:punct:]]").unwrap();
+        assert_eq!(remove_matching(vocab, &re), vec!["abc", "abc123", "123"]);
+    }
+
+    #[test]
+    fn test_build_vocab_reject() {
+        let fname = test_util::wordlist_fname("wordlist1.txt");
+        let build = |reject: Option<Regex>| {
+            SmartlistBuilder::new()
+                .infiles(vec![fname.to_str().unwrap()])
+                .base_vocab(vec!["2024".to_string(), "abc".to_string()])
+                .vocab_max_size(1000)
+                .tokenizers(vec![SmartlistTokenizer::BPE].into_iter())
+                .print_progress(false)
+                .reject(reject)
+                .build()
+                .unwrap()
+        };
+
+        let vocab = build(None);
+        assert!(vocab.contains(&"2024".to_string()));
+        assert!(vocab
+            .iter()
+            .any(|w| w.chars().all(|ch| ch.is_ascii_digit())));
+
+        let vocab = build(Some(Regex::new(r"^\d+$").unwrap()));
+        assert!(vocab.contains(&"abc".to_string()));
+        assert!(!vocab.contains(&"2024".to_string()));
+        assert!(!vocab
+            .iter()
+            .any(|w| w.chars().all(|ch| ch.is_ascii_digit())));
+    }
+
+    #[test]
+    fn test_casefold_dedup() {
+        let vocab: Vec<_> = [
+            "PASSWORD", "Password", "abc", "password", "Qwerty", "qwerty",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let (vocab, counts) = casefold_dedup(vocab, vec![1, 5, 2, 3, 4, 4]);
+        assert_eq!(vocab, vec!["Password", "abc", "qwerty"]);
+        assert_eq!(counts, vec![9, 2, 8]);
+    }
+
+    #[test]
+    fn test_build_vocab_casefold_dedup() {
+        let fname = test_util::wordlist_fname("wordlist1.txt");
+        let build = |casefold| {
+            SmartlistBuilder::new()
+                .infiles(vec![fname.to_str().unwrap()])
+                .base_vocab(vec![
+                    "Password".to_string(),
+                    "password".to_string(),
+                    "PASSWORD".to_string(),
+                ])
+                .vocab_max_size(1000)
+                .min_word_len(8)
+                .tokenizers(vec![SmartlistTokenizer::BPE].into_iter())
+                .print_progress(false)
+                .casefold_dedup(casefold)
+                .build()
+                .unwrap()
+        };
+
+        let vocab = build(false);
+        assert!(vocab.contains(&"Password".to_string()));
+        assert!(vocab.contains(&"PASSWORD".to_string()));
+
+        let vocab = build(true);
+        assert!(vocab.contains(&"password".to_string()));
+        assert!(!vocab.contains(&"Password".to_string()));
+        assert!(!vocab.contains(&"PASSWORD".to_string()));
+    }
 }
@@ -1,32 +1,184 @@
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::io::Write;
 use std::rc::Rc;
 
-use num_bigint::{BigUint, ToBigUint};
+use itertools::Itertools;
+use num_bigint::{BigInt, BigUint, ToBigUint};
+use ordered_float::OrderedFloat;
 
-use crate::charsets::Charset;
-use crate::mask::{parse_mask, validate_charsets, validate_wordlists, MaskOp};
+use crate::charsets::{expand_custom_charset, find_symbol, Charset};
+use crate::helpers::open_input;
+use crate::mask::{Mask, MaskOp};
+use crate::model::CharModel;
+use crate::output::WordsWriter;
 use crate::stackbuf::StackBuf;
-use crate::wordlists::{Wordlist, WordlistIterator};
-use crate::{BoxResult, MAX_WORD_SIZE};
+use crate::wordlists::{wordlist_files, Wordlist, WordlistIterator, WordlistOptions};
+use crate::{BoxResult, BUFFER_SIZE, MAX_WORD_SIZE};
 
 pub trait WordGenerator {
-    fn gen<'b>(&self, out: &mut Box<dyn Write + 'b>) -> Result<(), std::io::Error>;
+    fn gen<'b>(&self, out: &mut WordsWriter<'b>) -> Result<(), std::io::Error>;
     fn combinations(&self) -> BigUint;
+    /// returns true iff `combinations` is the exact number of words generated, otherwise it
+    /// is an upper bound
+    fn is_exact_count(&self) -> bool {
+        true
+    }
+    /// returns the word at index `n` in generation order without generating the words before
+    /// it, or `None` if `n` is past the last word or the generator cannot compute it
+    fn nth_word(&self, _n: &BigUint) -> Option<Vec<u8>> {
+        None
+    }
+    /// the mask of the generated words, empty for generators without a mask
+    fn mask(&self) -> &str;
+    fn kind(&self) -> GeneratorKind;
+}
+
+/// the kind of a word generator, see `get_word_generator_with_options`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratorKind {
+    /// `CharsetGenerator` - a mask of charsets only
+    Charset,
+    /// `WordlistGenerator` - a mask with wordlists
+    Wordlist,
+    /// `CombineGenerator`
+    Combine,
+    /// `ModelGenerator`
+    Model,
+    /// `PositionalGenerator`
+    Positional,
+    /// `ZipGenerator`
+    Zip,
+    /// `DeltaGenerator`
+    Delta,
+}
+
+/// the number of char classes of `char_class`
+const CHAR_CLASSES: usize = 4;
+
+/// returns the char class of `ch` - 0 for lower, 1 for upper, 2 for digit and 3 for symbol
+/// (any other byte)
+#[inline]
+fn char_class(ch: u8) -> usize {
+    if ch.is_ascii_lowercase() {
+        0
+    } else if ch.is_ascii_uppercase() {
+        1
+    } else if ch.is_ascii_digit() {
+        2
+    } else {
+        3
+    }
 }
 
 /// Generator optimized for charsets only
 pub struct CharsetGenerator {
     pub mask: Vec<MaskOp>,
+    mask_text: String,
     pub minlen: usize,
     pub maxlen: usize,
     charsets: Vec<Charset>,
     min_word: Vec<u8>,
+    /// inclusive first and last words to generate, see `with_range`
+    range: Option<(Vec<u8>, Vec<u8>)>,
+    /// generate only the first words of each length, see `with_max_per_length`
+    max_per_length: Option<usize>,
+    /// generate only the words with at least this many char classes, see `with_min_classes`
+    min_classes: usize,
+}
+
+/// iterator over the words of a `CharsetGenerator` in generation order, see
+/// `CharsetGenerator::iter`
+pub struct CharsetIterator<'a> {
+    generator: &'a CharsetGenerator,
+    /// the next word to yield, None once all words were yielded
+    word: Option<Vec<u8>>,
+}
+
+/// Generator of concatenations of any number of words from a single wordlist, with a total
+/// length between `total_minlen` and `total_maxlen`
+pub struct CombineGenerator {
+    pub total_minlen: usize,
+    pub total_maxlen: usize,
+    wordlist: Rc<Wordlist>,
+}
+
+/// Generator of a charsets mask words ordered from the most to the least probable according
+/// to a `CharModel`, instead of the odometer order of `CharsetGenerator`
+pub struct ModelGenerator {
+    pub mask: Vec<MaskOp>,
+    mask_text: String,
+    /// the chars of each position sorted by ascending cost (most probable first)
+    chars: Vec<Vec<u8>>,
+    costs: Vec<Vec<f64>>,
+}
+
+/// Generator of a charsets mask with any subset of its positions present (e.g. `?d?d?d` also
+/// generates `?d` and `?d?d` from every pair of positions), ordered by subset size. the number
+/// of subsets grows exponentially with the mask length, `minlen` and `maxlen` bound their size
+pub struct PositionalGenerator {
+    pub mask: Vec<MaskOp>,
+    mask_text: String,
+    pub minlen: usize,
+    pub maxlen: usize,
+    custom_charsets: Vec<String>,
+    lens: Vec<usize>,
 }
 
 /// Wordlist Generator for both charsets and wordlists
 pub struct WordlistGenerator {
     pub mask: Vec<MaskOp>,
+    mask_text: String,
     items: Vec<WordlistItem>,
+    unordered_unique: bool,
+    no_repeat: NoRepeat,
+    /// skip words using less distinct wordlist words, 0 or 1 to keep all words
+    min_distinct_words: usize,
+    /// words may contain a newline byte, so they are written one by one with their length
+    has_newlines: bool,
+}
+
+/// Generator of a mask with its wordlists iterated in lockstep by line, like python's `zip`,
+/// instead of their cartesian product - the first line of each wordlist, then the second
+/// etc. stopping at the shortest. charset positions are generated as a product on every line.
+/// the lines are kept in file order, blank ones included so paired lines stay aligned
+pub struct ZipGenerator {
+    pub mask: Vec<MaskOp>,
+    mask_text: String,
+    items: Vec<ZipItem>,
+    /// the lines of each wordlist, empty for wordlists not used by the mask
+    lines: Vec<Vec<Vec<u8>>>,
+    steps: usize,
+    /// words may contain a newline byte, so they are written one by one with their length
+    has_newlines: bool,
+}
+
+/// Generator of only the words of a mask using the lines appended to its wordlist since it
+/// was last generated - every word with at least one of the new words on its wordlist
+/// positions. split by the first position with a new word: old words before it and any word
+/// after it, so no word is generated twice
+pub struct DeltaGenerator {
+    mask_text: String,
+    parts: Vec<WordlistGenerator>,
+}
+
+enum ZipItem {
+    /// the chars of a charset position in generation order
+    Chars(Vec<u8>),
+    Wordlist(usize),
+}
+
+/// which words repeated on wordlist positions of a mask are skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoRepeat {
+    #[default]
+    Off,
+    /// the same word on consecutive wordlist positions, charset positions between them are
+    /// ignored (e.g. `?w1-?w1` skips `alpha-alpha`)
+    Adjacent,
+    /// the same word on any two wordlist positions
+    Any,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -46,6 +198,147 @@ enum Position<'a> {
     },
 }
 
+/// additional options for building word generators
+#[derive(Debug, Clone, Default)]
+pub struct GeneratorOptions {
+    wordlist_options: WordlistOptions,
+    unordered_unique: bool,
+    positional_increment: bool,
+    range: Option<(String, String)>,
+    no_repeat: NoRepeat,
+    min_distinct_words: usize,
+    zip: bool,
+    one_per_length: bool,
+    max_per_length: Option<usize>,
+    min_classes: usize,
+    delta_from: Option<usize>,
+    anti_mask: Option<String>,
+    /// wordlists loaded by the generators built with these options (or their clones) by
+    /// filename, see `cache_wordlists`
+    wordlists_cache: Option<WordlistsCache>,
+}
+
+/// loaded wordlists by filename, shared between generators
+type WordlistsCache = Rc<RefCell<HashMap<String, Rc<Wordlist>>>>;
+
+impl GeneratorOptions {
+    pub fn new() -> GeneratorOptions {
+        GeneratorOptions::default()
+    }
+    pub fn wordlist_options(mut self, wordlist_options: WordlistOptions) -> Self {
+        self.wordlist_options = wordlist_options;
+        self
+    }
+    /// emit only one ordering of words drawn from the same wordlist on multiple positions
+    /// (e.g. `?w1?w1` emits `alpha beta` but not `beta alpha`)
+    pub fn unordered_unique(mut self, unordered_unique: bool) -> Self {
+        self.unordered_unique = unordered_unique;
+        self
+    }
+    /// generate charsets masks with any subset of their positions, see `PositionalGenerator`
+    pub fn positional_increment(mut self, positional_increment: bool) -> Self {
+        self.positional_increment = positional_increment;
+        self
+    }
+    /// generate only the words from `from` to `to` (inclusive) of a charsets mask, see
+    /// `CharsetGenerator::with_range`
+    pub fn range(mut self, range: Option<(String, String)>) -> Self {
+        self.range = range;
+        self
+    }
+    /// skip words repeating the same wordlist word, compared by bytes. combinations are not
+    /// reduced by it so they are an upper bound
+    pub fn no_repeat(mut self, no_repeat: NoRepeat) -> Self {
+        self.no_repeat = no_repeat;
+        self
+    }
+    /// skip words using less than `min_distinct_words` distinct words on their wordlist
+    /// positions, compared by bytes (e.g. 2 skips `the the the`). combinations are not reduced
+    /// by it so they are an upper bound
+    pub fn min_distinct_words(mut self, min_distinct_words: usize) -> Self {
+        self.min_distinct_words = min_distinct_words;
+        self
+    }
+    /// iterate the wordlists of the mask in lockstep instead of their product, see
+    /// `ZipGenerator`
+    pub fn zip(mut self, zip: bool) -> Self {
+        self.zip = zip;
+        self
+    }
+    /// generate only the first word of each length of a charsets mask, see
+    /// `CharsetGenerator::with_one_per_length`
+    pub fn one_per_length(mut self, one_per_length: bool) -> Self {
+        self.one_per_length = one_per_length;
+        self
+    }
+    /// generate only the first `max_per_length` words of each length of a charsets mask, see
+    /// `CharsetGenerator::with_max_per_length`
+    pub fn max_per_length(mut self, max_per_length: Option<usize>) -> Self {
+        self.max_per_length = max_per_length;
+        self
+    }
+    /// generate only the words of a charsets mask with at least `min_classes` of the char
+    /// classes lower, upper, digit and symbol, see `CharsetGenerator::with_min_classes`
+    pub fn min_classes(mut self, min_classes: usize) -> Self {
+        self.min_classes = min_classes;
+        self
+    }
+    /// generate only the words using the lines of the wordlist after its first `delta_from`
+    /// lines, see `DeltaGenerator`
+    pub fn delta_from(mut self, delta_from: Option<usize>) -> Self {
+        self.delta_from = delta_from;
+        self
+    }
+    /// generate the words not matching the charsets mask on any position, from the chars of
+    /// the `anti_mask` alphabet (a custom charset definition), see
+    /// `CharsetGenerator::with_complement`
+    pub fn anti_mask(mut self, anti_mask: Option<String>) -> Self {
+        self.anti_mask = anti_mask;
+        self
+    }
+    /// keep the loaded wordlists and share them with the following generators built with
+    /// these options (or their clones), so many masks using the same wordlist load it once
+    pub fn cache_wordlists(mut self, cache_wordlists: bool) -> Self {
+        self.wordlists_cache = cache_wordlists.then(Default::default);
+        self
+    }
+
+    /// loads a wordlist, or returns it from the cache if it was already loaded
+    fn load_wordlist(&self, fname: &str) -> BoxResult<Rc<Wordlist>> {
+        if let Some(wordlist) = self
+            .wordlists_cache
+            .as_ref()
+            .and_then(|cache| cache.borrow().get(fname).cloned())
+        {
+            return Ok(wordlist);
+        }
+        let wordlist = Rc::new(Wordlist::from_file_with_options(
+            fname,
+            &self.wordlist_options,
+        )?);
+        if let Some(cache) = &self.wordlists_cache {
+            cache
+                .borrow_mut()
+                .insert(fname.to_owned(), wordlist.clone());
+        }
+        Ok(wordlist)
+    }
+
+    /// returns the wordlists loaded by the generators built with these options (or their
+    /// clones) sorted by filename, none without `cache_wordlists`
+    pub fn loaded_wordlists(&self) -> Vec<(String, Rc<Wordlist>)> {
+        let mut wordlists: Vec<_> = self.wordlists_cache.as_ref().map_or(vec![], |cache| {
+            cache
+                .borrow()
+                .iter()
+                .map(|(fname, wordlist)| (fname.clone(), wordlist.clone()))
+                .collect()
+        });
+        wordlists.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        wordlists
+    }
+}
+
 /// returns the correct word generator based on the args provided
 pub fn get_word_generator<'a>(
     mask: &'a str,
@@ -54,28 +347,213 @@ pub fn get_word_generator<'a>(
     custom_charsets: &[&'a str],
     wordlists_fnames: &[&'a str],
 ) -> BoxResult<Box<dyn WordGenerator + 'a>> {
-    let mask_ops = parse_mask(mask)?;
-    validate_charsets(&mask_ops, custom_charsets.len())?;
-    validate_wordlists(&mask_ops, wordlists_fnames.len())?;
+    get_word_generator_with_options(
+        mask,
+        minlen,
+        maxlen,
+        custom_charsets,
+        wordlists_fnames,
+        &GeneratorOptions::default(),
+    )
+}
+
+/// same as `get_word_generator` with additional generation `options`
+pub fn get_word_generator_with_options<'a>(
+    mask: &'a str,
+    minlen: Option<usize>,
+    maxlen: Option<usize>,
+    custom_charsets: &[&'a str],
+    wordlists_fnames: &[&'a str],
+    options: &GeneratorOptions,
+) -> BoxResult<Box<dyn WordGenerator + 'a>> {
+    let mask: Mask = mask.parse()?;
+    mask.validate(custom_charsets.len(), wordlists_fnames.len())?;
+
+    if let Some(old_lines) = options.delta_from {
+        if mask.wordlist_refs() != [0] || wordlists_fnames.len() != 1 {
+            bail!("generating new words only supports masks with a single wordlist");
+        } else if minlen.is_some() || maxlen.is_some() {
+            bail!("cannot set minlen or maxlen with wordlists");
+        } else if options.zip
+            || options.unordered_unique
+            || options.no_repeat != NoRepeat::Off
+            || options.min_distinct_words > 1
+            || options.wordlist_options.is_dedup()
+        {
+            bail!("generating new words only cannot be used with zip, unordered unique, no repeat, min distinct words or dedup");
+        }
+        return Ok(Box::new(DeltaGenerator::new(
+            mask.into_ops(),
+            wordlists_fnames[0],
+            custom_charsets,
+            old_lines,
+            options,
+        )?));
+    }
 
-    if mask_ops.iter().all(|op| !matches!(op, MaskOp::Wordlist(_))) {
-        Ok(Box::new(CharsetGenerator::new(
-            mask_ops,
+    if options.zip && !mask.has_wordlists() {
+        bail!("zipping wordlists requires a mask with wordlists");
+    } else if options.zip
+        && (options.unordered_unique
+            || options.no_repeat != NoRepeat::Off
+            || options.min_distinct_words > 1)
+    {
+        bail!("zipped wordlists have a single word per position, they cannot be repeated");
+    } else if options.min_distinct_words > 0 && !mask.has_wordlists() {
+        bail!("min distinct words requires a mask with wordlists");
+    }
+
+    if options.anti_mask.is_some() && (mask.has_wordlists() || options.positional_increment) {
+        bail!("anti mask supports charsets masks only, without positional increment");
+    }
+
+    if !mask.has_wordlists() && options.positional_increment {
+        Ok(Box::new(PositionalGenerator::new(
+            mask.into_ops(),
             minlen,
             maxlen,
             custom_charsets,
         )?))
+    } else if !mask.has_wordlists() {
+        let mut word_gen = CharsetGenerator::new(mask.into_ops(), minlen, maxlen, custom_charsets)?;
+        if let Some(alphabet) = &options.anti_mask {
+            let alphabet = expand_custom_charset(alphabet.as_bytes());
+            if alphabet.is_empty() {
+                bail!("anti mask alphabet cannot be empty");
+            }
+            word_gen = word_gen.with_complement(&Charset::from_chars(&alphabet))?;
+        }
+        if options.min_classes > 0 {
+            if options.range.is_some() || options.one_per_length || options.max_per_length.is_some()
+            {
+                bail!("min classes cannot be used with --from and --to or words per length limits");
+            }
+            word_gen = word_gen.with_min_classes(options.min_classes)?;
+        }
+        match (&options.range, options.max_per_length) {
+            _ if options.one_per_length && options.max_per_length.is_some() => {
+                bail!("one word per length cannot be used with a max number of words per length")
+            }
+            (Some(_), _) if options.one_per_length => {
+                bail!("--from and --to cannot be used with one word per length")
+            }
+            (Some(_), Some(_)) => {
+                bail!("--from and --to cannot be used with a max number of words per length")
+            }
+            (Some((from, to)), None) => Ok(Box::new(
+                word_gen.with_range(from.as_bytes(), to.as_bytes())?,
+            )),
+            (None, _) if options.one_per_length => Ok(Box::new(word_gen.with_one_per_length())),
+            (None, Some(max_per_length)) => {
+                Ok(Box::new(word_gen.with_max_per_length(max_per_length)?))
+            }
+            (None, None) => Ok(Box::new(word_gen)),
+        }
+    } else if options.positional_increment {
+        bail!("positional increment supports charsets masks only")
+    } else if options.one_per_length {
+        bail!("one word per length supports charsets masks only")
+    } else if options.max_per_length.is_some() {
+        bail!("max words per length supports charsets masks only")
+    } else if options.min_classes > 0 {
+        bail!("min classes supports charsets masks only")
+    } else if options.range.is_some() {
+        bail!("--from and --to support charsets masks only")
     } else if minlen.is_some() || maxlen.is_some() {
         bail!("cannot set minlen or maxlen with wordlists")
+    } else if options.zip {
+        Ok(Box::new(ZipGenerator::new(
+            mask.into_ops(),
+            wordlists_fnames,
+            custom_charsets,
+            &options.wordlist_options,
+        )?))
     } else {
-        Ok(Box::new(WordlistGenerator::new(
-            mask_ops,
+        Ok(Box::new(WordlistGenerator::with_options(
+            mask.into_ops(),
             wordlists_fnames,
             custom_charsets,
+            options,
         )?))
     }
 }
 
+/// calculates the number of words a mask generates without building its generator.
+/// wordlists are not loaded - `wordlists_lens` holds the number of words of each wordlist
+pub fn mask_combinations(
+    mask: &str,
+    minlen: Option<usize>,
+    maxlen: Option<usize>,
+    custom_charsets: &[&str],
+    wordlists_lens: &[usize],
+) -> BoxResult<BigUint> {
+    let mask: Mask = mask.parse()?;
+    mask.validate(custom_charsets.len(), wordlists_lens.len())?;
+
+    let lens: Vec<usize> = mask
+        .ops()
+        .iter()
+        .map(|op| match op {
+            MaskOp::Char(_) => Ok(1),
+            MaskOp::BuiltinCharset(ch) => match find_symbol(*ch) {
+                Some(charset) => Ok(charset.chars.len()),
+                None => bail!("unknown mask symbol - {}", ch),
+            },
+            MaskOp::BuiltinCharsetExcept(ch, except) => {
+                Ok(Charset::from_symbol_except(*ch, except).len())
+            }
+            MaskOp::CustomCharset(idx) => {
+                Ok(expand_custom_charset(custom_charsets[*idx].as_bytes()).len())
+            }
+            MaskOp::Wordlist(idx) => Ok(wordlists_lens[*idx]),
+        })
+        .collect::<BoxResult<_>>()?;
+
+    if !mask.has_wordlists() {
+        let (minlen, maxlen) = resolve_lengths(minlen, maxlen, lens.len())?;
+        Ok(charsets_combinations(&lens, minlen, maxlen))
+    } else if minlen.is_some() || maxlen.is_some() {
+        bail!("cannot set minlen or maxlen with wordlists")
+    } else {
+        Ok(lens.iter().map(|&len| len.to_biguint().unwrap()).product())
+    }
+}
+
+/// validates and returns the (minlen, maxlen) of a charsets mask with `mask_len` positions
+fn resolve_lengths(
+    minlen: Option<usize>,
+    maxlen: Option<usize>,
+    mask_len: usize,
+) -> BoxResult<(usize, usize)> {
+    // min/max pwd length is by default the longest word
+    let minlen = minlen.unwrap_or(mask_len);
+    let maxlen = maxlen.unwrap_or(mask_len);
+
+    // validate minlen
+    if !(0 < minlen && minlen <= maxlen && minlen <= mask_len) {
+        bail!("minlen is invalid");
+    }
+    if maxlen > mask_len {
+        bail!("maxlen is invalid");
+    }
+    Ok((minlen, maxlen))
+}
+
+/// sums the number of words of every length from minlen to maxlen, where each position has
+/// `lens[pos]` options
+fn charsets_combinations(lens: &[usize], minlen: usize, maxlen: usize) -> BigUint {
+    let mut combs: BigUint = 0.to_biguint().unwrap();
+    // running product of the first `i` lens
+    let mut prefix_product: BigUint = 1.to_biguint().unwrap();
+    for (i, len) in lens.iter().enumerate().take(maxlen) {
+        prefix_product *= *len;
+        if i + 1 >= minlen {
+            combs += &prefix_product;
+        }
+    }
+    combs
+}
+
 impl<'a> CharsetGenerator {
     pub fn new(
         mask: Vec<MaskOp>,
@@ -88,40 +566,357 @@ impl<'a> CharsetGenerator {
             .map(|op| match op {
                 MaskOp::Char(ch) => Charset::from_chars(vec![*ch as u8].as_ref()),
                 MaskOp::BuiltinCharset(ch) => Charset::from_symbol(*ch),
-                MaskOp::CustomCharset(idx) => Charset::from_chars(custom_charsets[*idx].as_bytes()),
+                MaskOp::BuiltinCharsetExcept(ch, except) => {
+                    Charset::from_symbol_except(*ch, except)
+                }
+                MaskOp::CustomCharset(idx) => Charset::from_custom(custom_charsets[*idx]),
                 MaskOp::Wordlist(_) => unreachable!("cant handle wordlists"),
             })
             .collect();
 
-        // min/max pwd length is by default the longest word
-        let minlen = minlen.unwrap_or_else(|| charsets.len());
-        let maxlen = maxlen.unwrap_or_else(|| charsets.len());
-
-        // validate minlen
-        if !(0 < minlen && minlen <= maxlen && minlen <= charsets.len()) {
-            bail!("minlen is invalid");
-        }
-        if maxlen > charsets.len() {
-            bail!("maxlen is invalid");
-        }
+        let (minlen, maxlen) = resolve_lengths(minlen, maxlen, charsets.len())?;
 
         // prepare min word - the longest first word
         let min_word: Vec<u8> = charsets.iter().map(|c| c.min_char).collect();
 
         Ok(CharsetGenerator {
+            mask_text: Mask::new(mask.clone()).to_string(),
             mask,
             minlen,
             maxlen,
             charsets,
             min_word,
+            range: None,
+            max_per_length: None,
+            min_classes: 0,
         })
     }
 
-    #[allow(clippy::borrowed_box)]
+    /// generates only the first word of each length from minlen to maxlen, to quickly see
+    /// what a mask produces at every length
+    pub fn with_one_per_length(mut self) -> CharsetGenerator {
+        self.max_per_length = Some(1);
+        self
+    }
+
+    /// generates only the first `max_per_length` words of each length from minlen to maxlen,
+    /// a length balanced sample instead of exhausting the short lengths first
+    pub fn with_max_per_length(mut self, max_per_length: usize) -> BoxResult<CharsetGenerator> {
+        if max_per_length == 0 {
+            bail!("max words per length must be at least 1");
+        }
+        self.max_per_length = Some(max_per_length);
+        Ok(self)
+    }
+
+    /// generates only the words with at least `min_classes` of the 4 char classes - lower,
+    /// upper, digit and symbol (any other byte), e.g. 3 for a "3 of 4 classes" password policy
+    pub fn with_min_classes(mut self, min_classes: usize) -> BoxResult<CharsetGenerator> {
+        if !(1..=CHAR_CLASSES).contains(&min_classes) {
+            bail!(
+                "min classes must be between 1 and {}, got {}",
+                CHAR_CLASSES,
+                min_classes
+            );
+        }
+        self.min_classes = min_classes;
+        Ok(self)
+    }
+
+    /// returns true if `word` has at least `min_classes` char classes
+    #[inline]
+    fn has_min_classes(&self, word: &[u8]) -> bool {
+        let classes = word
+            .iter()
+            .fold(0usize, |classes, &ch| classes | 1 << char_class(ch));
+        classes.count_ones() as usize >= self.min_classes
+    }
+
+    /// counts the words of length `pwdlen` with at least `min_classes` char classes by
+    /// inclusion-exclusion - the words using exactly the classes of a set are the words using
+    /// only its classes minus those of its subsets, with alternating signs
+    fn min_classes_combinations(&self, pwdlen: usize) -> BigUint {
+        let class_counts: Vec<[usize; CHAR_CLASSES]> = self.charsets[..pwdlen]
+            .iter()
+            .map(|charset| {
+                let mut counts = [0; CHAR_CLASSES];
+                charset.iter().for_each(|ch| counts[char_class(ch)] += 1);
+                counts
+            })
+            .collect();
+        // the words using only the classes of each subset of the classes
+        let only_classes: Vec<BigInt> = (0..1usize << CHAR_CLASSES)
+            .map(|subset| {
+                class_counts
+                    .iter()
+                    .map(|counts| {
+                        let count: usize = (0..CHAR_CLASSES)
+                            .filter(|class| subset & 1 << class != 0)
+                            .map(|class| counts[class])
+                            .sum();
+                        BigInt::from(count)
+                    })
+                    .product()
+            })
+            .collect();
+
+        let mut total = BigInt::from(0);
+        for classes in 0..1usize << CHAR_CLASSES {
+            if (classes.count_ones() as usize) < self.min_classes {
+                continue;
+            }
+            // iterates the subsets of `classes`
+            let mut subset = classes;
+            loop {
+                let sign = (classes.count_ones() - subset.count_ones()) % 2;
+                match sign {
+                    0 => total += &only_classes[subset],
+                    _ => total -= &only_classes[subset],
+                }
+                if subset == 0 {
+                    break;
+                }
+                subset = (subset - 1) & classes;
+            }
+        }
+        total.to_biguint().unwrap()
+    }
+
+    /// returns the number of words of length `pwdlen` to generate, capped by `max_per_length`
+    fn len_combinations(&self, pwdlen: usize) -> BigUint {
+        let combs: BigUint = self.charsets[..pwdlen]
+            .iter()
+            .map(|c| c.len.to_biguint().unwrap())
+            .product();
+        match self.max_per_length {
+            Some(max_per_length) => combs.min(max_per_length.to_biguint().unwrap()),
+            None => combs,
+        }
+    }
+
+    /// generates the complement of the mask against `alphabet` - the words of the mask lengths
+    /// not matching the mask on any position, each position iterating the chars of `alphabet`
+    /// not in its charset
+    pub fn with_complement(mut self, alphabet: &Charset) -> BoxResult<CharsetGenerator> {
+        let mut charsets = Vec::with_capacity(self.charsets.len());
+        for (pos, charset) in self.charsets.iter().enumerate() {
+            match charset.complement(alphabet) {
+                Some(complement) => charsets.push(complement),
+                None => bail!(
+                    "position {} of the mask has every char of the alphabet, its complement is empty",
+                    pos + 1
+                ),
+            }
+        }
+        self.min_word = charsets.iter().map(|c| c.min_char).collect();
+        self.charsets = charsets;
+        Ok(self)
+    }
+
+    /// restricts the generated words to the ones from `from` to `to` (inclusive) in generation
+    /// order. both must be words of the mask and the mask must have a single length
+    pub fn with_range(mut self, from: &[u8], to: &[u8]) -> BoxResult<CharsetGenerator> {
+        if self.minlen != self.maxlen {
+            bail!("--from and --to cannot be used with --minlen or --maxlen");
+        }
+        let from_index = self.word_index(from)?;
+        let to_index = self.word_index(to)?;
+        if from_index > to_index {
+            bail!(
+                "range start {:?} comes after range end {:?}",
+                String::from_utf8_lossy(from),
+                String::from_utf8_lossy(to)
+            );
+        }
+        self.range = Some((from.to_vec(), to.to_vec()));
+        Ok(self)
+    }
+
+    /// returns the index of `word` in generation order, validating it is a word of the mask
+    fn word_index(&self, word: &[u8]) -> BoxResult<BigUint> {
+        if word.len() != self.charsets.len() {
+            bail!(
+                "{:?} must have the mask length of {}",
+                String::from_utf8_lossy(word),
+                self.charsets.len()
+            );
+        }
+        self.len_word_index(word)
+    }
+
+    /// returns the index of `word` among the words of its length in generation order
+    fn len_word_index(&self, word: &[u8]) -> BoxResult<BigUint> {
+        let mut index = 0.to_biguint().unwrap();
+        for (pos, (&chr, charset)) in word.iter().zip(self.charsets.iter()).enumerate() {
+            let rank = match charset.iter().position(|c| c == chr) {
+                Some(rank) => rank,
+                None => bail!(
+                    "{:?} does not match the mask - char {} is not in the charset of its position",
+                    String::from_utf8_lossy(word),
+                    pos + 1
+                ),
+            };
+            index = index * charset.len() + rank;
+        }
+        Ok(index)
+    }
+
+    /// iterates over the words of every length from minlen to maxlen in the same order as
+    /// `gen`, without buffering them
+    pub fn iter(&self) -> CharsetIterator<'_> {
+        let first = match &self.range {
+            Some((from, _)) => from.clone(),
+            None => self.min_word[..self.minlen].to_vec(),
+        };
+        CharsetIterator {
+            generator: self,
+            word: Some(first),
+        }
+    }
+
+    /// returns the word following `word` in generation order, or None if it is the last one
+    fn next_word(&self, word: &[u8]) -> Option<Vec<u8>> {
+        let pwdlen = word.len();
+        let next_len_word = || (pwdlen < self.maxlen).then(|| self.min_word[..=pwdlen].to_vec());
+        match (&self.range, self.max_per_length) {
+            (Some((_, to)), _) if word == &to[..] => return None,
+            (Some(_), _) | (None, None) => {}
+            (None, Some(max_per_length)) => {
+                // the words of the mask are always valid
+                let index = self.len_word_index(word).unwrap() + 1u32;
+                if index >= max_per_length.to_biguint().unwrap() {
+                    return next_len_word();
+                }
+            }
+        }
+
+        let mut next = word.to_vec();
+        for pos in (0..pwdlen).rev() {
+            let chr = next[pos];
+            let next_chr = self.charsets[pos][chr as usize];
+            next[pos] = next_chr;
+            if chr < next_chr {
+                return Some(next);
+            }
+        }
+        // every position wrapped around - continue with the first word of the next length
+        match self.range {
+            Some(_) => None,
+            None => next_len_word(),
+        }
+    }
+
+    /// returns the word of length `pwdlen` at `index` in generation order, the last position
+    /// changing the fastest
+    fn decode_word(&self, mut index: BigUint, pwdlen: usize) -> Vec<u8> {
+        let mut word = vec![0u8; pwdlen];
+        for pos in (0..pwdlen).rev() {
+            let charset = &self.charsets[pos];
+            let rank = usize::try_from(&index % charset.len).unwrap();
+            index /= charset.len;
+            word[pos] = charset.iter().nth(rank).unwrap();
+        }
+        word
+    }
+
+    /// generates the words of `range` in generation order, see `with_range`
+    fn gen_range<'b>(
+        &self,
+        from: &[u8],
+        to: &[u8],
+        out: &mut WordsWriter<'b>,
+    ) -> Result<(), std::io::Error> {
+        let pwdlen = from.len();
+        let mut buf = StackBuf::new();
+        let batch_size = buf.len() / (pwdlen + 1);
+
+        let word = &mut [b'\n'; MAX_WORD_SIZE][..=pwdlen];
+        word[..pwdlen].copy_from_slice(from);
+
+        'outer_loop: loop {
+            'batch_for: for _ in 0..batch_size {
+                buf.write(word);
+                if &word[..pwdlen] == to {
+                    break 'outer_loop;
+                }
+                for pos in (0..pwdlen).rev() {
+                    let chr = word[pos];
+                    let next_chr = self.charsets[pos][chr as usize];
+                    word[pos] = next_chr;
+
+                    if chr < next_chr {
+                        continue 'batch_for;
+                    }
+                }
+                break 'outer_loop;
+            }
+
+            out.write_words(buf.getdata(), pwdlen)?;
+            buf.clear();
+        }
+        out.write_words(buf.getdata(), pwdlen)?;
+        Ok(())
+    }
+
+    /// generates the first `max_per_length` words of each length, see `with_max_per_length`
+    fn gen_max_per_length<'b>(&self, out: &mut WordsWriter<'b>) -> Result<(), std::io::Error> {
+        let mut buf = Vec::with_capacity(BUFFER_SIZE + MAX_WORD_SIZE);
+        for pwdlen in self.minlen..=self.maxlen {
+            let len_combs = usize::try_from(self.len_combinations(pwdlen)).unwrap();
+            for index in 0..len_combs {
+                buf.extend_from_slice(&self.decode_word(index.into(), pwdlen));
+                buf.push(b'\n');
+                if buf.len() >= BUFFER_SIZE {
+                    out.write_words(&buf, pwdlen)?;
+                    buf.clear();
+                }
+            }
+            out.write_words(&buf, pwdlen)?;
+            buf.clear();
+        }
+        Ok(())
+    }
+
+    /// like `gen_by_length`, skipping the words with less than `min_classes` char classes
+    fn gen_min_classes_by_length<'b>(
+        &self,
+        pwdlen: usize,
+        out: &mut WordsWriter<'b>,
+    ) -> Result<(), std::io::Error> {
+        let mut buf = StackBuf::new();
+        let batch_size = buf.len() / (pwdlen + 1);
+
+        let word = &mut [b'\n'; MAX_WORD_SIZE][..=pwdlen];
+        word[..pwdlen].copy_from_slice(&self.min_word[..pwdlen]);
+
+        'outer_loop: loop {
+            'batch_for: for _ in 0..batch_size {
+                if self.has_min_classes(&word[..pwdlen]) {
+                    buf.write(word);
+                }
+                for pos in (0..pwdlen).rev() {
+                    let chr = word[pos];
+                    let next_chr = self.charsets[pos][chr as usize];
+                    word[pos] = next_chr;
+
+                    if chr < next_chr {
+                        continue 'batch_for;
+                    }
+                }
+                break 'outer_loop;
+            }
+
+            out.write_words(buf.getdata(), pwdlen)?;
+            buf.clear();
+        }
+        out.write_words(buf.getdata(), pwdlen)?;
+        Ok(())
+    }
+
     fn gen_by_length<'b>(
         &self,
         pwdlen: usize,
-        out: &mut Box<dyn Write + 'b>,
+        out: &mut WordsWriter<'b>,
     ) -> Result<(), std::io::Error> {
         let mut buf = StackBuf::new();
         let batch_size = buf.len() / (pwdlen + 1);
@@ -144,17 +939,43 @@ impl<'a> CharsetGenerator {
                 break 'outer_loop;
             }
 
-            out.write_all(buf.getdata())?;
+            out.write_words(buf.getdata(), pwdlen)?;
             buf.clear();
         }
-        out.write_all(buf.getdata())?;
+        out.write_words(buf.getdata(), pwdlen)?;
         Ok(())
     }
 }
 
-impl<'a> WordGenerator for CharsetGenerator {
+impl Iterator for CharsetIterator<'_> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let word = self.word.take()?;
+            self.word = self.generator.next_word(&word);
+            if self.generator.min_classes == 0 || self.generator.has_min_classes(&word) {
+                return Some(word);
+            }
+        }
+    }
+}
+
+impl WordGenerator for CharsetGenerator {
     /// generates all words into the output buffer `out`
-    fn gen<'b>(&self, out: &mut Box<dyn Write + 'b>) -> Result<(), std::io::Error> {
+    fn gen<'b>(&self, out: &mut WordsWriter<'b>) -> Result<(), std::io::Error> {
+        if let Some((from, to)) = &self.range {
+            return self.gen_range(from, to, out);
+        }
+        if self.max_per_length.is_some() {
+            return self.gen_max_per_length(out);
+        }
+        if self.min_classes > 0 {
+            for pwdlen in self.minlen..=self.maxlen {
+                self.gen_min_classes_by_length(pwdlen, out)?;
+            }
+            return Ok(());
+        }
         for pwdlen in self.minlen..=self.maxlen {
             self.gen_by_length(pwdlen, out)?;
         }
@@ -163,17 +984,53 @@ impl<'a> WordGenerator for CharsetGenerator {
 
     /// calculates number of words to be generated by this WordGenerator
     fn combinations(&self) -> BigUint {
-        let mut combs: BigUint = 0.to_biguint().unwrap();
-        for i in self.minlen..=self.maxlen {
-            combs += self
-                .charsets
-                .iter()
-                .take(i)
-                .fold(1.to_biguint().unwrap(), |acc, x| {
-                    (acc * x.len).to_biguint().unwrap()
-                });
+        if let Some((from, to)) = &self.range {
+            // both were validated by with_range
+            return self.word_index(to).unwrap() - self.word_index(from).unwrap() + 1u32;
         }
-        combs
+        if self.max_per_length.is_some() {
+            return (self.minlen..=self.maxlen)
+                .map(|pwdlen| self.len_combinations(pwdlen))
+                .sum();
+        }
+        if self.min_classes > 0 {
+            return (self.minlen..=self.maxlen)
+                .map(|pwdlen| self.min_classes_combinations(pwdlen))
+                .sum();
+        }
+        let lens: Vec<_> = self.charsets.iter().map(|c| c.len).collect();
+        charsets_combinations(&lens, self.minlen, self.maxlen)
+    }
+
+    fn nth_word(&self, n: &BigUint) -> Option<Vec<u8>> {
+        // skipped words shift the index of every following word
+        if self.min_classes > 0 {
+            return None;
+        }
+        if let Some((from, _)) = &self.range {
+            if n >= &self.combinations() {
+                return None;
+            }
+            return Some(self.decode_word(self.word_index(from).unwrap() + n, from.len()));
+        }
+        // the words of each length follow the words of the shorter lengths
+        let mut index = n.clone();
+        for pwdlen in self.minlen..=self.maxlen {
+            let len_combs = self.len_combinations(pwdlen);
+            if index < len_combs {
+                return Some(self.decode_word(index, pwdlen));
+            }
+            index -= &len_combs;
+        }
+        None
+    }
+
+    fn mask(&self) -> &str {
+        &self.mask_text
+    }
+
+    fn kind(&self) -> GeneratorKind {
+        GeneratorKind::Charset
     }
 }
 
@@ -183,11 +1040,41 @@ impl<'a> WordlistGenerator {
         wordlists_fnames: &[&'a str],
         custom_charsets: &[&'a str],
     ) -> BoxResult<WordlistGenerator> {
+        Self::with_options(
+            mask,
+            wordlists_fnames,
+            custom_charsets,
+            &GeneratorOptions::default(),
+        )
+    }
+
+    pub fn with_options(
+        mask: Vec<MaskOp>,
+        wordlists_fnames: &[&'a str],
+        custom_charsets: &[&'a str],
+        options: &GeneratorOptions,
+    ) -> BoxResult<WordlistGenerator> {
+        let used_wordlists = Mask::new(mask.clone()).wordlist_refs();
         let mut wordlists_data = vec![];
-        for fname in wordlists_fnames.iter() {
-            wordlists_data.push(Rc::new(Wordlist::from_file(fname)?));
+        for (idx, fname) in wordlists_fnames.iter().enumerate() {
+            let wordlist = options.load_wordlist(fname)?;
+            // blank lines are skipped on load, a wordlist of only those has no words to generate
+            if wordlist.is_empty() && used_wordlists.contains(&idx) {
+                bail!("wordlist {} has no words (blank lines are skipped)", fname);
+            }
+            wordlists_data.push(wordlist);
         }
+        Self::from_wordlists(mask, &wordlists_data, custom_charsets, options)
+    }
 
+    /// builds the generator of a mask from already loaded wordlists, indexed by the mask
+    /// wordlist positions
+    fn from_wordlists(
+        mask: Vec<MaskOp>,
+        wordlists_data: &[Rc<Wordlist>],
+        custom_charsets: &[&'a str],
+        options: &GeneratorOptions,
+    ) -> BoxResult<WordlistGenerator> {
         let items: Vec<WordlistItem> = mask
             .iter()
             .map(|op| match op {
@@ -195,18 +1082,146 @@ impl<'a> WordlistGenerator {
                     WordlistItem::Charset(Charset::from_chars(vec![*ch as u8].as_ref()))
                 }
                 MaskOp::BuiltinCharset(ch) => WordlistItem::Charset(Charset::from_symbol(*ch)),
+                MaskOp::BuiltinCharsetExcept(ch, except) => {
+                    WordlistItem::Charset(Charset::from_symbol_except(*ch, except))
+                }
                 MaskOp::CustomCharset(idx) => {
-                    WordlistItem::Charset(Charset::from_chars(custom_charsets[*idx].as_bytes()))
+                    WordlistItem::Charset(Charset::from_custom(custom_charsets[*idx]))
                 }
                 MaskOp::Wordlist(idx) => WordlistItem::Wordlist(Rc::clone(&wordlists_data[*idx])),
             })
             .collect();
 
-        Ok(WordlistGenerator { mask, items })
+        // the words are built in a fixed buffer with their newline
+        let max_word_len: usize = items
+            .iter()
+            .map(|item| match item {
+                WordlistItem::Charset(_) => 1,
+                WordlistItem::Wordlist(wordlist) => wordlist.max_word_len(),
+            })
+            .sum();
+        if max_word_len >= MAX_WORD_SIZE {
+            bail!(
+                "the longest word of the mask is {} bytes, exceeding the max word size of {}",
+                max_word_len,
+                MAX_WORD_SIZE - 1
+            );
+        }
+
+        let wordlist_positions = mask
+            .iter()
+            .filter(|op| matches!(op, MaskOp::Wordlist(_)))
+            .count();
+        if options.min_distinct_words > wordlist_positions {
+            bail!(
+                "cannot use {} distinct words, the mask has only {} wordlist positions",
+                options.min_distinct_words,
+                wordlist_positions
+            );
+        }
+
+        let has_newlines = items.iter().any(|item| match item {
+            WordlistItem::Charset(charset) => charset.contains(b'\n'),
+            WordlistItem::Wordlist(wordlist) => wordlist.has_newlines(),
+        });
+        Ok(WordlistGenerator {
+            mask_text: Mask::new(mask.clone()).to_string(),
+            mask,
+            items,
+            has_newlines,
+            unordered_unique: options.unordered_unique,
+            no_repeat: options.no_repeat,
+            min_distinct_words: options.min_distinct_words,
+        })
+    }
+
+    /// returns true iff `word` repeats a word on its wordlist positions according to
+    /// `self.no_repeat`. `segments` is reused for the (start, len) of the previous words
+    #[inline]
+    fn has_repeated_word(
+        &self,
+        positions: &[Position],
+        word: &[u8],
+        segments: &mut Vec<(usize, usize)>,
+    ) -> bool {
+        segments.clear();
+        let mut start = 0;
+        for position in positions.iter() {
+            let len = match position {
+                Position::CharsetPos { .. } => {
+                    start += 1;
+                    continue;
+                }
+                Position::WordlistPos { idx, .. } => idx.current_len(),
+            };
+            let current = &word[start..start + len];
+            let previous = match self.no_repeat {
+                NoRepeat::Any => &segments[..],
+                _ => &segments[segments.len().saturating_sub(1)..],
+            };
+            if previous
+                .iter()
+                .any(|&(prev_start, prev_len)| &word[prev_start..prev_start + prev_len] == current)
+            {
+                return true;
+            }
+            segments.push((start, len));
+            start += len;
+        }
+        false
+    }
+
+    /// returns the number of distinct words on the wordlist positions of `word`, compared by
+    /// bytes. `segments` is reused for the (start, len) of the distinct words
+    #[inline]
+    fn distinct_words(
+        &self,
+        positions: &[Position],
+        word: &[u8],
+        segments: &mut Vec<(usize, usize)>,
+    ) -> usize {
+        segments.clear();
+        let mut start = 0;
+        for position in positions.iter() {
+            let len = match position {
+                Position::CharsetPos { .. } => {
+                    start += 1;
+                    continue;
+                }
+                Position::WordlistPos { idx, .. } => idx.current_len(),
+            };
+            let current = &word[start..start + len];
+            if !segments
+                .iter()
+                .any(|&(prev_start, prev_len)| &word[prev_start..prev_start + prev_len] == current)
+            {
+                segments.push((start, len));
+            }
+            start += len;
+        }
+        segments.len()
+    }
+
+    /// returns true iff every wordlist used on multiple positions has non-decreasing word
+    /// indices from left to right
+    #[inline]
+    fn is_unordered_unique(&self, positions: &[Position]) -> bool {
+        let mut last_indices = [0usize; 9];
+        for (op, position) in self.mask.iter().zip(positions.iter()) {
+            if let (MaskOp::Wordlist(wordlist_idx), Position::WordlistPos { idx, .. }) =
+                (op, position)
+            {
+                let word_idx = idx.current_index();
+                if word_idx < last_indices[*wordlist_idx] {
+                    return false;
+                }
+                last_indices[*wordlist_idx] = word_idx;
+            }
+        }
+        true
     }
 
-    #[allow(clippy::borrowed_box)]
-    fn gen_words<'b>(&self, out: &mut Box<dyn Write + 'b>) -> Result<(), std::io::Error> {
+    fn gen_words<'b>(&self, out: &mut WordsWriter<'b>) -> Result<(), std::io::Error> {
         let mut buf = StackBuf::new();
 
         let mut word_buf = [b'\n'; MAX_WORD_SIZE];
@@ -240,13 +1255,26 @@ impl<'a> WordlistGenerator {
         let mut word_len = min_word.len();
 
         word[..word_len].copy_from_slice(&min_word);
+        let mut segments = Vec::with_capacity(positions.len());
 
         'outer_loop: loop {
             if buf.pos() + word_len >= buf.len() {
                 out.write_all(buf.getdata())?;
                 buf.clear();
             }
-            buf.write(&word[..word_len]);
+            if (!self.unordered_unique || self.is_unordered_unique(&positions))
+                && (self.no_repeat == NoRepeat::Off
+                    || !self.has_repeated_word(&positions, word, &mut segments))
+                && (self.min_distinct_words <= 1
+                    || self.distinct_words(&positions, word, &mut segments)
+                        >= self.min_distinct_words)
+            {
+                if self.has_newlines {
+                    out.write_words(&word[..word_len], word_len - 1)?;
+                } else {
+                    buf.write(&word[..word_len]);
+                }
+            }
 
             let mut pos = word_len - 2;
 
@@ -261,13 +1289,8 @@ impl<'a> WordlistGenerator {
                             continue 'outer_loop;
                         }
 
-                        // on debug build we have overflow checks - but we dont care
-                        // from overflow in this case its the rightmost position
-                        if cfg!(debug_assertions) && pos == 0 {
-                            pos = 0;
-                        } else {
-                            pos -= 1;
-                        }
+                        // only the leftmost position moves below 0, after which the loop ends
+                        pos = pos.saturating_sub(1);
                     }
                     Position::WordlistPos { wordlist, idx } => {
                         let finished;
@@ -307,67 +1330,617 @@ impl<'a> WordlistGenerator {
                             word_len = (word_len as isize + offset) as usize;
                         }
 
-                        // copy the next word to the adjusted buffer
+                        // copy the next word to the adjusted buffer, pos is the last byte of
+                        // the word so the preceding positions always fit it
+                        assert!(pos + 1 >= wlen, "word of {} bytes ends at {}", wlen, pos);
                         word[pos + 1 - wlen..=pos].copy_from_slice(wordlist_word);
 
                         if !finished {
                             continue 'outer_loop;
                         }
 
-                        // on debug build we have overflow checks - but we dont care
-                        // from overflow in this case its the rightmost position
-                        if cfg!(debug_assertions) && pos < wlen {
-                            pos = 0;
-                        } else {
-                            pos -= wlen;
+                        // only the leftmost position moves below 0, after which the loop ends
+                        pos = pos.saturating_sub(wlen);
+                    }
+                }
+            }
+
+            // done
+            break;
+        }
+        out.write_all(buf.getdata())?;
+        Ok(())
+    }
+
+    /// a wordlist of n words repeated k times yields multisets of size k - C(n+k-1, k)
+    fn unordered_unique_combinations(&self) -> BigUint {
+        let mut wordlist_repeats = [0usize; 9];
+        let mut wordlist_lens = [0usize; 9];
+        let mut combs = 1.to_biguint().unwrap();
+        for (op, item) in self.mask.iter().zip(self.items.iter()) {
+            match (op, item) {
+                (MaskOp::Wordlist(idx), WordlistItem::Wordlist(wl)) => {
+                    wordlist_repeats[*idx] += 1;
+                    wordlist_lens[*idx] = wl.len();
+                }
+                (_, WordlistItem::Charset(c)) => combs *= c.len,
+                _ => unreachable!("wordlist item must match its mask op"),
+            }
+        }
+
+        for (&k, &n) in wordlist_repeats.iter().zip(wordlist_lens.iter()) {
+            // C(n+k-1, k) = prod_{i=1..k} (n+i-1) / i, exact division at each step
+            let mut multisets = 1.to_biguint().unwrap();
+            for i in 1..=k {
+                multisets = multisets * (n + i - 1) / i;
+            }
+            combs *= multisets;
+        }
+        combs
+    }
+}
+
+impl WordGenerator for WordlistGenerator {
+    /// generates all words into the output buffer `out`
+    fn gen<'b>(&self, out: &mut WordsWriter<'b>) -> Result<(), std::io::Error> {
+        self.gen_words(out)?;
+        Ok(())
+    }
+
+    fn combinations(&self) -> BigUint {
+        if self.unordered_unique {
+            return self.unordered_unique_combinations();
+        }
+        self.items
+            .iter()
+            .map(|item| match item {
+                WordlistItem::Wordlist(wl) => wl.len().to_biguint().unwrap(),
+                WordlistItem::Charset(c) => c.len.to_biguint().unwrap(),
+            })
+            .product()
+    }
+
+    fn is_exact_count(&self) -> bool {
+        self.no_repeat == NoRepeat::Off && self.min_distinct_words <= 1
+    }
+
+    fn nth_word(&self, n: &BigUint) -> Option<Vec<u8>> {
+        // skipped words shift the index of every following word
+        if self.unordered_unique || !self.is_exact_count() || n >= &self.combinations() {
+            return None;
+        }
+        let mut index = n.clone();
+        let mut parts = Vec::with_capacity(self.items.len());
+        for item in self.items.iter().rev() {
+            let len = match item {
+                WordlistItem::Wordlist(wl) => wl.len(),
+                WordlistItem::Charset(c) => c.len,
+            };
+            let rank = usize::try_from(&index % len).unwrap();
+            index /= len;
+            parts.push(match item {
+                WordlistItem::Wordlist(wl) => wl.word(rank).unwrap().to_vec(),
+                WordlistItem::Charset(c) => vec![c.iter().nth(rank).unwrap()],
+            });
+        }
+        Some(parts.into_iter().rev().flatten().collect())
+    }
+
+    fn mask(&self) -> &str {
+        &self.mask_text
+    }
+
+    fn kind(&self) -> GeneratorKind {
+        GeneratorKind::Wordlist
+    }
+}
+
+impl CombineGenerator {
+    pub fn new(
+        wordlist_fname: &str,
+        total_minlen: usize,
+        total_maxlen: usize,
+        options: &GeneratorOptions,
+    ) -> BoxResult<CombineGenerator> {
+        if total_minlen == 0 || total_minlen > total_maxlen {
+            bail!(
+                "total min length must be between 1 and the total max length, got {}..{}",
+                total_minlen,
+                total_maxlen
+            );
+        }
+        if total_maxlen >= MAX_WORD_SIZE {
+            bail!("total max length must be less than {}", MAX_WORD_SIZE);
+        }
+
+        let wordlist = options.load_wordlist(wordlist_fname)?;
+        if wordlist.is_empty() {
+            bail!("wordlist {} has no words", wordlist_fname);
+        }
+        Ok(CombineGenerator {
+            total_minlen,
+            total_maxlen,
+            wordlist,
+        })
+    }
+
+    /// appends every word to the first `len` bytes of `word`, writing the words within the length
+    /// range and recursing to append more words
+    fn gen_combined<'b>(
+        &self,
+        word: &mut [u8; MAX_WORD_SIZE],
+        len: usize,
+        buf: &mut StackBuf,
+        out: &mut WordsWriter<'b>,
+    ) -> Result<(), std::io::Error> {
+        // words are sorted by length so no later word fits once one is too long
+        for subword in self.wordlist.iter() {
+            let newlen = len + subword.len();
+            if newlen > self.total_maxlen {
+                break;
+            }
+            word[len..newlen].copy_from_slice(subword);
+
+            if newlen >= self.total_minlen && self.wordlist.has_newlines() {
+                // the following words overwrite the newline
+                word[newlen] = b'\n';
+                out.write_words(&word[..=newlen], newlen)?;
+            } else if newlen >= self.total_minlen {
+                if buf.pos() + newlen + 1 > buf.len() {
+                    out.write_all(buf.getdata())?;
+                    buf.clear();
+                }
+                buf.write(&word[..newlen]);
+                buf.write(b"\n");
+            }
+            self.gen_combined(word, newlen, buf, out)?;
+        }
+        Ok(())
+    }
+}
+
+impl WordGenerator for CombineGenerator {
+    fn gen<'b>(&self, out: &mut WordsWriter<'b>) -> Result<(), std::io::Error> {
+        let mut buf = StackBuf::new();
+        let mut word = [0u8; MAX_WORD_SIZE];
+        self.gen_combined(&mut word, 0, &mut buf, out)?;
+        out.write_all(buf.getdata())?;
+        Ok(())
+    }
+
+    fn combinations(&self) -> BigUint {
+        // len2combs[i] is the number of concatenations with a total length of i
+        let len_counts = self.wordlist.len_counts();
+        let mut len2combs: Vec<BigUint> = vec![0.to_biguint().unwrap(); self.total_maxlen + 1];
+        len2combs[0] = 1.to_biguint().unwrap();
+        for total in 1..=self.total_maxlen {
+            for (len, count) in len_counts.iter().filter(|(len, _)| *len <= total) {
+                let combs = &len2combs[total - len] * *count;
+                len2combs[total] += combs;
+            }
+        }
+        len2combs[self.total_minlen..].iter().sum()
+    }
+
+    fn mask(&self) -> &str {
+        ""
+    }
+
+    fn kind(&self) -> GeneratorKind {
+        GeneratorKind::Combine
+    }
+}
+
+impl PositionalGenerator {
+    pub fn new(
+        mask: Vec<MaskOp>,
+        minlen: Option<usize>,
+        maxlen: Option<usize>,
+        custom_charsets: &[&str],
+    ) -> BoxResult<PositionalGenerator> {
+        // all the non empty subsets by default
+        let (minlen, maxlen) = resolve_lengths(minlen.or(Some(1)), maxlen, mask.len())?;
+        let full = CharsetGenerator::new(mask.to_vec(), None, None, custom_charsets)?;
+        let lens = full.charsets.iter().map(|charset| charset.len()).collect();
+
+        Ok(PositionalGenerator {
+            mask_text: Mask::new(mask.clone()).to_string(),
+            mask,
+            minlen,
+            maxlen,
+            custom_charsets: custom_charsets.iter().map(|&c| c.to_owned()).collect(),
+            lens,
+        })
+    }
+}
+
+impl WordGenerator for PositionalGenerator {
+    fn gen<'b>(&self, out: &mut WordsWriter<'b>) -> Result<(), std::io::Error> {
+        let custom_charsets: Vec<&str> = self.custom_charsets.iter().map(|c| c.as_str()).collect();
+        for size in self.minlen..=self.maxlen {
+            for positions in (0..self.mask.len()).combinations(size) {
+                let mask = positions
+                    .iter()
+                    .map(|&pos| self.mask[pos].clone())
+                    .collect();
+                // the mask is validated on new so this cannot fail
+                let generator = CharsetGenerator::new(mask, None, None, &custom_charsets)
+                    .map_err(|e| std::io::Error::other(e.to_string()))?;
+                generator.gen(out)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn combinations(&self) -> BigUint {
+        // size2combs[k] is the sum of the words of all the subsets of k positions
+        let mut size2combs: Vec<BigUint> = vec![0.to_biguint().unwrap(); self.lens.len() + 1];
+        size2combs[0] = 1.to_biguint().unwrap();
+        for (i, &len) in self.lens.iter().enumerate() {
+            for size in (1..=i + 1).rev() {
+                let combs = &size2combs[size - 1] * len;
+                size2combs[size] += combs;
+            }
+        }
+        size2combs[self.minlen..=self.maxlen].iter().sum()
+    }
+
+    fn mask(&self) -> &str {
+        &self.mask_text
+    }
+
+    fn kind(&self) -> GeneratorKind {
+        GeneratorKind::Positional
+    }
+}
+
+impl ModelGenerator {
+    pub fn new(
+        mask: Vec<MaskOp>,
+        custom_charsets: &[&str],
+        model: &CharModel,
+    ) -> BoxResult<ModelGenerator> {
+        let mut chars = vec![];
+        let mut costs = vec![];
+        for op in mask.iter() {
+            let charset = match op {
+                MaskOp::Char(ch) => Charset::from_chars(&[*ch as u8]),
+                MaskOp::BuiltinCharset(ch) => Charset::from_symbol(*ch),
+                MaskOp::BuiltinCharsetExcept(ch, except) => {
+                    Charset::from_symbol_except(*ch, except)
+                }
+                MaskOp::CustomCharset(idx) => Charset::from_custom(custom_charsets[*idx]),
+                MaskOp::Wordlist(_) => bail!("ordering by a model supports charsets masks only"),
+            };
+            let mut pos_chars = charset.chars_sorted();
+            // stable sort keeps ties in the charset order
+            pos_chars.sort_by_key(|&ch| OrderedFloat(model.cost(ch)));
+            costs.push(pos_chars.iter().map(|&ch| model.cost(ch)).collect());
+            chars.push(pos_chars);
+        }
+        Ok(ModelGenerator {
+            mask_text: Mask::new(mask.clone()).to_string(),
+            mask,
+            chars,
+            costs,
+        })
+    }
+}
+
+impl WordGenerator for ModelGenerator {
+    /// best first traversal of the keyspace. each word is the indices of its chars on the
+    /// sorted positions, its successors increment one index at or after the last incremented
+    /// position - so every word is reached exactly once and never before a more probable one.
+    /// note: the queue grows by up to the mask length per emitted word, so memory grows with
+    /// the number of words generated
+    fn gen<'b>(&self, out: &mut WordsWriter<'b>) -> Result<(), std::io::Error> {
+        let mut buf = StackBuf::new();
+        let mut word = vec![b'\n'; self.chars.len() + 1];
+
+        // summed per word rather than updated incrementally, so equal words costs stay equal
+        let word_cost = |indices: &[usize]| -> f64 {
+            indices
+                .iter()
+                .enumerate()
+                .map(|(pos, &idx)| self.costs[pos][idx])
+                .sum()
+        };
+        let first: Vec<usize> = vec![0; self.chars.len()];
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((OrderedFloat(word_cost(&first)), first, 0)));
+
+        while let Some(Reverse((_, indices, last_pos))) = queue.pop() {
+            for (pos, &idx) in indices.iter().enumerate() {
+                word[pos] = self.chars[pos][idx];
+            }
+            if buf.pos() + word.len() > buf.len() {
+                out.write_words(buf.getdata(), self.chars.len())?;
+                buf.clear();
+            }
+            buf.write(&word);
+
+            for pos in last_pos..indices.len() {
+                if indices[pos] + 1 < self.chars[pos].len() {
+                    let mut next = indices.clone();
+                    next[pos] += 1;
+                    queue.push(Reverse((OrderedFloat(word_cost(&next)), next, pos)));
+                }
+            }
+        }
+        out.write_words(buf.getdata(), self.chars.len())?;
+        Ok(())
+    }
+
+    fn combinations(&self) -> BigUint {
+        let lens: Vec<_> = self.chars.iter().map(|chars| chars.len()).collect();
+        charsets_combinations(&lens, lens.len(), lens.len())
+    }
+
+    fn mask(&self) -> &str {
+        &self.mask_text
+    }
+
+    fn kind(&self) -> GeneratorKind {
+        GeneratorKind::Model
+    }
+}
+
+impl ZipGenerator {
+    pub fn new(
+        mask: Vec<MaskOp>,
+        wordlists_fnames: &[&str],
+        custom_charsets: &[&str],
+        options: &WordlistOptions,
+    ) -> BoxResult<ZipGenerator> {
+        if options.is_dedup() {
+            bail!("cannot dedup zipped wordlists, their lines would no longer be paired");
+        }
+        let used_wordlists = Mask::new(mask.clone()).wordlist_refs();
+        let mut lines = vec![];
+        for (idx, fname) in wordlists_fnames.iter().enumerate() {
+            let mut wordlist_lines = vec![];
+            if used_wordlists.contains(&idx) {
+                for path in wordlist_files(fname, options)? {
+                    for line in options.records(open_input(path)?) {
+                        wordlist_lines.push(options.parse_line(&line?).to_vec());
+                    }
+                }
+            }
+            lines.push(wordlist_lines);
+        }
+
+        let items: Vec<ZipItem> = mask
+            .iter()
+            .map(|op| {
+                let charset = match op {
+                    MaskOp::Char(ch) => Charset::from_chars(&[*ch as u8]),
+                    MaskOp::BuiltinCharset(ch) => Charset::from_symbol(*ch),
+                    MaskOp::BuiltinCharsetExcept(ch, except) => {
+                        Charset::from_symbol_except(*ch, except)
+                    }
+                    MaskOp::CustomCharset(idx) => Charset::from_custom(custom_charsets[*idx]),
+                    MaskOp::Wordlist(idx) => return ZipItem::Wordlist(*idx),
+                };
+                ZipItem::Chars(charset.iter().collect())
+            })
+            .collect();
+
+        let max_word_len: usize = items
+            .iter()
+            .map(|item| match item {
+                ZipItem::Chars(_) => 1,
+                ZipItem::Wordlist(idx) => lines[*idx].iter().map(Vec::len).max().unwrap_or(0),
+            })
+            .sum();
+        if max_word_len >= MAX_WORD_SIZE {
+            bail!(
+                "the longest word of the mask is {} bytes, exceeding the max word size of {}",
+                max_word_len,
+                MAX_WORD_SIZE - 1
+            );
+        }
+
+        let steps = used_wordlists
+            .iter()
+            .map(|&idx| lines[idx].len())
+            .min()
+            .unwrap_or(0);
+        let has_newlines = items.iter().any(|item| match item {
+            ZipItem::Chars(chars) => chars.contains(&b'\n'),
+            ZipItem::Wordlist(idx) => lines[*idx].iter().any(|line| line.contains(&b'\n')),
+        });
+        Ok(ZipGenerator {
+            mask_text: Mask::new(mask.clone()).to_string(),
+            mask,
+            items,
+            lines,
+            steps,
+            has_newlines,
+        })
+    }
+}
+
+impl WordGenerator for ZipGenerator {
+    fn gen<'b>(&self, out: &mut WordsWriter<'b>) -> Result<(), std::io::Error> {
+        let mut buf = StackBuf::new();
+        let mut word = Vec::with_capacity(MAX_WORD_SIZE);
+        // the index of the current char of every charset position
+        let mut indices = vec![0; self.items.len()];
+
+        for step in 0..self.steps {
+            'words: loop {
+                word.clear();
+                for (item, &idx) in self.items.iter().zip(indices.iter()) {
+                    match item {
+                        ZipItem::Chars(chars) => word.push(chars[idx]),
+                        ZipItem::Wordlist(wordlist) => {
+                            word.extend_from_slice(&self.lines[*wordlist][step])
+                        }
+                    }
+                }
+                word.push(b'\n');
+                if self.has_newlines {
+                    out.write_words(&word, word.len() - 1)?;
+                } else {
+                    if buf.pos() + word.len() >= buf.len() {
+                        out.write_all(buf.getdata())?;
+                        buf.clear();
+                    }
+                    buf.write(&word);
+                }
+
+                // advance the charset positions from right to left
+                for (item, idx) in self.items.iter().zip(indices.iter_mut()).rev() {
+                    if let ZipItem::Chars(chars) = item {
+                        *idx += 1;
+                        if *idx < chars.len() {
+                            continue 'words;
                         }
+                        *idx = 0;
                     }
                 }
+                break;
             }
-
-            // done
-            break;
         }
         out.write_all(buf.getdata())?;
         Ok(())
     }
-}
-
-impl<'a> WordGenerator for WordlistGenerator {
-    /// generates all words into the output buffer `out`
-    fn gen<'b>(&self, out: &mut Box<dyn Write + 'b>) -> Result<(), std::io::Error> {
-        self.gen_words(out)?;
-        Ok(())
-    }
 
     fn combinations(&self) -> BigUint {
         self.items
             .iter()
             .map(|item| match item {
-                WordlistItem::Wordlist(wl) => wl.len().to_biguint().unwrap(),
-                WordlistItem::Charset(c) => c.len.to_biguint().unwrap(),
+                ZipItem::Chars(chars) => chars.len(),
+                ZipItem::Wordlist(_) => 1,
             })
-            .product()
+            .fold(self.steps.to_biguint().unwrap(), |combs, len| combs * len)
+    }
+
+    fn mask(&self) -> &str {
+        &self.mask_text
+    }
+
+    fn kind(&self) -> GeneratorKind {
+        GeneratorKind::Zip
+    }
+}
+
+impl DeltaGenerator {
+    /// `old_lines` is the number of lines of the wordlist `fname` already generated
+    pub fn new(
+        mask: Vec<MaskOp>,
+        fname: &str,
+        custom_charsets: &[&str],
+        old_lines: usize,
+        options: &GeneratorOptions,
+    ) -> BoxResult<DeltaGenerator> {
+        let wordlist_options = &options.wordlist_options;
+        // referenced by the parts masks as ?w1, ?w2 and ?w3
+        let wordlists = [
+            options.load_wordlist(fname)?,
+            Rc::new(Wordlist::from_file_lines(
+                fname,
+                wordlist_options,
+                0..old_lines,
+            )?),
+            Rc::new(Wordlist::from_file_lines(
+                fname,
+                wordlist_options,
+                old_lines..usize::MAX,
+            )?),
+        ];
+        let positions = mask
+            .iter()
+            .filter(|op| matches!(op, MaskOp::Wordlist(_)))
+            .count();
+
+        let mut parts = vec![];
+        for first_new in 0..positions {
+            // wordlists are empty when nothing was appended or everything is new
+            if wordlists[2].is_empty() || (first_new > 0 && wordlists[1].is_empty()) {
+                continue;
+            }
+            let mut position = 0;
+            let part_mask = mask
+                .iter()
+                .map(|op| match op {
+                    MaskOp::Wordlist(_) => {
+                        position += 1;
+                        MaskOp::Wordlist(match (position - 1).cmp(&first_new) {
+                            std::cmp::Ordering::Less => 1,
+                            std::cmp::Ordering::Equal => 2,
+                            std::cmp::Ordering::Greater => 0,
+                        })
+                    }
+                    op => op.clone(),
+                })
+                .collect();
+            parts.push(WordlistGenerator::from_wordlists(
+                part_mask,
+                &wordlists,
+                custom_charsets,
+                &GeneratorOptions::default(),
+            )?);
+        }
+
+        Ok(DeltaGenerator {
+            mask_text: Mask::new(mask).to_string(),
+            parts,
+        })
+    }
+}
+
+impl WordGenerator for DeltaGenerator {
+    fn gen<'b>(&self, out: &mut WordsWriter<'b>) -> Result<(), std::io::Error> {
+        for part in self.parts.iter() {
+            part.gen(out)?;
+        }
+        Ok(())
+    }
+
+    fn combinations(&self) -> BigUint {
+        self.parts.iter().map(|part| part.combinations()).sum()
+    }
+
+    fn mask(&self) -> &str {
+        &self.mask_text
+    }
+
+    fn kind(&self) -> GeneratorKind {
+        GeneratorKind::Delta
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
     use std::io::{Cursor, Write};
+    use std::rc::Rc;
+    use std::{env, fs};
+
+    use itertools::Itertools;
 
     use num_bigint::{BigUint, ToBigUint};
 
-    use crate::generators::get_word_generator;
-    use crate::mask::parse_mask;
+    use crate::charsets::Charset;
+    use crate::generators::{
+        get_word_generator, get_word_generator_with_options, mask_combinations, GeneratorKind,
+        GeneratorOptions, NoRepeat,
+    };
+    use crate::mask::{parse_mask, MaskOp};
     use crate::test_util::wordlist_fname;
+    use crate::wordlists::{Wordlist, WordlistOptions};
 
-    use super::{CharsetGenerator, WordGenerator};
+    use super::{
+        charsets_combinations, CharsetGenerator, CombineGenerator, ModelGenerator,
+        PositionalGenerator, WordGenerator, WordlistGenerator, WordlistItem, ZipGenerator,
+    };
+    use crate::model::CharModel;
+    use crate::output::{OutputOptions, WordsWriter};
 
     #[test]
     fn test_gen_words_single_digit() {
         let mask = parse_mask("?d").unwrap();
-        let word_gen = CharsetGenerator::new(mask.to_vec(), None, None, &vec![]).unwrap();
+        let word_gen = CharsetGenerator::new(mask.to_vec(), None, None, &[]).unwrap();
 
         assert_eq!(word_gen.mask, mask);
         assert_eq!(word_gen.minlen, 1);
@@ -384,7 +1957,7 @@ mod tests {
     #[test]
     fn test_gen_upper_lower_1_4() {
         let mask = parse_mask("?u?l?u?l").unwrap();
-        let word_gen = CharsetGenerator::new(mask.to_vec(), Some(1), None, &vec![]).unwrap();
+        let word_gen = CharsetGenerator::new(mask.to_vec(), Some(1), None, &[]).unwrap();
 
         assert_eq!(word_gen.mask, mask);
         assert_eq!(word_gen.minlen, 1);
@@ -398,7 +1971,7 @@ mod tests {
     #[test]
     fn test_gen_pwd_upper_lower_year_1_4() {
         let mask = parse_mask("pwd?u?l201?1").unwrap();
-        let word_gen = CharsetGenerator::new(mask.to_vec(), Some(1), None, &vec!["56789"]).unwrap();
+        let word_gen = CharsetGenerator::new(mask.to_vec(), Some(1), None, &["56789"]).unwrap();
 
         assert_eq!(word_gen.mask, mask);
         assert_eq!(word_gen.minlen, 1);
@@ -409,6 +1982,382 @@ mod tests {
         assert_gen(Box::new(word_gen), "upper-lower-year-1-4.txt");
     }
 
+    #[test]
+    fn test_charset_iter() {
+        let alphabet = Charset::from_custom("abc123");
+        let new_gen = |mask: &str, minlen, maxlen, custom_charsets: &[&str]| {
+            CharsetGenerator::new(parse_mask(mask).unwrap(), minlen, maxlen, custom_charsets)
+                .unwrap()
+        };
+        for word_gen in [
+            new_gen("?d", None, None, &[]),
+            new_gen("?u?l?u?l", Some(1), None, &[]),
+            new_gen("pwd?u?l201?1", Some(1), None, &["56789"]),
+            new_gen("?d?1?d", Some(2), Some(3), &["ab"]),
+            new_gen("?b?b", None, None, &[]),
+            new_gen("?l?d?d", None, None, &[])
+                .with_range(b"b98", b"c03")
+                .unwrap(),
+            new_gen("?l?d?d", None, None, &[])
+                .with_range(b"q42", b"q42")
+                .unwrap(),
+            new_gen("?u?l?d", Some(1), None, &[]).with_one_per_length(),
+            new_gen("?l?d", Some(1), None, &[])
+                .with_max_per_length(12)
+                .unwrap(),
+            new_gen("?1?d", None, None, &["ab"])
+                .with_complement(&alphabet)
+                .unwrap(),
+        ] {
+            let expected = gen_to_bytes(&word_gen);
+            let words: Vec<_> = word_gen.iter().collect();
+            assert_eq!(
+                usize::try_from(word_gen.combinations()).unwrap(),
+                words.len(),
+                "{}",
+                word_gen.mask()
+            );
+            let mut buf = vec![];
+            for word in words {
+                buf.extend(word);
+                buf.push(b'\n');
+            }
+            assert_eq!(buf, expected, "{}", word_gen.mask());
+        }
+
+        let word_gen = new_gen("?d?d", None, None, &[]);
+        let filtered: Vec<_> = word_gen.iter().filter(|w| w[0] == w[1]).collect();
+        assert_eq!(filtered.len(), 10);
+        assert_eq!(filtered[3], b"33");
+    }
+
+    #[test]
+    fn test_gen_bytes_wraparound() {
+        let mask = parse_mask("?b?b").unwrap();
+        let word_gen = CharsetGenerator::new(mask, None, None, &[]).unwrap();
+        let buf = gen_to_bytes(&word_gen);
+
+        // every 2 byte value exactly once, in order
+        assert_eq!(buf.len(), 256 * 256 * 3);
+        for (i, word) in buf.chunks(3).enumerate() {
+            assert_eq!(word, [(i / 256) as u8, (i % 256) as u8, b'\n']);
+        }
+    }
+
+    #[test]
+    fn test_gen_builtin_charset_except() {
+        let word_gen = get_word_generator("?d{-0}?d{-0}", None, None, &[], &[]).unwrap();
+        assert_eq!(word_gen.combinations(), 81.to_biguint().unwrap());
+        let words = gen_to_string(word_gen);
+        assert_eq!(words.lines().count(), 81);
+        assert!(words.starts_with("11\n12\n"));
+        assert!(!words.contains('0'));
+
+        let word_gen = get_word_generator("?d{-0}", Some(1), None, &[], &[]).unwrap();
+        assert_eq!(word_gen.combinations(), 9.to_biguint().unwrap());
+        assert_eq!(gen_to_string(word_gen), "1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+
+        // a dash after a charset is a literal dash
+        let word_gen = get_word_generator("20?d?d-01-01", None, None, &[], &[]).unwrap();
+        let words = gen_to_string(word_gen);
+        assert_eq!(words.lines().count(), 100);
+        assert!(words.starts_with("2000-01-01\n2001-01-01\n"));
+    }
+
+    #[test]
+    fn test_gen_range() {
+        let mask = parse_mask("?l?d?d").unwrap();
+        let word_gen = CharsetGenerator::new(mask.clone(), None, None, &[])
+            .unwrap()
+            .with_range(b"a98", b"b01")
+            .unwrap();
+        assert_eq!(word_gen.combinations(), 4.to_biguint().unwrap());
+        assert_eq!(gen_to_string(Box::new(word_gen)), "a98\na99\nb00\nb01\n");
+
+        // the whole keyspace and a single word
+        let word_gen = CharsetGenerator::new(mask.clone(), None, None, &[])
+            .unwrap()
+            .with_range(b"a00", b"z99")
+            .unwrap();
+        assert_eq!(word_gen.combinations(), 2600.to_biguint().unwrap());
+        assert_eq!(gen_to_string(Box::new(word_gen)).lines().count(), 2600);
+        let options = GeneratorOptions::new().range(Some(("q42".to_owned(), "q42".to_owned())));
+        let word_gen =
+            get_word_generator_with_options("?l?d?d", None, None, &[], &[], &options).unwrap();
+        assert_eq!(gen_to_string(word_gen), "q42\n");
+
+        let new_gen = || CharsetGenerator::new(mask.clone(), None, None, &[]).unwrap();
+        assert!(new_gen().with_range(b"b00", b"a00").is_err());
+        assert!(new_gen().with_range(b"a0", b"a00").is_err());
+        assert!(new_gen().with_range(b"A00", b"a00").is_err());
+        assert!(CharsetGenerator::new(mask, Some(1), None, &[])
+            .unwrap()
+            .with_range(b"a00", b"a01")
+            .is_err());
+        let wordlist1 = wordlist_fname("wordlist1.txt");
+        let wordlists = vec![wordlist1.to_str().unwrap()];
+        assert!(
+            get_word_generator_with_options("?w1", None, None, &[], &wordlists, &options).is_err()
+        );
+    }
+
+    #[test]
+    fn test_gen_min_classes() {
+        let classes = |word: &str| {
+            let classes: [fn(&u8) -> bool; 4] = [
+                u8::is_ascii_lowercase,
+                u8::is_ascii_uppercase,
+                u8::is_ascii_digit,
+                |ch| !ch.is_ascii_alphanumeric(),
+            ];
+            classes
+                .iter()
+                .filter(|class| word.as_bytes().iter().any(class))
+                .count()
+        };
+        for mask in ["?1?1?d", "?1?d?1?1", "?l?u?d?s"] {
+            let all_words = gen_to_string(
+                get_word_generator_with_options(
+                    mask,
+                    Some(1),
+                    None,
+                    &["aB!"],
+                    &[],
+                    &GeneratorOptions::new(),
+                )
+                .unwrap(),
+            );
+            for min_classes in 1..=4 {
+                let expected: Vec<_> = all_words
+                    .lines()
+                    .filter(|word| classes(word) >= min_classes)
+                    .collect();
+                let options = GeneratorOptions::new().min_classes(min_classes);
+                let word_gen =
+                    get_word_generator_with_options(mask, Some(1), None, &["aB!"], &[], &options)
+                        .unwrap();
+                assert_eq!(
+                    word_gen.combinations(),
+                    expected.len().to_biguint().unwrap(),
+                    "{} {}",
+                    mask,
+                    min_classes
+                );
+                assert!(word_gen.nth_word(&0u32.into()).is_none());
+                let words = gen_to_string(word_gen);
+                assert_eq!(words.lines().collect::<Vec<_>>(), expected);
+            }
+        }
+
+        let word_gen = CharsetGenerator::new(parse_mask("?1?d?1").unwrap(), None, None, &["aB!"])
+            .unwrap()
+            .with_min_classes(3)
+            .unwrap();
+        let words: Vec<_> = word_gen
+            .iter()
+            .map(|word| String::from_utf8(word).unwrap() + "\n")
+            .collect();
+        assert_eq!(words.concat(), gen_to_string(Box::new(word_gen)));
+
+        for min_classes in [0, 5] {
+            let word_gen = CharsetGenerator::new(parse_mask("?d").unwrap(), None, None, &[]);
+            assert!(word_gen.unwrap().with_min_classes(min_classes).is_err());
+        }
+        let wordlist = wordlist_fname("wordlist1.txt");
+        let wordlists = [wordlist.to_str().unwrap()];
+        let options = GeneratorOptions::new().min_classes(2);
+        for (mask, options) in [
+            ("?w1?d", options.clone()),
+            ("?d?l", options.clone().one_per_length(true)),
+            ("?d?l", options.max_per_length(Some(2))),
+        ] {
+            assert!(
+                get_word_generator_with_options(mask, None, None, &[], &wordlists, &options)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_gen_max_per_length() {
+        let options = GeneratorOptions::new().max_per_length(Some(3));
+        let word_gen =
+            get_word_generator_with_options("?d?d?d", Some(1), None, &[], &[], &options).unwrap();
+        assert_eq!(word_gen.combinations(), 9.to_biguint().unwrap());
+        assert_eq!(
+            gen_to_string(word_gen),
+            "0\n1\n2\n00\n01\n02\n000\n001\n002\n"
+        );
+
+        // lengths with less words than the max are generated whole
+        let options = GeneratorOptions::new().max_per_length(Some(20));
+        let word_gen =
+            get_word_generator_with_options("?d?d", Some(1), None, &[], &[], &options).unwrap();
+        assert_eq!(word_gen.combinations(), 30.to_biguint().unwrap());
+        let words = gen_to_string(word_gen);
+        assert_eq!(words.lines().count(), 30);
+        assert!(words.ends_with("\n18\n19\n"));
+
+        let word_gen = CharsetGenerator::new(parse_mask("?d").unwrap(), None, None, &[]).unwrap();
+        assert!(word_gen.with_max_per_length(0).is_err());
+        let wordlist = wordlist_fname("wordlist1.txt");
+        for (mask, options) in [
+            ("?w1", options.clone()),
+            ("?d", options.clone().one_per_length(true)),
+            ("?d", options.range(Some(("1".to_owned(), "2".to_owned())))),
+        ] {
+            let wordlists = [wordlist.to_str().unwrap()];
+            assert!(
+                get_word_generator_with_options(mask, None, None, &[], &wordlists, &options)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_gen_one_per_length() {
+        let options = GeneratorOptions::new().one_per_length(true);
+        let word_gen =
+            get_word_generator_with_options("?u?l?d?s", Some(2), None, &[], &[], &options).unwrap();
+        assert_eq!(word_gen.combinations(), 3.to_biguint().unwrap());
+        assert_eq!(gen_to_string(word_gen), "Aa\nAa0\nAa0 \n");
+
+        let range_options = options
+            .clone()
+            .range(Some(("a0".to_owned(), "a1".to_owned())));
+        assert!(
+            get_word_generator_with_options("?l?d", None, None, &[], &[], &range_options).is_err()
+        );
+        let wordlist1 = wordlist_fname("wordlist1.txt");
+        let wordlists = vec![wordlist1.to_str().unwrap()];
+        assert!(
+            get_word_generator_with_options("?w1?d", None, None, &[], &wordlists, &options)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_gen_anti_mask() {
+        let options = GeneratorOptions::new().anti_mask(Some("abc123".to_owned()));
+        let word_gen =
+            get_word_generator_with_options("?l?d", None, None, &[], &[], &options).unwrap();
+        assert_eq!(word_gen.combinations(), 9.to_biguint().unwrap());
+        assert_eq!(
+            gen_to_string(word_gen),
+            "1a\n1b\n1c\n2a\n2b\n2c\n3a\n3b\n3c\n"
+        );
+
+        // a literal char and a custom charset, with a range on the complement
+        let options = GeneratorOptions::new()
+            .anti_mask(Some("?d".to_owned()))
+            .range(Some(("03".to_owned(), "12".to_owned())));
+        let word_gen =
+            get_word_generator_with_options("5?1", None, None, &["56789"], &[], &options).unwrap();
+        assert_eq!(word_gen.combinations(), 5.to_biguint().unwrap());
+        assert_eq!(gen_to_string(word_gen), "03\n04\n10\n11\n12\n");
+
+        let options = GeneratorOptions::new().anti_mask(Some("?d".to_owned()));
+        let word_gen =
+            get_word_generator_with_options("?l?u", Some(1), None, &[], &[], &options).unwrap();
+        assert_eq!(word_gen.combinations(), 110.to_biguint().unwrap());
+        let err = get_word_generator_with_options("?d?l", None, None, &[], &[], &options)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "position 1 of the mask has every char of the alphabet, its complement is empty"
+        );
+        let wordlist1 = wordlist_fname("wordlist1.txt");
+        let wordlists = vec![wordlist1.to_str().unwrap()];
+        assert!(
+            get_word_generator_with_options("?w1?d", None, None, &[], &wordlists, &options)
+                .is_err()
+        );
+        let options = GeneratorOptions::new().anti_mask(Some(String::new()));
+        assert!(get_word_generator_with_options("?d", None, None, &[], &[], &options).is_err());
+    }
+
+    #[test]
+    fn test_gen_nth_word() {
+        let wordlist1 = wordlist_fname("wordlist1.txt");
+        let wordlist2 = wordlist_fname("wordlist2.txt");
+        let wordlists = vec![wordlist1.to_str().unwrap(), wordlist2.to_str().unwrap()];
+        let range = GeneratorOptions::new().range(Some(("b8".to_owned(), "d2".to_owned())));
+        let one_per_length = GeneratorOptions::new().one_per_length(true);
+        let max_per_length = GeneratorOptions::new().max_per_length(Some(15));
+        let default = GeneratorOptions::new();
+        for (mask, minlen, maxlen, options) in [
+            ("?d?l?1", None, None, &default),
+            ("?d?l?1", Some(1), None, &default),
+            ("?d?l?1", Some(2), Some(2), &default),
+            ("?l?d", None, None, &range),
+            ("?u?l?d", Some(1), None, &one_per_length),
+            ("?d?l?1", Some(1), None, &max_per_length),
+            ("?w1?1?w2", None, None, &default),
+            ("?w2-?d", None, None, &default),
+        ] {
+            let word_gen =
+                get_word_generator_with_options(mask, minlen, maxlen, &["xy"], &wordlists, options)
+                    .unwrap();
+            let combs = usize::try_from(word_gen.combinations()).unwrap();
+            let words: Vec<_> = (0..combs)
+                .map(|i| String::from_utf8(word_gen.nth_word(&i.into()).unwrap()).unwrap())
+                .collect();
+            assert_eq!(words.join("\n") + "\n", gen_to_string(word_gen), "{}", mask);
+        }
+
+        let word_gen = get_word_generator("?d?d", None, None, &[], &[]).unwrap();
+        assert_eq!(word_gen.nth_word(&42u32.into()), Some(b"42".to_vec()));
+        assert_eq!(word_gen.nth_word(&100u32.into()), None);
+
+        // words skipped by no repeat shift the indices
+        let options = GeneratorOptions::new().no_repeat(NoRepeat::Adjacent);
+        let word_gen =
+            get_word_generator_with_options("?w1?w1", None, None, &[], &wordlists, &options)
+                .unwrap();
+        assert_eq!(word_gen.nth_word(&0u32.into()), None);
+    }
+
+    #[test]
+    fn test_gen_cache_wordlists() {
+        let wordlist1 = wordlist_fname("wordlist1.txt");
+        let wordlist2 = wordlist_fname("wordlist2.txt");
+        let wordlists = vec![wordlist1.to_str().unwrap(), wordlist2.to_str().unwrap()];
+        let first_wordlist = |options: &GeneratorOptions, mask: &str| {
+            let word_gen = WordlistGenerator::with_options(
+                parse_mask(mask).unwrap(),
+                &wordlists,
+                &[],
+                options,
+            )
+            .unwrap();
+            match &word_gen.items[0] {
+                WordlistItem::Wordlist(wordlist) => wordlist.clone(),
+                WordlistItem::Charset(_) => unreachable!("a wordlist position"),
+            }
+        };
+
+        let options = GeneratorOptions::new().cache_wordlists(true);
+        let wordlist = first_wordlist(&options, "?w1?d");
+        // loaded once, for every mask and every clone of the options
+        assert!(Rc::ptr_eq(&wordlist, &first_wordlist(&options, "?w1-?w2")));
+        assert!(Rc::ptr_eq(
+            &wordlist,
+            &first_wordlist(&options.clone(), "?w1")
+        ));
+        let output = gen_to_string(Box::new(
+            WordlistGenerator::with_options(parse_mask("?w1").unwrap(), &wordlists, &[], &options)
+                .unwrap(),
+        ));
+        assert_eq!(output.lines().count(), 10);
+
+        let options = GeneratorOptions::new();
+        assert!(!Rc::ptr_eq(
+            &first_wordlist(&options, "?w1"),
+            &first_wordlist(&options, "?w1")
+        ));
+    }
+
     #[test]
     fn test_get_word_generator_charset() {
         let mask = "?d?d?d?d";
@@ -476,14 +2425,316 @@ mod tests {
         assert!(word_gen.is_err());
     }
 
-    fn assert_gen<'a>(w: Box<dyn WordGenerator + 'a>, fname: &str) -> String {
+    #[test]
+    fn test_word_generator_unordered_unique() {
+        let wordlist = wordlist_fname("wordlist-3words.txt");
+        let wordlists = vec![wordlist.to_str().unwrap()];
+        let options = GeneratorOptions::new().unordered_unique(true);
+        let word_gen =
+            get_word_generator_with_options("?w1 ?w1", None, None, &[], &wordlists, &options)
+                .unwrap();
+
+        assert_eq!(word_gen.combinations(), 6.to_biguint().unwrap());
+        assert_eq!(
+            gen_to_string(word_gen),
+            "beta beta\nbeta alpha\nbeta gamma\nalpha alpha\nalpha gamma\ngamma gamma\n"
+        );
+    }
+
+    #[test]
+    fn test_word_generator_kind() {
+        let wordlist = wordlist_fname("wordlist-3words.txt");
+        let wordlists = vec![wordlist.to_str().unwrap()];
+        let word_gen = |mask, options: &GeneratorOptions| {
+            get_word_generator_with_options(mask, None, None, &["ab"], &wordlists, options).unwrap()
+        };
+
+        let charset_gen = word_gen("?d\\?1", &GeneratorOptions::new());
+        assert_eq!(charset_gen.kind(), GeneratorKind::Charset);
+        assert_eq!(charset_gen.mask(), "?d\\?1");
+        assert!(charset_gen.is_exact_count());
+
+        let positional_gen = word_gen("?d?d", &GeneratorOptions::new().positional_increment(true));
+        assert_eq!(positional_gen.kind(), GeneratorKind::Positional);
+        assert_eq!(positional_gen.mask(), "?d?d");
+
+        let wordlist_gen = word_gen("?w1?d", &GeneratorOptions::new());
+        assert_eq!(wordlist_gen.kind(), GeneratorKind::Wordlist);
+        assert_eq!(wordlist_gen.mask(), "?w1?d");
+        assert!(wordlist_gen.is_exact_count());
+        let no_repeat_gen = word_gen("?w1?w1", &GeneratorOptions::new().no_repeat(NoRepeat::Any));
+        assert!(!no_repeat_gen.is_exact_count());
+
+        let combine_gen =
+            CombineGenerator::new(wordlists[0], 1, 10, &GeneratorOptions::new()).unwrap();
+        assert_eq!(combine_gen.kind(), GeneratorKind::Combine);
+        assert_eq!(combine_gen.mask(), "");
+
+        let model_gen =
+            ModelGenerator::new(parse_mask("?l").unwrap(), &[], &CharModel::new()).unwrap();
+        assert_eq!(model_gen.kind(), GeneratorKind::Model);
+        assert_eq!(model_gen.mask(), "?l");
+    }
+
+    #[test]
+    fn test_zip_generator() {
+        let wordlist1 = wordlist_fname("wordlist-3words.txt");
+        let wordlist2 = wordlist_fname("wordlist1.txt");
+        let wordlists = vec![wordlist1.to_str().unwrap(), wordlist2.to_str().unwrap()];
+        let options = GeneratorOptions::new().zip(true);
+        let word_gen = |mask| {
+            get_word_generator_with_options(mask, None, None, &["ab"], &wordlists, &options)
+                .unwrap()
+        };
+
+        // lines in file order, stopping at the shortest wordlist
+        let zip_gen = word_gen("?w1:?w2");
+        assert_eq!(zip_gen.kind(), GeneratorKind::Zip);
+        assert_eq!(zip_gen.combinations(), 3.to_biguint().unwrap());
+        assert_eq!(
+            gen_to_string(zip_gen),
+            "alpha:123456\nbeta:password\ngamma:123456789\n"
+        );
+
+        // charsets are a product on every line, a repeated wordlist has the same word
+        let zip_gen = word_gen("?w1?1?w1");
+        assert_eq!(zip_gen.combinations(), 6.to_biguint().unwrap());
+        assert_eq!(
+            gen_to_string(zip_gen),
+            "alphaaalpha\nalphabalpha\nbetaabeta\nbetabbeta\ngammaagamma\ngammabgamma\n"
+        );
+
+        // a wordlist not used by the mask does not limit the lines
+        assert_eq!(word_gen("?w2").combinations(), 10.to_biguint().unwrap());
+
+        let invalid_options = [
+            GeneratorOptions::new().zip(true).unordered_unique(true),
+            GeneratorOptions::new()
+                .zip(true)
+                .wordlist_options(WordlistOptions::new().dedup(true)),
+        ];
+        for options in invalid_options.iter() {
+            assert!(get_word_generator_with_options(
+                "?w1?w2",
+                None,
+                None,
+                &[],
+                &wordlists,
+                options
+            )
+            .is_err());
+        }
+        assert!(get_word_generator_with_options("?d", None, None, &[], &[], &options).is_err());
+        assert!(ZipGenerator::new(
+            parse_mask("?w1").unwrap(),
+            &[wordlists[0]],
+            &[],
+            &WordlistOptions::new()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_delta_generator() {
+        let fname = env::temp_dir().join("cracken-test-delta-wordlist.txt");
+        fs::write(&fname, "a\nb\nc\ndd\n").unwrap();
+        let fname = fname.to_str().unwrap();
+        let delta_gen = |mask, old_lines| {
+            let options = GeneratorOptions::new().delta_from(Some(old_lines));
+            get_word_generator_with_options(mask, None, None, &[], &[fname], &options).unwrap()
+        };
+        let words = |word_gen: Box<dyn WordGenerator>| {
+            let output = gen_to_string(word_gen);
+            output.lines().map(|w| w.to_owned()).collect::<Vec<_>>()
+        };
+
+        let word_gen = delta_gen("?w1-?w1?d", 2);
+        assert_eq!(word_gen.kind(), GeneratorKind::Delta);
+        assert_eq!(word_gen.mask(), "?w1-?w1?d");
+        // 4 * 4 - 2 * 2 pairs with a new word
+        assert_eq!(word_gen.combinations(), 120.to_biguint().unwrap());
+        let mut delta = words(word_gen);
+        assert_eq!(delta.len(), 120);
+        delta.sort();
+        delta.dedup();
+        assert_eq!(delta.len(), 120);
+        assert!(delta.iter().all(|w| w.contains('c') || w.contains('d')));
+
+        // nothing appended and everything new
+        assert!(words(delta_gen("?w1?w1", 4)).is_empty());
+        assert_eq!(words(delta_gen("?w1?w1", 0)).len(), 16);
+        assert_eq!(words(delta_gen("?w1", 3)), vec!["dd"]);
+
+        let options = GeneratorOptions::new().delta_from(Some(1));
+        for mask in ["?d", "?w1?w2"] {
+            let wordlists = [fname, fname];
+            assert!(
+                get_word_generator_with_options(mask, None, None, &[], &wordlists, &options)
+                    .is_err()
+            );
+        }
+        fs::remove_file(fname).unwrap();
+    }
+
+    #[test]
+    fn test_word_generator_no_repeat() {
+        let wordlist = wordlist_fname("wordlist-3words.txt");
+        let wordlists = vec![wordlist.to_str().unwrap()];
+        let word_gen = |mask, no_repeat| {
+            let options = GeneratorOptions::new().no_repeat(no_repeat);
+            get_word_generator_with_options(mask, None, None, &[], &wordlists, &options).unwrap()
+        };
+
+        // combinations are an upper bound
+        assert_eq!(
+            word_gen("?w1?w1", NoRepeat::Adjacent).combinations(),
+            9.to_biguint().unwrap()
+        );
+        assert_eq!(
+            gen_to_string(word_gen("?w1?w1", NoRepeat::Adjacent))
+                .lines()
+                .count(),
+            3 * 2
+        );
+        assert_eq!(
+            gen_to_string(word_gen("?w1?w1", NoRepeat::Off))
+                .lines()
+                .count(),
+            9
+        );
+        assert_eq!(
+            gen_to_string(word_gen("?w1-?w1", NoRepeat::Adjacent)),
+            "beta-alpha\nbeta-gamma\nalpha-beta\nalpha-gamma\ngamma-beta\ngamma-alpha\n"
+        );
+
+        let adjacent = gen_to_string(word_gen("?w1 ?w1 ?w1", NoRepeat::Adjacent));
+        assert_eq!(adjacent.lines().count(), 3 * 2 * 2);
+        assert!(adjacent.contains("beta alpha beta\n"));
+        let any = gen_to_string(word_gen("?w1 ?w1 ?w1", NoRepeat::Any));
+        assert_eq!(any.lines().count(), 3 * 2);
+        assert!(!any.contains("beta alpha beta\n"));
+    }
+
+    #[test]
+    fn test_word_generator_min_distinct_words() {
+        let wordlist = wordlist_fname("wordlist-3words.txt");
+        let wordlists = vec![wordlist.to_str().unwrap()];
+        let word_gen = |mask, min_distinct_words| {
+            let options = GeneratorOptions::new().min_distinct_words(min_distinct_words);
+            get_word_generator_with_options(mask, None, None, &[], &wordlists, &options)
+        };
+
+        // combinations are an upper bound
+        let two_distinct = word_gen("?w1?w1?w1", 2).unwrap();
+        assert_eq!(two_distinct.combinations(), 27.to_biguint().unwrap());
+        assert!(!two_distinct.is_exact_count());
+        let two_distinct = gen_to_string(two_distinct);
+        assert_eq!(two_distinct.lines().count(), 27 - 3);
+        assert!(!two_distinct.contains("betabetabeta\n"));
+        assert!(two_distinct.contains("betaalphabeta\n"));
+        assert!(two_distinct.contains("betabetaalpha\n"));
+
+        let three_distinct = gen_to_string(word_gen("?w1 ?w1 ?w1", 3).unwrap());
+        assert_eq!(three_distinct.lines().count(), 3 * 2);
+        assert!(three_distinct.contains("beta alpha gamma\n"));
+        assert_eq!(
+            gen_to_string(word_gen("?w1 ?w1 ?w1", 1).unwrap())
+                .lines()
+                .count(),
+            27
+        );
+
+        assert!(word_gen("?w1?d?w1", 3).is_err());
+        assert!(word_gen("?d?d", 2).is_err());
+    }
+
+    #[test]
+    fn test_word_generator_unordered_unique_mixed() {
+        let wordlist1 = wordlist_fname("wordlist-3words.txt");
+        let wordlist2 = wordlist_fname("wordlist1.txt");
+        let wordlists = vec![wordlist1.to_str().unwrap(), wordlist2.to_str().unwrap()];
+        let options = GeneratorOptions::new().unordered_unique(true);
+        let word_gen = get_word_generator_with_options(
+            "?w1?w2?w1?d?w1",
+            None,
+            None,
+            &[],
+            &wordlists,
+            &options,
+        )
+        .unwrap();
+
+        // C(3+3-1, 3) * 10 * 10
+        let expected = 10 * 10 * 10;
+        assert_eq!(word_gen.combinations(), expected.to_biguint().unwrap());
+        assert_eq!(gen_to_string(word_gen).lines().count(), expected);
+    }
+
+    #[test]
+    fn test_charsets_combinations_long_mask() {
+        let lens: Vec<usize> = (0..64).map(|i| [10, 26, 33, 95, 256][i % 5]).collect();
+        for (minlen, maxlen) in [(1, 64), (1, 1), (17, 50), (64, 64), (30, 29)] {
+            // naive sum of the product of every prefix
+            let mut expected = 0.to_biguint().unwrap();
+            for len in minlen..=maxlen {
+                expected += lens[..len]
+                    .iter()
+                    .fold(1.to_biguint().unwrap(), |acc, x| acc * x);
+            }
+            assert_eq!(charsets_combinations(&lens, minlen, maxlen), expected);
+        }
+    }
+
+    #[test]
+    fn test_mask_combinations() {
+        let custom_charsets = ["abcd", "01"];
+        let cases = vec![
+            ("?d?s?u?l?a?b", "5368197120", None, None),
+            ("?d?d?d?d?d?d?d?d", "111111110", Some(1), Some(8)),
+            ("?d?d?d?d?d?d?d?d", "10000", Some(4), Some(4)),
+            ("?d?1?2?d", "930", Some(1), None),
+            ("?w1?d?w2?l?w1?1", "1248000", None, None),
+            ("?w2", "12", None, None),
+            ("?d{-0}?d{-0}", "81", None, None),
+            ("?l{-aeiou}?w1", "210", None, None),
+        ];
+        for (mask, result, minlen, maxlen) in cases {
+            assert_eq!(
+                mask_combinations(mask, minlen, maxlen, &custom_charsets, &[10, 12]).unwrap(),
+                BigUint::parse_bytes(result.as_bytes(), 10).unwrap()
+            );
+        }
+
+        // must match the combinations of the built generator
+        let wordlist1 = wordlist_fname("wordlist1.txt");
+        let wordlists = vec![wordlist1.to_str().unwrap()];
+        let word_gen = get_word_generator("?w1?d?1", None, None, &["!@#"], &wordlists).unwrap();
+        assert_eq!(
+            mask_combinations("?w1?d?1", None, None, &["!@#"], &[10]).unwrap(),
+            word_gen.combinations()
+        );
+
+        assert!(mask_combinations("?w1", Some(1), None, &[], &[10]).is_err());
+        assert!(mask_combinations("?w2", None, None, &[], &[10]).is_err());
+        assert!(mask_combinations("?d?d", Some(3), None, &[], &[]).is_err());
+    }
+
+    fn gen_to_bytes(w: &dyn WordGenerator) -> Vec<u8> {
         let mut buf: Vec<u8> = Vec::new();
         {
-            let mut cur: Box<dyn Write> = Box::new(Cursor::new(&mut buf));
-            w.gen(&mut cur).unwrap();
+            let mut out = WordsWriter::new(Box::new(Cursor::new(&mut buf)), OutputOptions::new());
+            w.gen(&mut out).unwrap();
+            out.flush().unwrap();
         }
+        buf
+    }
+
+    fn gen_to_string<'a>(w: Box<dyn WordGenerator + 'a>) -> String {
+        String::from_utf8(gen_to_bytes(w.as_ref())).unwrap()
+    }
 
-        let result = String::from_utf8(buf).unwrap();
+    fn assert_gen<'a>(w: Box<dyn WordGenerator + 'a>, fname: &str) -> String {
+        let result = gen_to_string(w);
         let expected = fs::read_to_string(wordlist_fname(fname)).unwrap();
 
         let mut s2 = fname.to_owned();
@@ -523,4 +2774,212 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_positional_generator() {
+        let mask = parse_mask("a?1b").unwrap();
+        let word_gen = PositionalGenerator::new(mask.to_vec(), None, None, &["xy"]).unwrap();
+        assert_eq!(word_gen.combinations(), 11.to_biguint().unwrap());
+        assert_eq!(
+            gen_to_string(Box::new(word_gen)),
+            "a\nx\ny\nb\nax\nay\nab\nxb\nyb\naxb\nayb\n"
+        );
+
+        let word_gen = PositionalGenerator::new(mask.to_vec(), Some(2), Some(2), &["xy"]).unwrap();
+        assert_eq!(word_gen.combinations(), 5.to_biguint().unwrap());
+        assert_eq!(gen_to_string(Box::new(word_gen)), "ax\nay\nab\nxb\nyb\n");
+
+        // (1 + 10) ** 4 - 1 words, including duplicates from different positions
+        let mask = parse_mask("?d?d?d?d").unwrap();
+        let word_gen = PositionalGenerator::new(mask, None, None, &[]).unwrap();
+        assert_eq!(word_gen.combinations(), 14640.to_biguint().unwrap());
+        assert_eq!(gen_to_string(Box::new(word_gen)).lines().count(), 14640);
+
+        let options = GeneratorOptions::new().positional_increment(true);
+        assert!(
+            get_word_generator_with_options("?w1?d", None, None, &[], &["x"], &options).is_err()
+        );
+    }
+
+    #[test]
+    fn test_word_generator_short_wordlists_fuzz() {
+        // a small deterministic lcg, so failures are reproducible
+        let mut seed: u64 = 42;
+        let mut rand = |n: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % n
+        };
+
+        for round in 0..30 {
+            let wordlists_words: Vec<Vec<String>> = (0..2)
+                .map(|_| {
+                    (0..1 + rand(4))
+                        .map(|_| {
+                            (0..1 + rand(3))
+                                .map(|_| (b'a' + rand(3) as u8) as char)
+                                .collect()
+                        })
+                        .collect()
+                })
+                .collect();
+            let mask: String = (0..1 + rand(4))
+                .map(|_| ["?w1", "?w2", "?1", "x"][rand(4) as usize])
+                .collect();
+            if !mask.contains("?w") {
+                continue;
+            }
+
+            let fnames: Vec<_> = wordlists_words
+                .iter()
+                .enumerate()
+                .map(|(i, words)| {
+                    let fname =
+                        env::temp_dir().join(format!("cracken-test-fuzz-{}-{}.txt", round, i));
+                    fs::write(&fname, words.join("\n")).unwrap();
+                    fname
+                })
+                .collect();
+            let wordlists: Vec<_> = fnames.iter().map(|f| f.to_str().unwrap()).collect();
+
+            // expected - the cartesian product of each position words in wordlist order
+            let loaded: Vec<_> = wordlists
+                .iter()
+                .map(|fname| {
+                    Wordlist::from_file(fname)
+                        .map(|w| w.iter().map(|w| w.to_vec()).collect::<Vec<_>>())
+                })
+                .collect();
+            let output = get_word_generator(&mask, None, None, &["01"], &wordlists)
+                .map(|word_gen| gen_to_string(word_gen).into_bytes());
+            // the files are removed before asserting, so a failing case does not leave them
+            for fname in &fnames {
+                fs::remove_file(fname).unwrap();
+            }
+            let case = format!(
+                "round {} mask {} wordlists {:?}",
+                round, mask, wordlists_words
+            );
+            let loaded: Vec<Vec<Vec<u8>>> = loaded
+                .into_iter()
+                .map(|words| words.unwrap_or_else(|e| panic!("{}: {}", case, e)))
+                .collect();
+
+            let position_words: Vec<Vec<Vec<u8>>> = parse_mask(&mask)
+                .unwrap()
+                .into_iter()
+                .map(|op| match op {
+                    MaskOp::Wordlist(idx) => loaded[idx].clone(),
+                    MaskOp::CustomCharset(_) => vec![b"0".to_vec(), b"1".to_vec()],
+                    MaskOp::Char(ch) => vec![vec![ch as u8]],
+                    MaskOp::BuiltinCharset(_) | MaskOp::BuiltinCharsetExcept(..) => unreachable!(),
+                })
+                .collect();
+            let expected: Vec<u8> = position_words
+                .iter()
+                .multi_cartesian_product()
+                .flat_map(|words| {
+                    words
+                        .into_iter()
+                        .flatten()
+                        .chain(b"\n")
+                        .copied()
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+
+            match output {
+                Ok(output) => assert_eq!(output, expected, "{}", case),
+                Err(e) => panic!("{}: {}", case, e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_word_generator_max_word_size() {
+        let fname = env::temp_dir().join("cracken-test-long-word.txt");
+        fs::write(&fname, "a".repeat(300)).unwrap();
+        let wordlists = vec![fname.to_str().unwrap()];
+        assert!(get_word_generator("?w1", None, None, &[], &wordlists).is_ok());
+        assert!(get_word_generator("?w1?w1", None, None, &[], &wordlists).is_err());
+        fs::remove_file(&fname).unwrap();
+    }
+
+    #[test]
+    fn test_word_generator_blank_wordlist() {
+        let blank = wordlist_fname("wordlist-blank-lines.txt");
+        let wordlist1 = wordlist_fname("wordlist1.txt");
+        let wordlists = vec![blank.to_str().unwrap(), wordlist1.to_str().unwrap()];
+
+        let err = get_word_generator("?w1?d", None, None, &[], &wordlists)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("wordlist-blank-lines.txt"));
+        // an unused blank wordlist is fine
+        assert!(get_word_generator("?w2?d", None, None, &[], &wordlists).is_ok());
+    }
+
+    #[test]
+    fn test_model_generator() {
+        let mut model = CharModel::new();
+        model.train(&b"baaa\nccb\n"[..]).unwrap();
+
+        let mask = parse_mask("?1?1").unwrap();
+        let word_gen = ModelGenerator::new(mask, &["abc"], &model).unwrap();
+        assert_eq!(word_gen.combinations(), 9.to_biguint().unwrap());
+        // a is the most probable, then b and c are equally probable
+        assert_eq!(
+            gen_to_string(Box::new(word_gen)),
+            "aa\nab\nac\nba\nca\nbb\nbc\ncb\ncc\n"
+        );
+
+        let mask = parse_mask("x?d?1").unwrap();
+        let word_gen = ModelGenerator::new(mask, &["abc"], &model).unwrap();
+        let output = gen_to_string(Box::new(word_gen));
+        let mut words: Vec<_> = output.lines().collect();
+        assert_eq!(words[..3], ["x0a", "x1a", "x2a"]);
+        words.sort_unstable();
+        words.dedup();
+        assert_eq!(words.len(), 30);
+
+        let mask = parse_mask("?w1").unwrap();
+        assert!(ModelGenerator::new(mask, &[], &model).is_err());
+    }
+
+    #[test]
+    fn test_combine_generator() {
+        let fname = wordlist_fname("wordlist-3words.txt");
+        let fname = fname.to_str().unwrap();
+        let word_gen = CombineGenerator::new(fname, 9, 10, &GeneratorOptions::new()).unwrap();
+        assert_eq!(word_gen.combinations(), 8.to_biguint().unwrap());
+        assert_eq!(
+            gen_to_string(Box::new(word_gen)),
+            "betaalpha\nbetagamma\nalphabeta\nalphaalpha\nalphagamma\ngammabeta\ngammaalpha\ngammagamma\n"
+        );
+
+        assert!(CombineGenerator::new(fname, 0, 10, &GeneratorOptions::new()).is_err());
+        assert!(CombineGenerator::new(fname, 5, 4, &GeneratorOptions::new()).is_err());
+    }
+
+    #[test]
+    fn test_combine_generator_combinations() {
+        // all 1 to 3 digits numbers, including ones with leading zeros
+        let fname = wordlist_fname("single-digits.txt");
+        let word_gen =
+            CombineGenerator::new(fname.to_str().unwrap(), 1, 3, &GeneratorOptions::new()).unwrap();
+        assert_eq!(word_gen.combinations(), 1110.to_biguint().unwrap());
+        let output = gen_to_string(Box::new(word_gen));
+        assert_eq!(output.lines().count(), 1110);
+        assert_eq!(&output[..9], "0\n00\n000\n");
+
+        let fname = wordlist_fname("wordlist1.txt");
+        let word_gen =
+            CombineGenerator::new(fname.to_str().unwrap(), 10, 14, &GeneratorOptions::new())
+                .unwrap();
+        let expected_combs = word_gen.combinations();
+        let output = gen_to_string(Box::new(word_gen));
+        assert_eq!(output.lines().count().to_biguint().unwrap(), expected_combs);
+        assert!(output.lines().all(|w| (10..=14).contains(&w.len())));
+    }
 }
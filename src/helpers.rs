@@ -1,15 +1,53 @@
-use std::io::{BufRead, BufReader, Error, Read};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender};
+use std::thread::{Scope, ScopedJoinHandle};
+use std::time::Instant;
 
+use crate::BUFFER_SIZE;
+
+/// returns a printable representation of a byte - the char itself if printable ascii
+/// (including a space), `\xNN` otherwise
+pub fn escape_byte(ch: u8) -> String {
+    if ch == b' ' || ch.is_ascii_graphic() {
+        (ch as char).to_string()
+    } else {
+        format!("\\x{:02x}", ch)
+    }
+}
+
+/// returns `record` without its ending `delimiter`, and without the `\r` of a CRLF line ending
+/// when the delimiter is a newline. the last record may not end with a delimiter
+pub fn strip_record_end(record: &[u8], delimiter: u8) -> &[u8] {
+    match record.strip_suffix(&[delimiter]) {
+        Some(record) if delimiter == b'\n' => record.strip_suffix(b"\r").unwrap_or(record),
+        Some(record) => record,
+        None => record,
+    }
+}
+
+/// iterates the raw lines of a reader, without the delimiter (newline by default) - see
+/// `strip_record_end`
 pub struct RawFileReader<R> {
     reader: BufReader<R>,
     buffer: Vec<u8>,
+    delimiter: u8,
 }
 
 impl<R: Read> RawFileReader<R> {
     pub fn new(reader: R) -> RawFileReader<R> {
+        Self::with_delimiter(reader, b'\n')
+    }
+
+    /// reads records separated by `delimiter` instead of newlines (e.g. `b'\0'`)
+    pub fn with_delimiter(reader: R, delimiter: u8) -> RawFileReader<R> {
         RawFileReader {
             reader: BufReader::new(reader),
             buffer: Vec::with_capacity(256),
+            delimiter,
         }
     }
 }
@@ -19,10 +57,12 @@ impl<R: Read> Iterator for RawFileReader<R> {
 
     fn next(&mut self) -> Option<Result<Vec<u8>, Error>> {
         self.buffer.clear();
-        match self.reader.read_until(b'\n', &mut self.buffer) {
+        match self.reader.read_until(self.delimiter, &mut self.buffer) {
             Ok(0) => None,
             Ok(_) => {
-                self.buffer.pop();
+                // the last record may not end with a delimiter
+                let len = strip_record_end(&self.buffer, self.delimiter).len();
+                self.buffer.truncate(len);
                 Some(Ok(self.buffer.to_vec()))
             }
             Err(e) => Some(Err(e)),
@@ -30,22 +70,1041 @@ impl<R: Read> Iterator for RawFileReader<R> {
     }
 }
 
+/// iterates the records of a reader of netstrings (`<length>:<bytes>,` e.g. `5:hello,`), so a
+/// record may contain any byte. whitespace between netstrings (e.g. a newline) is ignored
+pub struct NetstringReader<R> {
+    reader: BufReader<R>,
+}
+
+impl<R: Read> NetstringReader<R> {
+    pub fn new(reader: R) -> NetstringReader<R> {
+        NetstringReader {
+            reader: BufReader::new(reader),
+        }
+    }
+
+    /// skips the whitespace before the next netstring, returning false at the end of input
+    fn skip_whitespace(&mut self) -> Result<bool, Error> {
+        loop {
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                return Ok(false);
+            }
+            let skip = buf.iter().take_while(|ch| ch.is_ascii_whitespace()).count();
+            let found = skip < buf.len();
+            self.reader.consume(skip);
+            if found {
+                return Ok(true);
+            }
+        }
+    }
+
+    fn read_netstring(&mut self) -> Result<Vec<u8>, Error> {
+        let invalid = |msg: &str| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid netstring: {}", msg),
+            )
+        };
+        let mut len = Vec::with_capacity(8);
+        (&mut self.reader).take(21).read_until(b':', &mut len)?;
+        if len.pop() != Some(b':') || len.is_empty() || !len.iter().all(u8::is_ascii_digit) {
+            return Err(invalid("expected a length followed by ':'"));
+        }
+        let len: u64 = match std::str::from_utf8(&len).unwrap().parse() {
+            Ok(len) => len,
+            Err(_) => return Err(invalid("length is too large")),
+        };
+
+        let mut record = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut record)?;
+        if record.len() as u64 != len {
+            return Err(invalid("unexpected end of input"));
+        }
+        let mut end = [0u8];
+        if self.reader.read(&mut end)? != 1 || end[0] != b',' {
+            return Err(invalid("expected ',' after the data"));
+        }
+        Ok(record)
+    }
+}
+
+impl<R: Read> Iterator for NetstringReader<R> {
+    type Item = Result<Vec<u8>, Error>;
+
+    fn next(&mut self) -> Option<Result<Vec<u8>, Error>> {
+        match self.skip_whitespace() {
+            Ok(true) => Some(self.read_netstring()),
+            Ok(false) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// a writer discarding its input while counting the written words (newlines). fails with
+/// `ErrorKind::TimedOut` once the deadline passed - used to sample generation throughput
+pub struct SampleWriter {
+    words: u64,
+    deadline: Instant,
+}
+
+impl SampleWriter {
+    pub fn new(deadline: Instant) -> SampleWriter {
+        SampleWriter { words: 0, deadline }
+    }
+
+    pub fn words(&self) -> u64 {
+        self.words
+    }
+}
+
+impl Write for SampleWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if Instant::now() >= self.deadline {
+            return Err(Error::new(ErrorKind::TimedOut, "sample deadline reached"));
+        }
+        self.words += buf.iter().filter(|&&ch| ch == b'\n').count() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// a writer keeping the first `n` lines written without their newline. fails with
+/// `ErrorKind::Other` once it has them, to stop the generation early
+pub struct HeadWriter {
+    n: usize,
+    lines: Vec<Vec<u8>>,
+    line: Vec<u8>,
+}
+
+impl HeadWriter {
+    pub fn new(n: usize) -> HeadWriter {
+        HeadWriter {
+            n,
+            lines: Vec::with_capacity(n.min(1024)),
+            line: vec![],
+        }
+    }
+
+    /// returns true once the first `n` lines were written
+    pub fn is_full(&self) -> bool {
+        self.lines.len() >= self.n
+    }
+
+    pub fn into_lines(self) -> Vec<Vec<u8>> {
+        self.lines
+    }
+}
+
+impl Write for HeadWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        for &ch in buf {
+            if self.is_full() {
+                return Err(Error::other("head lines written"));
+            }
+            if ch == b'\n' {
+                self.lines.push(std::mem::take(&mut self.line));
+            } else {
+                self.line.push(ch);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// a writer passing its input to both `primary` and `secondary` (e.g. a file and stdout).
+/// once `secondary` fails with a broken pipe (e.g. piped to `head`) it is dropped and writes
+/// continue to `primary` only
+pub struct TeeWriter<A, B> {
+    primary: A,
+    secondary: Option<B>,
+}
+
+impl<A: Write, B: Write> TeeWriter<A, B> {
+    pub fn new(primary: A, secondary: B) -> TeeWriter<A, B> {
+        TeeWriter {
+            primary,
+            secondary: Some(secondary),
+        }
+    }
+
+    fn on_secondary<F: FnOnce(&mut B) -> Result<(), Error>>(&mut self, f: F) -> Result<(), Error> {
+        if let Some(secondary) = &mut self.secondary {
+            match f(secondary) {
+                Err(e) if e.kind() == ErrorKind::BrokenPipe => self.secondary = None,
+                res => res?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<A: Write, B: Write> Write for TeeWriter<A, B> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let n = self.primary.write(buf)?;
+        self.on_secondary(|secondary| secondary.write_all(&buf[..n]))?;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.primary.flush()?;
+        self.on_secondary(|secondary| secondary.flush())
+    }
+}
+
+/// a writer holding back a trailing newline of its input until more data is written, so the
+/// last word is written without its separator. the held back newline is dropped with the writer
+pub struct NoTrailingNewlineWriter<W> {
+    inner: W,
+    pending: bool,
+}
+
+impl<W: Write> NoTrailingNewlineWriter<W> {
+    pub fn new(inner: W) -> NoTrailingNewlineWriter<W> {
+        NoTrailingNewlineWriter {
+            inner,
+            pending: false,
+        }
+    }
+}
+
+impl<W: Write> Write for NoTrailingNewlineWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pending {
+            self.inner.write_all(b"\n")?;
+            self.pending = false;
+        }
+        let (data, trailing) = match buf.split_last() {
+            Some((b'\n', data)) => (data, true),
+            _ => (buf, false),
+        };
+        self.inner.write_all(data)?;
+        self.pending = trailing;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+}
+
+/// a writer handing its data in `BUFFER_SIZE` chunks to a thread of `scope` writing them to
+/// the inner writer, so generation continues while a slow consumer drains the previous chunks.
+/// up to `depth` chunks are pending, then writes block until the consumer catches up.
+/// errors of the inner writer are returned by the following write or flush
+pub struct ThreadedWriter<'scope> {
+    buf: Vec<u8>,
+    chunks_tx: Option<SyncSender<Vec<u8>>>,
+    /// written chunks sent back for reuse, and an empty chunk once a flush is done
+    done_rx: Receiver<Vec<u8>>,
+    handle: Option<ScopedJoinHandle<'scope, Result<(), Error>>>,
+}
+
+impl<'scope> ThreadedWriter<'scope> {
+    pub fn new<'env, W: Write + Send + 'scope>(
+        scope: &'scope Scope<'scope, 'env>,
+        inner: W,
+        depth: usize,
+    ) -> ThreadedWriter<'scope> {
+        let (chunks_tx, chunks_rx) = sync_channel(depth);
+        let (done_tx, done_rx) = channel();
+        let handle = scope.spawn(move || Self::write_chunks(inner, chunks_rx, done_tx));
+        ThreadedWriter {
+            buf: Vec::with_capacity(BUFFER_SIZE),
+            chunks_tx: Some(chunks_tx),
+            done_rx,
+            handle: Some(handle),
+        }
+    }
+
+    /// writes every received chunk, an empty chunk flushes the inner writer
+    fn write_chunks<W: Write>(
+        mut inner: W,
+        chunks_rx: Receiver<Vec<u8>>,
+        done_tx: Sender<Vec<u8>>,
+    ) -> Result<(), Error> {
+        for mut chunk in chunks_rx {
+            if chunk.is_empty() {
+                inner.flush()?;
+            } else {
+                inner.write_all(&chunk)?;
+                chunk.clear();
+            }
+            // the writer may be gone already, nothing to reuse the chunk for
+            let _ = done_tx.send(chunk);
+        }
+        inner.flush()
+    }
+
+    fn send(&mut self, chunk: Vec<u8>) -> Result<(), Error> {
+        let sent = match &self.chunks_tx {
+            Some(chunks_tx) => chunks_tx.send(chunk).is_ok(),
+            None => false,
+        };
+        if sent {
+            Ok(())
+        } else {
+            Err(self.thread_error())
+        }
+    }
+
+    /// returns the error the writing thread stopped on
+    fn thread_error(&mut self) -> Error {
+        self.chunks_tx = None;
+        match self.handle.take().map(|handle| handle.join()) {
+            Some(Ok(Err(e))) => e,
+            Some(Err(_)) => Error::other("writer thread panicked"),
+            _ => Error::new(ErrorKind::BrokenPipe, "writer thread stopped"),
+        }
+    }
+
+    /// sends the buffered data, taking a written chunk for reuse as the next buffer
+    fn send_buf(&mut self) -> Result<(), Error> {
+        let next_buf = match self.done_rx.try_recv() {
+            Ok(chunk) if chunk.capacity() > 0 => chunk,
+            _ => Vec::with_capacity(BUFFER_SIZE),
+        };
+        let chunk = std::mem::replace(&mut self.buf, next_buf);
+        self.send(chunk)
+    }
+}
+
+impl Write for ThreadedWriter<'_> {
+    fn write(&mut self, data: &[u8]) -> Result<usize, Error> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= BUFFER_SIZE {
+            self.send_buf()?;
+        }
+        Ok(data.len())
+    }
+
+    /// blocks until all the data written so far is written and flushed by the inner writer
+    fn flush(&mut self) -> Result<(), Error> {
+        if !self.buf.is_empty() {
+            self.send_buf()?;
+        }
+        self.send(vec![])?;
+        loop {
+            match self.done_rx.recv() {
+                Ok(chunk) if chunk.capacity() == 0 => return Ok(()),
+                Ok(_) => continue,
+                Err(_) => return Err(self.thread_error()),
+            }
+        }
+    }
+}
+
+impl Drop for ThreadedWriter<'_> {
+    fn drop(&mut self) {
+        if !self.buf.is_empty() {
+            let _ = self.send_buf();
+        }
+        // closing the channel ends the thread once all chunks are written
+        self.chunks_tx = None;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// returns true iff `path` is a regular file (following symlinks), as opposed to a fifo, a
+/// device or a directory that cannot be memory mapped or read more than once
+pub fn is_regular_file<P: AsRef<Path>>(path: P) -> bool {
+    fs::metadata(path).is_ok_and(|metadata| metadata.is_file())
+}
+
+/// the compression of an input, detected by its magic bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// detects the compression of data starting with `header`
+    pub fn detect(header: &[u8]) -> Compression {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Compression::Xz
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// returns a reader of the decompressed content of `reader`, detecting its compression by its
+/// magic bytes. uncompressed data is read as is. gzip, zstd and xz require the feature of the
+/// same name, otherwise they fail with `ErrorKind::Unsupported` - they must be decompressed first
+pub fn decompress<R: Read + Send + 'static>(reader: R) -> Result<Box<dyn Read + Send>, Error> {
+    let mut reader = BufReader::new(reader);
+    match Compression::detect(reader.fill_buf()?) {
+        Compression::None => Ok(Box::new(reader)),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Ok(Box::new(flate2::read::MultiGzDecoder::new(reader))),
+        #[cfg(not(feature = "gzip"))]
+        Compression::Gzip => Err(unsupported_compression("gzip")),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Ok(Box::new(ZstdDecoder::new(reader)?)),
+        #[cfg(not(feature = "zstd"))]
+        Compression::Zstd => Err(unsupported_compression("zstd")),
+        #[cfg(feature = "xz")]
+        Compression::Xz => Ok(Box::new(XzDecoder::new(reader))),
+        #[cfg(not(feature = "xz"))]
+        Compression::Xz => Err(unsupported_compression("xz")),
+    }
+}
+
+/// the error of an input compressed with `name` when its feature is disabled
+#[cfg(not(all(feature = "gzip", feature = "zstd", feature = "xz")))]
+fn unsupported_compression(name: &str) -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        format!(
+            "{} compressed input requires the {} feature, decompress it first (e.g. {} -d)",
+            name, name, name
+        ),
+    )
+}
+
+/// decodes the zstd frames of a reader one after the other, as written by `zstd` when
+/// compressing several files into one or by `pzstd`
+#[cfg(feature = "zstd")]
+struct ZstdDecoder<R: BufRead> {
+    decoder: Option<ruzstd::decoding::StreamingDecoder<R, ruzstd::decoding::FrameDecoder>>,
+}
+
+#[cfg(feature = "zstd")]
+impl<R: BufRead> ZstdDecoder<R> {
+    fn new(reader: R) -> Result<Self, Error> {
+        let decoder = ruzstd::decoding::StreamingDecoder::new(reader)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        Ok(ZstdDecoder {
+            decoder: Some(decoder),
+        })
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<R: BufRead> Read for ZstdDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        loop {
+            let decoder = match self.decoder.as_mut() {
+                Some(decoder) => decoder,
+                None => return Ok(0),
+            };
+            let n = decoder.read(buf)?;
+            if n > 0 || buf.is_empty() || decoder.get_mut().fill_buf()?.is_empty() {
+                return Ok(n);
+            }
+            // the frame ended and another one follows it
+            let (reader, frame_decoder) = self.decoder.take().unwrap().into_parts();
+            let decoder =
+                ruzstd::decoding::StreamingDecoder::new_with_decoder(reader, frame_decoder)
+                    .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            self.decoder = Some(decoder);
+        }
+    }
+}
+
+/// decodes an xz stream on a separate thread, as lzma-rs only decodes into a writer. the
+/// decoded chunks are sent to the reader over a bounded channel, so at most a few chunks
+/// are buffered ahead of the reader
+#[cfg(feature = "xz")]
+struct XzDecoder {
+    chunks: std::sync::mpsc::Receiver<Result<Vec<u8>, Error>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+#[cfg(feature = "xz")]
+impl XzDecoder {
+    const CHUNKS: usize = 4;
+    const CHUNK_SIZE: usize = 64 * 1024;
+
+    fn new<R: BufRead + Send + 'static>(mut reader: R) -> Self {
+        let (sender, chunks) = std::sync::mpsc::sync_channel(Self::CHUNKS);
+        std::thread::spawn(move || {
+            let mut writer =
+                std::io::BufWriter::with_capacity(Self::CHUNK_SIZE, ChannelWriter(sender.clone()));
+            let decoded = lzma_rs::xz_decompress(&mut reader, &mut writer)
+                .map_err(|e| match e {
+                    lzma_rs::error::Error::IoError(e) => e,
+                    e => Error::new(ErrorKind::InvalidData, e),
+                })
+                .and_then(|()| writer.flush());
+            if let Err(e) = decoded {
+                // the reader may be gone already, there is no one to report the error to
+                let _ = sender.send(Err(e));
+            }
+        });
+        XzDecoder {
+            chunks,
+            chunk: vec![],
+            pos: 0,
+        }
+    }
+}
+
+#[cfg(feature = "xz")]
+impl Read for XzDecoder {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        while self.pos == self.chunk.len() {
+            match self.chunks.recv() {
+                Ok(chunk) => {
+                    self.chunk = chunk?;
+                    self.pos = 0;
+                }
+                // the decoding thread is done
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// sends everything written to it as chunks over a channel, failing with a broken pipe once
+/// the receiver is dropped
+#[cfg(feature = "xz")]
+struct ChannelWriter(std::sync::mpsc::SyncSender<Result<Vec<u8>, Error>>);
+
+#[cfg(feature = "xz")]
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.0
+            .send(Ok(buf.to_vec()))
+            .map_err(|_| Error::from(ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// the input spec that reads from stdin instead of a file
+pub const STDIN_INPUT: &str = "-";
+
+/// returns whether the input spec refers to stdin rather than to a file
+pub fn is_stdin_input<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref() == Path::new(STDIN_INPUT)
+}
+
+/// opens an input spec as is - stdin for `-` or the file at the given path otherwise
+pub fn open_raw_input<P: AsRef<Path>>(path: P) -> Result<Box<dyn Read + Send>, Error> {
+    let path = path.as_ref();
+    if is_stdin_input(path) {
+        return Ok(Box::new(std::io::stdin()));
+    }
+    Ok(Box::new(fs::File::open(path)?))
+}
+
+/// opens an input spec (a file path or `-` for stdin) for reading its decompressed
+/// content. every input of cracken is read with it, see `open_raw_input` and `decompress`
+pub fn open_input<P: AsRef<Path>>(path: P) -> Result<Box<dyn Read + Send>, Error> {
+    let path = path.as_ref();
+    decompress(open_raw_input(path)?).map_err(|e| match e.kind() {
+        ErrorKind::Unsupported => Error::new(e.kind(), format!("{}: {}", path.display(), e)),
+        _ => e,
+    })
+}
+
+/// keeps the `n` largest items pushed to it, in a min heap of at most `n` items. push
+/// `Reverse` items to keep the smallest instead
+#[derive(Debug)]
+pub struct TopN<T: Ord> {
+    n: usize,
+    heap: BinaryHeap<Reverse<T>>,
+}
+
+impl<T: Ord> TopN<T> {
+    pub fn new(n: usize) -> TopN<T> {
+        TopN {
+            n,
+            heap: BinaryHeap::with_capacity(n.min(1 << 20) + 1),
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.heap.len() < self.n {
+            self.heap.push(Reverse(item));
+        } else if let Some(mut smallest) = self.heap.peek_mut() {
+            if item > smallest.0 {
+                *smallest = Reverse(item);
+            }
+        }
+    }
+
+    /// returns whether a pushed item would be kept, where `is_larger` returns whether it is
+    /// larger than the given smallest kept item. lets callers build costly items only if kept
+    pub fn would_keep<F: FnOnce(&T) -> bool>(&self, is_larger: F) -> bool {
+        if self.heap.len() < self.n {
+            return true;
+        }
+        self.heap
+            .peek()
+            .is_some_and(|smallest| is_larger(&smallest.0))
+    }
+
+    /// returns the kept items from the largest to the smallest
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        // sorted ascending by Reverse, i.e. descending by the items
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(item)| item)
+            .collect()
+    }
+}
+
+/// a read only memory map of a whole file
+#[cfg(all(feature = "mmap", unix))]
+#[derive(Debug)]
+pub struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+#[cfg(all(feature = "mmap", unix))]
+impl Mmap {
+    pub fn open(file: &std::fs::File) -> Result<Mmap, Error> {
+        use std::os::unix::io::AsRawFd;
+
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            // mmap of zero bytes is invalid
+            return Ok(Mmap {
+                ptr: std::ptr::null_mut(),
+                len,
+            });
+        }
+        // safety: the mapping is private and read only, it is unmapped on drop
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(Error::last_os_error());
+        }
+        Ok(Mmap { ptr, len })
+    }
+}
+
+#[cfg(all(feature = "mmap", unix))]
+impl std::ops::Deref for Mmap {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        // safety: ptr is a valid mapping of len bytes while self is alive
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+#[cfg(all(feature = "mmap", unix))]
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // safety: ptr and len are of a mapping created by Mmap::open
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::File;
+    use std::io::{ErrorKind, Read, Write};
+    use std::time::{Duration, Instant};
+
+    use std::sync::{Arc, Mutex};
 
-    use crate::helpers::RawFileReader;
+    use crate::helpers::{
+        is_regular_file, is_stdin_input, open_input, Compression, HeadWriter, NetstringReader,
+        NoTrailingNewlineWriter, RawFileReader, SampleWriter, TeeWriter, ThreadedWriter, TopN,
+        STDIN_INPUT,
+    };
     use crate::test_util::wordlist_fname;
 
     #[test]
     fn test_reader() {
         let file = File::open(wordlist_fname("vocab.txt")).unwrap();
         let reader = RawFileReader::new(file);
-        let expected: Vec<_> = vec!["a", "e", "1", "i", "o"]
+        let expected: Vec<_> = ["a", "e", "1", "i", "o"]
             .iter()
             .map(|s| s.as_bytes())
             .collect();
         let lines = reader.take(5).map(|s| s.unwrap()).collect::<Vec<_>>();
         assert_eq!(lines, expected);
     }
+
+    #[test]
+    fn test_reader_with_delimiter() {
+        let file = File::open(wordlist_fname("passwords-null.txt")).unwrap();
+        let reader = RawFileReader::with_delimiter(file, b'\0');
+        let lines = reader.map(|s| s.unwrap()).collect::<Vec<_>>();
+        let expected: Vec<&[u8]> = vec![b"hello", b"multi\nline", b"pass123", b"last"];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_reader_no_trailing_newline() {
+        let reader = RawFileReader::new("abc\ndef".as_bytes());
+        let lines = reader.map(|s| s.unwrap()).collect::<Vec<_>>();
+        assert_eq!(lines, vec![b"abc", b"def"]);
+    }
+
+    #[test]
+    fn test_reader_crlf() {
+        let reader = RawFileReader::new("abc\r\nd\ref\r\n\r\nlast\r".as_bytes());
+        let lines = reader.map(|s| s.unwrap()).collect::<Vec<_>>();
+        let expected: Vec<&[u8]> = vec![b"abc", b"d\ref", b"", b"last\r"];
+        assert_eq!(lines, expected);
+
+        // only newline delimited lines end with CRLF
+        let reader = RawFileReader::with_delimiter("abc\r\0".as_bytes(), b'\0');
+        let lines = reader.map(|s| s.unwrap()).collect::<Vec<_>>();
+        assert_eq!(lines, vec![b"abc\r"]);
+    }
+
+    /// a writer into a shared vec that fails once it holds `limit` bytes
+    struct SharedWriter {
+        data: Arc<Mutex<Vec<u8>>>,
+        limit: usize,
+    }
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let mut data = self.data.lock().unwrap();
+            if data.len() + buf.len() > self.limit {
+                return Err(ErrorKind::BrokenPipe.into());
+            }
+            data.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_threaded_writer() {
+        let data = Arc::new(Mutex::new(vec![]));
+        let inner = SharedWriter {
+            data: data.clone(),
+            limit: usize::MAX,
+        };
+        let expected: Vec<u8> = (0..100_000u32).flat_map(|i| i.to_le_bytes()).collect();
+        std::thread::scope(|scope| {
+            let mut writer = ThreadedWriter::new(scope, inner, 2);
+            for chunk in expected.chunks(1000) {
+                writer.write_all(chunk).unwrap();
+            }
+            writer.flush().unwrap();
+            assert_eq!(*data.lock().unwrap(), expected);
+
+            writer.write_all(b"tail").unwrap();
+            drop(writer);
+            assert!(data.lock().unwrap().ends_with(b"tail"));
+        });
+
+        // the inner writer may be borrowed from outside the scope
+        let mut borrowed = vec![];
+        std::thread::scope(|scope| {
+            let mut writer = ThreadedWriter::new(scope, &mut borrowed, 2);
+            writer.write_all(b"abc").unwrap();
+        });
+        assert_eq!(borrowed, b"abc");
+    }
+
+    #[test]
+    fn test_threaded_writer_error() {
+        let inner = SharedWriter {
+            data: Arc::new(Mutex::new(vec![])),
+            limit: 10,
+        };
+        std::thread::scope(|scope| {
+            let mut writer = ThreadedWriter::new(scope, inner, 2);
+            let err = (0..1000)
+                .map(|_| writer.write_all(&[b'a'; 1000]))
+                .find_map(Result::err)
+                .or_else(|| writer.flush().err())
+                .unwrap();
+            assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+        });
+    }
+
+    #[test]
+    fn test_head_writer() {
+        let mut writer = HeadWriter::new(2);
+        writer.write_all(b"ab").unwrap();
+        writer.write_all(b"c\nd").unwrap();
+        assert!(!writer.is_full());
+        let err = writer.write_all(b"\ne\nf\n").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Other);
+        assert!(writer.is_full());
+        assert_eq!(writer.into_lines(), vec![b"abc".to_vec(), b"d".to_vec()]);
+
+        let mut writer = HeadWriter::new(0);
+        assert!(writer.write_all(b"a\n").is_err());
+        assert!(writer.into_lines().is_empty());
+    }
+
+    #[test]
+    fn test_no_trailing_newline_writer() {
+        let mut writer = NoTrailingNewlineWriter::new(vec![]);
+        writer.write_all(b"a\nb\n").unwrap();
+        assert_eq!(writer.inner, b"a\nb");
+        writer.write_all(b"c").unwrap();
+        writer.write_all(b"\n").unwrap();
+        writer.write_all(b"").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.inner, b"a\nb\nc");
+        // an empty last word keeps the separator of the word before it
+        writer.write_all(b"\n\n").unwrap();
+        assert_eq!(writer.inner, b"a\nb\nc\n\n");
+    }
+
+    #[test]
+    fn test_tee_writer() {
+        let data = Arc::new(Mutex::new(vec![]));
+        let secondary = SharedWriter {
+            data: data.clone(),
+            limit: 6,
+        };
+        let mut writer = TeeWriter::new(vec![], secondary);
+        writer.write_all(b"abc\n").unwrap();
+        assert_eq!(*data.lock().unwrap(), b"abc\n");
+        // the broken secondary is dropped, the primary gets everything
+        writer.write_all(b"def\n").unwrap();
+        writer.write_all(b"ghi\n").unwrap();
+        writer.flush().unwrap();
+        assert!(writer.secondary.is_none());
+        assert_eq!(writer.primary, b"abc\ndef\nghi\n");
+        assert_eq!(*data.lock().unwrap(), b"abc\n");
+
+        // other errors of the secondary are returned
+        struct FailingWriter;
+        impl Write for FailingWriter {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(ErrorKind::PermissionDenied.into())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let mut writer = TeeWriter::new(vec![], FailingWriter);
+        let err = writer.write_all(b"abc\n").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_sample_writer() {
+        let mut writer = SampleWriter::new(Instant::now() + Duration::from_secs(60));
+        writer.write_all(b"a\nb\nc").unwrap();
+        writer.write_all(b"\n").unwrap();
+        assert_eq!(writer.words(), 3);
+
+        let mut writer = SampleWriter::new(Instant::now());
+        let err = writer.write_all(b"a\n").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert_eq!(writer.words(), 0);
+    }
+
+    #[test]
+    fn test_is_regular_file() {
+        assert!(is_regular_file(wordlist_fname("wordlist1.txt")));
+        assert!(!is_regular_file(wordlist_fname("wordlist-dir")));
+        assert!(!is_regular_file(wordlist_fname("no-such-file.txt")));
+        #[cfg(unix)]
+        assert!(!is_regular_file("/dev/null"));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_decompress_zstd_frames() {
+        let frame = std::fs::read(wordlist_fname("wordlist1.txt.zst")).unwrap();
+        let expected = std::fs::read(wordlist_fname("wordlist1.txt")).unwrap();
+        let mut content = vec![];
+        super::decompress(std::io::Cursor::new(
+            [frame.clone(), frame.clone()].concat(),
+        ))
+        .unwrap()
+        .read_to_end(&mut content)
+        .unwrap();
+        assert_eq!(content, [expected.clone(), expected].concat());
+
+        let mut content = vec![];
+        let truncated = frame[..frame.len() - 4].to_vec();
+        assert!(super::decompress(std::io::Cursor::new(truncated))
+            .unwrap()
+            .read_to_end(&mut content)
+            .is_err());
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn test_decompress_xz() {
+        let compressed = std::fs::read(wordlist_fname("wordlist1.txt.xz")).unwrap();
+        let mut content = vec![];
+        let truncated = compressed[..compressed.len() - 4].to_vec();
+        let err = super::decompress(std::io::Cursor::new(truncated))
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap_err();
+        assert_ne!(err.kind(), ErrorKind::BrokenPipe);
+
+        // the reader may be dropped before the decoding thread is done
+        let expected = std::fs::read(wordlist_fname("wordlist1.txt")).unwrap();
+        let mut reader = super::decompress(std::io::Cursor::new(compressed)).unwrap();
+        let mut buf = [0; 3];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, expected[..3]);
+        drop(reader);
+    }
+
+    #[test]
+    fn test_open_input() {
+        assert_eq!(Compression::detect(b"\x1f\x8b\x08"), Compression::Gzip);
+        assert_eq!(Compression::detect(b"(\xb5/\xfd"), Compression::Zstd);
+        assert_eq!(Compression::detect(b"\xfd7zXZ\x00"), Compression::Xz);
+        assert_eq!(Compression::detect(b"\x1f"), Compression::None);
+        assert_eq!(Compression::detect(b""), Compression::None);
+
+        let read = |fname: &str| -> std::io::Result<Vec<u8>> {
+            let mut content = vec![];
+            open_input(wordlist_fname(fname))?.read_to_end(&mut content)?;
+            Ok(content)
+        };
+        let expected = std::fs::read(wordlist_fname("wordlist1.txt")).unwrap();
+        assert_eq!(read("wordlist1.txt").unwrap(), expected);
+        for (fname, name, supported) in [
+            ("wordlist1.txt.gz", "gzip", cfg!(feature = "gzip")),
+            ("wordlist1.txt.zst", "zstd", cfg!(feature = "zstd")),
+            ("wordlist1.txt.xz", "xz", cfg!(feature = "xz")),
+        ] {
+            if supported {
+                assert_eq!(read(fname).unwrap(), expected);
+                continue;
+            }
+            let err = read(fname).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+            assert!(err.to_string().contains(fname), "{}", err);
+            assert!(
+                err.to_string().contains(&format!(
+                    "{} compressed input requires the {} feature",
+                    name, name
+                )),
+                "{}",
+                err
+            );
+        }
+        assert_eq!(
+            read("no-such-wordlist.txt").unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+
+        assert!(is_stdin_input("-"));
+        assert!(is_stdin_input(STDIN_INPUT));
+        assert!(!is_stdin_input("./-"));
+        assert!(!is_stdin_input(wordlist_fname("wordlist1.txt")));
+    }
+
+    #[test]
+    fn test_netstring_reader() {
+        let read = |data: &'static [u8]| -> Result<Vec<Vec<u8>>, std::io::Error> {
+            NetstringReader::new(data).collect()
+        };
+        assert_eq!(
+            read(b"5:hello,0:,3:a\nb,\n4:\x00,:\r,\r\n").unwrap(),
+            vec![
+                b"hello".to_vec(),
+                b"".to_vec(),
+                b"a\nb".to_vec(),
+                b"\x00,:\r".to_vec()
+            ]
+        );
+        assert!(read(b"").unwrap().is_empty());
+        assert!(read(b" \n").unwrap().is_empty());
+
+        for invalid in [
+            &b"hello"[..],
+            b"5hello,",
+            b":hello,",
+            b"x:hello,",
+            b"5:hell",
+            b"5:hello",
+            b"5:hello;",
+            b"99999999999999999999999:a,",
+        ] {
+            let err = read(invalid).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_top_n() {
+        let mut top = TopN::new(3);
+        for item in [5, 1, 9, 3, 7, 9, 2] {
+            top.push(item);
+        }
+        assert_eq!(top.into_sorted_vec(), vec![9, 9, 7]);
+
+        let mut bottom = TopN::new(2);
+        for item in [5, 1, 9, 3] {
+            bottom.push(std::cmp::Reverse(item));
+        }
+        let bottom: Vec<_> = bottom.into_sorted_vec().into_iter().map(|r| r.0).collect();
+        assert_eq!(bottom, vec![1, 3]);
+
+        let mut top = TopN::new(2);
+        assert!(top.would_keep(|_| false));
+        top.push(5);
+        top.push(3);
+        assert!(top.would_keep(|&smallest| 4 > smallest));
+        assert!(!top.would_keep(|&smallest| 2 > smallest));
+
+        let mut empty = TopN::new(0);
+        assert!(!empty.would_keep(|_| true));
+        empty.push(1);
+        assert!(empty.into_sorted_vec().is_empty());
+    }
+
+    #[cfg(all(feature = "mmap", unix))]
+    #[test]
+    fn test_mmap() {
+        let file = File::open(wordlist_fname("wordlist-3words.txt")).unwrap();
+        let mmap = crate::helpers::Mmap::open(&file).unwrap();
+        assert_eq!(&mmap[..], b"alpha\nbeta\ngamma\n");
+    }
 }
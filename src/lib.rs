@@ -8,13 +8,22 @@ extern crate regex;
 use std::error::Error;
 
 pub mod charsets;
+pub mod checksum;
+pub mod config;
 pub mod create_smartlist;
 pub mod generators;
 pub mod helpers;
+pub mod manifest;
 pub mod mask;
+pub mod masks_file;
+pub mod model;
+pub mod normalize;
+pub mod output;
 pub mod password_entropy;
 pub mod runner;
+pub mod sample;
 pub mod stackbuf;
+pub mod substrings;
 pub mod wordlists;
 
 pub mod built_info {
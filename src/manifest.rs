@@ -0,0 +1,313 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use num_bigint::BigUint;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::built_info;
+use crate::helpers::open_input;
+use crate::wordlists::{wordlist_files, WordlistFormat, WordlistOptions};
+use crate::BoxResult;
+
+/// describes a generation run so it can be reproduced later
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    pub masks: Vec<String>,
+    pub custom_charsets: Vec<String>,
+    pub wordlists: Vec<String>,
+    pub wordlist_options: WordlistOptions,
+    pub minlen: Option<usize>,
+    pub maxlen: Option<usize>,
+    /// total min and max lengths of --combine
+    pub combine: Option<(usize, usize)>,
+    pub combinations: BigUint,
+    /// the other options changing the generated words as given, e.g. `{"prefix": "x"}`
+    pub options: Map<String, Value>,
+    /// the files written with --output-dir, one per mask
+    pub output_files: Vec<String>,
+    /// the words generated before the run stopped early, by the --max-time deadline or a
+    /// closed output (e.g. piped to head)
+    pub stopped_after: Option<u64>,
+}
+
+/// hashes a record terminated by its delimiter, so a file digest does not depend on whether
+/// its last record ends with one. for newline terminated files it is their sha256sum
+fn update_record(hasher: &mut Sha256, format: WordlistFormat, record: &[u8]) {
+    match format {
+        WordlistFormat::Lines => {
+            hasher.update(record);
+            hasher.update(b"\n");
+        }
+        WordlistFormat::Nul => {
+            hasher.update(record);
+            hasher.update(b"\0");
+        }
+        WordlistFormat::Netstring => {
+            hasher.update(format!("{}:", record.len()).as_bytes());
+            hasher.update(record);
+            hasher.update(b",");
+        }
+    }
+}
+
+/// returns the number of records (e.g. lines) and the hex sha256 of the records of the given
+/// files concatenated, parsed by `options`. compressed files are digested decompressed
+fn files_digest<P: AsRef<Path>>(
+    fnames: &[P],
+    options: &WordlistOptions,
+) -> BoxResult<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let mut records = 0;
+    for fname in fnames {
+        for record in options.records(open_input(fname)?) {
+            update_record(&mut hasher, options.wordlist_format(), &record?);
+            records += 1;
+        }
+    }
+    let digest = hasher.finalize();
+    Ok((
+        records,
+        digest.iter().map(|b| format!("{:02x}", b)).collect(),
+    ))
+}
+
+/// returns the hex sha256 of the first `records` records (e.g. lines) of a file parsed by
+/// `options`, hashed like the manifest wordlists, or None if it has fewer records
+pub fn records_digest<P: AsRef<Path>>(
+    fname: P,
+    options: &WordlistOptions,
+    records: u64,
+) -> BoxResult<Option<String>> {
+    let mut hasher = Sha256::new();
+    let mut count = 0;
+    for record in options.records(open_input(fname)?) {
+        if count == records {
+            break;
+        }
+        update_record(&mut hasher, options.wordlist_format(), &record?);
+        count += 1;
+    }
+    if count < records {
+        return Ok(None);
+    }
+    let digest = hasher.finalize();
+    Ok(Some(digest.iter().map(|b| format!("{:02x}", b)).collect()))
+}
+
+/// a wordlist recorded in a manifest file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestWordlist {
+    pub filename: String,
+    /// the number of records (e.g. lines) of the wordlist
+    pub records: u64,
+    pub sha256: String,
+    /// the name of its `WordlistFormat`
+    pub format: String,
+}
+
+/// returns the wordlists recorded in a manifest file
+pub fn read_manifest_wordlists<P: AsRef<Path>>(fname: P) -> BoxResult<Vec<ManifestWordlist>> {
+    let value: Value = serde_json::from_reader(BufReader::new(File::open(fname)?))?;
+    let wordlists = match value["wordlists"].as_array() {
+        Some(wordlists) => wordlists,
+        None => bail!("manifest has no wordlists"),
+    };
+    wordlists
+        .iter()
+        .map(|wordlist| {
+            // manifests of older versions record newline terminated lines only
+            let records = wordlist["records"].as_u64().or(wordlist["lines"].as_u64());
+            let format = wordlist["format"].as_str().unwrap_or("lines");
+            match (
+                wordlist["filename"].as_str(),
+                records,
+                wordlist["sha256"].as_str(),
+            ) {
+                (Some(fname), Some(records), Some(sha256)) => Ok(ManifestWordlist {
+                    filename: fname.to_owned(),
+                    records,
+                    sha256: sha256.to_owned(),
+                    format: format.to_owned(),
+                }),
+                _ => bail!("invalid manifest wordlist: {}", wordlist),
+            }
+        })
+        .collect()
+}
+
+impl Manifest {
+    pub fn to_json(&self) -> BoxResult<Value> {
+        let wordlists = self
+            .wordlists
+            .iter()
+            .map(|fname| {
+                let (records, sha256) = files_digest(
+                    &wordlist_files(fname, &self.wordlist_options)?,
+                    &self.wordlist_options,
+                )?;
+                Ok(json!({
+                    "filename": fname,
+                    "format": self.wordlist_options.wordlist_format().name(),
+                    "records": records,
+                    "sha256": sha256,
+                }))
+            })
+            .collect::<BoxResult<Vec<_>>>()?;
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+        Ok(json!({
+            "version": built_info::PKG_VERSION,
+            "timestamp": timestamp,
+            "masks": self.masks,
+            "custom_charsets": self.custom_charsets,
+            "wordlists": wordlists,
+            "minlen": self.minlen,
+            "maxlen": self.maxlen,
+            "combine": self.combine.map(|(total_min, total_max)| {
+                json!({"total_min": total_min, "total_max": total_max})
+            }),
+            // as a string since it may not fit in a json number
+            "combinations": self.combinations.to_string(),
+            "options": self.options,
+            "output_files": self.output_files,
+            "stopped_after": self.stopped_after,
+        }))
+    }
+
+    pub fn to_file<P: AsRef<Path>>(&self, fname: P) -> BoxResult<()> {
+        let mut fp = BufWriter::new(File::create(fname)?);
+        serde_json::to_writer_pretty(&mut fp, &self.to_json()?)?;
+        fp.write_all(b"\n")?;
+        fp.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::ToBigUint;
+
+    use crate::test_util::wordlist_fname;
+
+    use crate::wordlists::{WordlistFormat, WordlistOptions};
+
+    use super::{read_manifest_wordlists, records_digest, Manifest};
+
+    #[test]
+    fn test_manifest_to_json() {
+        let wordlist = wordlist_fname("wordlist-3words.txt");
+        let manifest = Manifest {
+            masks: vec!["?w1?d".to_owned()],
+            custom_charsets: vec!["abc".to_owned()],
+            wordlists: vec![wordlist.to_str().unwrap().to_owned()],
+            minlen: Some(2),
+            combinations: 30.to_biguint().unwrap(),
+            ..Manifest::default()
+        };
+        let value = manifest.to_json().unwrap();
+
+        assert_eq!(value["masks"][0], "?w1?d");
+        assert_eq!(value["custom_charsets"][0], "abc");
+        assert_eq!(value["minlen"], 2);
+        assert!(value["maxlen"].is_null());
+        assert!(value["combine"].is_null());
+        assert!(value["stopped_after"].is_null());
+        assert_eq!(value["combinations"], "30");
+        assert_eq!(value["wordlists"][0]["records"], 3);
+        assert_eq!(value["wordlists"][0]["format"], "lines");
+        // sha256 of "alpha\nbeta\ngamma\n"
+        assert_eq!(
+            value["wordlists"][0]["sha256"],
+            "4fdbc441ea7b546100e086ac1e4fc5ae6749b7314311c99db05be450eca12996"
+        );
+
+        let fname = std::env::temp_dir().join("cracken-test-manifest-wordlists.json");
+        manifest.to_file(&fname).unwrap();
+        let wordlists = read_manifest_wordlists(&fname).unwrap();
+        std::fs::remove_file(&fname).unwrap();
+        assert_eq!(wordlists.len(), 1);
+        assert_eq!(wordlists[0].records, 3);
+        assert_eq!(wordlists[0].format, "lines");
+        let options = WordlistOptions::new();
+        let digest = records_digest(&wordlist, &options, 3).unwrap();
+        assert_eq!(digest.unwrap(), wordlists[0].sha256);
+    }
+
+    #[test]
+    fn test_manifest_records() {
+        // the last line without a newline is counted, with the digest of the same lines
+        // newline terminated
+        let fname = std::env::temp_dir().join("cracken-test-manifest-records.txt");
+        std::fs::write(&fname, b"alpha\nbeta").unwrap();
+        let manifest = Manifest {
+            wordlists: vec![fname.to_str().unwrap().to_owned()],
+            ..Manifest::default()
+        };
+        let value = manifest.to_json();
+        std::fs::remove_file(&fname).unwrap();
+        let value = value.unwrap();
+        assert_eq!(value["wordlists"][0]["records"], 2);
+        // sha256 of "alpha\nbeta\n"
+        assert_eq!(
+            value["wordlists"][0]["sha256"],
+            "e49c81e2d2f84e259d40e2fb8192f3bcd198b355184845d76d8f58807d0d78ee"
+        );
+
+        // NUL delimited records may contain newlines, blank records are counted too
+        let wordlist = wordlist_fname("wordlist-nul.txt");
+        let manifest = Manifest {
+            wordlists: vec![wordlist.to_str().unwrap().to_owned()],
+            wordlist_options: WordlistOptions::new().format(WordlistFormat::Nul),
+            ..Manifest::default()
+        };
+        let value = manifest.to_json().unwrap();
+        assert_eq!(value["wordlists"][0]["records"], 5);
+        assert_eq!(value["wordlists"][0]["format"], "nul");
+    }
+
+    #[test]
+    fn test_records_digest_compressed() {
+        let options = WordlistOptions::new();
+        let compressed = records_digest(wordlist_fname("wordlist1.txt.gz"), &options, 10);
+        #[cfg(feature = "gzip")]
+        {
+            let digest = records_digest(wordlist_fname("wordlist1.txt"), &options, 10);
+            assert_eq!(compressed.unwrap(), digest.unwrap());
+        }
+        #[cfg(not(feature = "gzip"))]
+        assert!(compressed.is_err());
+        let digest = records_digest(wordlist_fname("wordlist1.txt"), &options, 10).unwrap();
+        for (fname, supported) in [
+            ("wordlist1.txt.zst", cfg!(feature = "zstd")),
+            ("wordlist1.txt.xz", cfg!(feature = "xz")),
+        ] {
+            let compressed = records_digest(wordlist_fname(fname), &options, 10);
+            if supported {
+                assert_eq!(compressed.unwrap(), digest);
+            } else {
+                assert!(compressed.is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_records_digest() {
+        let wordlist = wordlist_fname("wordlist-3words.txt");
+        let options = WordlistOptions::new();
+        // sha256 of "alpha\nbeta\n"
+        let digest = "e49c81e2d2f84e259d40e2fb8192f3bcd198b355184845d76d8f58807d0d78ee";
+        let digest = Some(digest.to_owned());
+        assert_eq!(records_digest(&wordlist, &options, 2).unwrap(), digest);
+        assert_eq!(records_digest(&wordlist, &options, 4).unwrap(), None);
+
+        // the last line is hashed newline terminated
+        let fname = std::env::temp_dir().join("cracken-test-records-digest.txt");
+        std::fs::write(&fname, b"alpha\nbeta").unwrap();
+        let no_trailing_newline = records_digest(&fname, &options, 2);
+        std::fs::remove_file(&fname).unwrap();
+        assert_eq!(no_trailing_newline.unwrap(), digest);
+    }
+}
@@ -1,3 +1,9 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::charsets::find_symbol;
 use crate::{BoxResult, MAX_WORD_SIZE};
 use regex::Regex;
 
@@ -5,6 +11,8 @@ use regex::Regex;
 pub enum MaskOp {
     Char(char),
     BuiltinCharset(char),
+    /// a builtin charset without the given chars, e.g. `?l{-aeiou}` for consonants
+    BuiltinCharsetExcept(char, String),
     CustomCharset(usize),
     Wordlist(usize),
 }
@@ -14,20 +22,142 @@ impl Clone for MaskOp {
         match self {
             MaskOp::Char(ch) => MaskOp::Char(*ch),
             MaskOp::BuiltinCharset(ch) => MaskOp::BuiltinCharset(*ch),
+            MaskOp::BuiltinCharsetExcept(ch, except) => {
+                MaskOp::BuiltinCharsetExcept(*ch, except.clone())
+            }
             MaskOp::CustomCharset(idx) => MaskOp::CustomCharset(*idx),
             MaskOp::Wordlist(idx) => MaskOp::Wordlist(*idx),
         }
     }
 }
 
-/// parses `mask` string into the operations it means
+/// a parsed mask - a sequence of positions, each a char, charset or wordlist
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mask {
+    ops: Vec<MaskOp>,
+}
+
+impl Mask {
+    pub fn new(ops: Vec<MaskOp>) -> Mask {
+        Mask { ops }
+    }
+
+    pub fn ops(&self) -> &[MaskOp] {
+        &self.ops
+    }
+
+    pub fn into_ops(self) -> Vec<MaskOp> {
+        self.ops
+    }
+
+    /// returns the number of positions of the mask
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// returns the length of the longest generated word, or None if the mask has wordlists
+    pub fn max_len(&self) -> Option<usize> {
+        if self.has_wordlists() {
+            None
+        } else {
+            Some(self.ops.len())
+        }
+    }
+
+    pub fn has_wordlists(&self) -> bool {
+        self.ops.iter().any(|op| matches!(op, MaskOp::Wordlist(_)))
+    }
+
+    /// returns the sorted and unique zero based indices of custom charsets used by the mask
+    pub fn custom_charset_refs(&self) -> Vec<usize> {
+        self.refs(|op| match op {
+            MaskOp::CustomCharset(idx) => Some(*idx),
+            _ => None,
+        })
+    }
+
+    /// returns the sorted and unique zero based indices of wordlists used by the mask
+    pub fn wordlist_refs(&self) -> Vec<usize> {
+        self.refs(|op| match op {
+            MaskOp::Wordlist(idx) => Some(*idx),
+            _ => None,
+        })
+    }
+
+    /// validates all custom charsets and wordlists referenced by the mask are provided
+    pub fn validate(&self, custom_charsets_len: usize, wordlists_len: usize) -> BoxResult<()> {
+        validate_charsets(&self.ops, custom_charsets_len)?;
+        validate_wordlists(&self.ops, wordlists_len)
+    }
+
+    fn refs<F: Fn(&MaskOp) -> Option<usize>>(&self, f: F) -> Vec<usize> {
+        let mut refs: Vec<_> = self.ops.iter().filter_map(f).collect();
+        refs.sort_unstable();
+        refs.dedup();
+        refs
+    }
+}
+
+impl FromStr for Mask {
+    type Err = Box<dyn Error>;
+
+    fn from_str(mask: &str) -> Result<Self, Self::Err> {
+        Ok(Mask::new(parse_mask(mask)?))
+    }
+}
+
+impl fmt::Display for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut prev_op: Option<&MaskOp> = None;
+        for op in self.ops.iter() {
+            match op {
+                MaskOp::Char(ch) if *ch == '?' || *ch == '\\' => write!(f, "\\{}", ch)?,
+                // a brace right after a builtin charset would start a chars exclusion
+                MaskOp::Char('{')
+                    if matches!(
+                        prev_op,
+                        Some(MaskOp::BuiltinCharset(_) | MaskOp::BuiltinCharsetExcept(..))
+                    ) =>
+                {
+                    write!(f, "\\{{")?
+                }
+                MaskOp::Char(ch) => write!(f, "{}", ch)?,
+                MaskOp::BuiltinCharset(ch) => write!(f, "?{}", ch)?,
+                MaskOp::BuiltinCharsetExcept(ch, except) => {
+                    write!(f, "?{}{{-", ch)?;
+                    for ch in except.chars() {
+                        if ch == '}' || ch == '\\' {
+                            write!(f, "\\")?;
+                        }
+                        write!(f, "{}", ch)?;
+                    }
+                    write!(f, "}}")?
+                }
+                MaskOp::CustomCharset(idx) => write!(f, "?{}", idx + 1)?,
+                MaskOp::Wordlist(idx) => write!(f, "?w{}", idx + 1)?,
+            }
+            prev_op = Some(op);
+        }
+        Ok(())
+    }
+}
+
+/// parses `mask` string into the operations it means.
+///
+/// a builtin charset followed by braces of a dash and chars of that charset excludes them from
+/// it, e.g. `?d{-0}` for 1 to 9. `}` and `\` are escaped inside the braces as `\}` and `\\`, and
+/// a literal `{` right after a charset as `\{`
 pub fn parse_mask(mask: &str) -> BoxResult<Vec<MaskOp>> {
     if !is_valid_mask(mask) {
         bail!("Invalid mask");
     }
 
     let mut mask_ops = vec![];
-    let mut chars = mask.chars();
+    let mut chars = mask.chars().peekable();
     let mut next = chars.next();
 
     while next.is_some() {
@@ -40,7 +170,7 @@ pub fn parse_mask(mask: &str) -> BoxResult<Vec<MaskOp>> {
                 let next_chr = chars.next().unwrap();
 
                 // 2.1 custom charset
-                if next_chr.is_digit(10) {
+                if next_chr.is_ascii_digit() {
                     mask_ops.push(MaskOp::CustomCharset(((next_chr as u8) - b'1') as usize))
 
                 // 2.2 wordlist
@@ -48,7 +178,52 @@ pub fn parse_mask(mask: &str) -> BoxResult<Vec<MaskOp>> {
                     let idx = chars.next().unwrap();
                     mask_ops.push(MaskOp::Wordlist(((idx as u8) - b'1') as usize));
 
-                // 2.3 builtin charset
+                // 2.3 builtin charset, optionally excluding some of its chars
+                } else if chars.next_if_eq(&'{').is_some() {
+                    if chars.next_if_eq(&'-').is_none() {
+                        bail!(
+                            "a literal {{ right after ?{} must be escaped as \\{{",
+                            next_chr
+                        );
+                    }
+                    let mut except = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('\\') if chars.peek().is_some() => {
+                                except.push(chars.next().unwrap())
+                            }
+                            Some('}') => break,
+                            Some(ch) => except.push(ch),
+                            None => bail!("unterminated ?{}{{-{}", next_chr, except),
+                        }
+                    }
+                    if except.is_empty() {
+                        bail!("?{}{{-}} excludes no chars", next_chr);
+                    }
+                    let charset = find_symbol(next_chr).unwrap().chars;
+                    if let Some(ch) = except
+                        .chars()
+                        .find(|&ch| !ch.is_ascii() || !charset.contains(&(ch as u8)))
+                    {
+                        bail!(
+                            "?{}{{-{}}} excludes {:?}, not a char of ?{}",
+                            next_chr,
+                            except,
+                            ch,
+                            next_chr
+                        );
+                    }
+                    let mut excluded: Vec<char> = except.chars().collect();
+                    excluded.sort_unstable();
+                    excluded.dedup();
+                    if excluded.len() == charset.len() {
+                        bail!(
+                            "?{}{{-{}}} excludes all the chars of the charset",
+                            next_chr,
+                            except
+                        );
+                    }
+                    mask_ops.push(MaskOp::BuiltinCharsetExcept(next_chr, except));
                 } else {
                     mask_ops.push(MaskOp::BuiltinCharset(next_chr))
                 }
@@ -58,6 +233,15 @@ pub fn parse_mask(mask: &str) -> BoxResult<Vec<MaskOp>> {
         }
         next = chars.next();
     }
+
+    // every position generates at least one byte, and the words are built in fixed buffers
+    if mask_ops.len() >= MAX_WORD_SIZE {
+        bail!(
+            "mask has {} positions, exceeding the max word size of {}",
+            mask_ops.len(),
+            MAX_WORD_SIZE - 1
+        );
+    }
     Ok(mask_ops)
 }
 
@@ -69,15 +253,12 @@ pub fn validate_charsets(mask: &[MaskOp], customer_charests_len: usize) -> BoxRe
             _ => None,
         })
         .max();
-    match max_charset_len {
-        None => {}
-        Some(&n) => {
-            if n >= customer_charests_len {
-                bail!(
-                    "mask contains unspecified custom charset: ?{} - please add use -c \"<chars>\"",
-                    n + 1
-                );
-            }
+    if let Some(&n) = max_charset_len {
+        if n >= customer_charests_len {
+            bail!(
+                "mask contains unspecified custom charset: ?{} - please add use -c \"<chars>\"",
+                n + 1
+            );
         }
     }
     Ok(())
@@ -91,38 +272,122 @@ pub fn validate_wordlists(mask: &[MaskOp], wordlists_len: usize) -> BoxResult<()
             _ => None,
         })
         .max();
-    match max_wordlist_len {
-        None => {}
-        Some(&n) => {
-            if n >= wordlists_len {
-                bail!(
-                    "mask contains unspecified wordlist: ?w{} - please add -w <wordlist_file>",
-                    n + 1
-                );
-            }
+    if let Some(&n) = max_wordlist_len {
+        if n >= wordlists_len {
+            bail!(
+                "mask contains unspecified wordlist: ?w{} - please add -w <wordlist_file>",
+                n + 1
+            );
         }
     }
     Ok(())
 }
 
+/// the wordlists and custom charsets given inline in a mask by their index, see
+/// `extract_inline_resources`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct InlineResources {
+    pub custom_charsets: BTreeMap<usize, String>,
+    pub wordlists: BTreeMap<usize, String>,
+}
+
+impl InlineResources {
+    pub fn is_empty(&self) -> bool {
+        self.custom_charsets.is_empty() && self.wordlists.is_empty()
+    }
+}
+
+/// splits the resources given inline in `mask` off it - `?w1{names.txt}` loads names.txt as
+/// the first wordlist and `?1{abc}` uses `abc` as the first custom charset. a `}` inside the
+/// braces is escaped as `\}`, and a literal `{` right after a charset as `\{`.
+/// returns the mask without them, referencing the same index more than once needs the same
+/// resource or none
+pub fn extract_inline_resources(mask: &str) -> BoxResult<(String, InlineResources)> {
+    let mut stripped = String::with_capacity(mask.len());
+    let mut resources = InlineResources::default();
+    let mut chars = mask.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        stripped.push(ch);
+        let symbol = match ch {
+            '\\' => {
+                stripped.extend(chars.next());
+                continue;
+            }
+            '?' if chars.peek() == Some(&'w') => {
+                stripped.extend(chars.next());
+                "?w"
+            }
+            '?' => "?",
+            _ => continue,
+        };
+        let idx = match chars.next() {
+            Some(idx) => {
+                stripped.push(idx);
+                idx
+            }
+            None => break,
+        };
+        let idx = match idx.to_digit(10) {
+            Some(idx @ 1..=9) if chars.peek() == Some(&'{') => idx as usize - 1,
+            _ => continue,
+        };
+
+        chars.next();
+        let mut value = String::new();
+        loop {
+            match chars.next() {
+                Some('\\') if chars.peek() == Some(&'}') => value.push(chars.next().unwrap()),
+                Some('}') => break,
+                Some(ch) => value.push(ch),
+                None => bail!("unterminated {}{}{{ in mask", symbol, idx + 1),
+            }
+        }
+        let refs = match symbol {
+            "?w" => &mut resources.wordlists,
+            _ => &mut resources.custom_charsets,
+        };
+        insert_inline(refs, symbol, idx, value)?;
+    }
+    Ok((stripped, resources))
+}
+
+/// adds an inline resource of index `idx`, failing if a different one was already given
+pub fn insert_inline(
+    refs: &mut BTreeMap<usize, String>,
+    symbol: &str,
+    idx: usize,
+    value: String,
+) -> BoxResult<()> {
+    match refs.get(&idx) {
+        Some(existing) if *existing != value => bail!(
+            "{}{} is given inline as both {:?} and {:?}",
+            symbol,
+            idx + 1,
+            existing,
+            value
+        ),
+        _ => {
+            refs.insert(idx, value);
+            Ok(())
+        }
+    }
+}
+
 /// returns true iff the mask is valid
 fn is_valid_mask(mask: &str) -> bool {
     lazy_static! {
-        static ref RE: Regex = Regex::new(
-            format!(
-                r"^(\?[ludsab1-9]|\?w[1-9]|\\.|[^?\\]){{1,{}}}$",
-                MAX_WORD_SIZE - 1
-            )
-            .as_str()
-        )
-        .unwrap();
+        // the length is validated after parsing, by the number of positions
+        static ref RE: Regex =
+            Regex::new(r"^(\?[ludsab]|\?[1-9]|\?w[1-9]|\\.|[^?\\])+$").unwrap();
     }
     RE.is_match(mask)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{is_valid_mask, parse_mask, MaskOp};
+    use super::{extract_inline_resources, is_valid_mask, parse_mask, Mask, MaskOp};
+    use crate::MAX_WORD_SIZE;
 
     #[test]
     fn test_is_valid_mask() {
@@ -182,4 +447,179 @@ mod tests {
             assert_eq!(mask_ops, expected);
         }
     }
+
+    #[test]
+    fn test_mask_round_trip() {
+        let masks = vec![
+            "?d?d",
+            "?l?u?a?b?s",
+            "a ?ld?1?2?w2b\\?a?w1",
+            "\\\\?d",
+            "pwd?u?l201?1",
+            "?l{-aeiou}?d{-0}",
+            "?d-0",
+            "?d\\{-0}",
+            "?s{-\\}\\\\}?s{-\\}}",
+        ];
+        for mask_str in masks {
+            let mask: Mask = mask_str.parse().unwrap();
+            assert_eq!(mask.to_string(), mask_str);
+            assert_eq!(mask.to_string().parse::<Mask>().unwrap(), mask);
+        }
+
+        // escaping of regular chars is dropped
+        let mask: Mask = "\\a?d".parse().unwrap();
+        assert_eq!(mask.to_string(), "a?d");
+    }
+
+    #[test]
+    fn test_parse_mask_except() {
+        let cases = vec![
+            (
+                "?d{-0}",
+                vec![MaskOp::BuiltinCharsetExcept('d', "0".to_owned())],
+            ),
+            (
+                "?l{-aeiou}?d",
+                vec![
+                    MaskOp::BuiltinCharsetExcept('l', "aeiou".to_owned()),
+                    MaskOp::BuiltinCharset('d'),
+                ],
+            ),
+            (
+                "?s{-\\}\\\\-}",
+                vec![MaskOp::BuiltinCharsetExcept('s', "}\\-".to_owned())],
+            ),
+            // a dash after a charset is always a literal dash
+            (
+                "?d-0",
+                vec![
+                    MaskOp::BuiltinCharset('d'),
+                    MaskOp::Char('-'),
+                    MaskOp::Char('0'),
+                ],
+            ),
+            (
+                "?d\\{-0}",
+                vec![
+                    MaskOp::BuiltinCharset('d'),
+                    MaskOp::Char('{'),
+                    MaskOp::Char('-'),
+                    MaskOp::Char('0'),
+                    MaskOp::Char('}'),
+                ],
+            ),
+            (
+                "a{-0}",
+                vec![
+                    MaskOp::Char('a'),
+                    MaskOp::Char('{'),
+                    MaskOp::Char('-'),
+                    MaskOp::Char('0'),
+                    MaskOp::Char('}'),
+                ],
+            ),
+        ];
+        for (mask, expected) in cases {
+            assert_eq!(parse_mask(mask).unwrap(), expected, "{}", mask);
+            let mask: Mask = mask.parse().unwrap();
+            assert_eq!(mask.to_string().parse::<Mask>().unwrap(), mask);
+        }
+
+        for mask in [
+            "?d{-0123456789}",
+            "?d{-01234567890}",
+            "?d{-a}",
+            "?d{-}",
+            "?d{-0",
+            "?d{0}",
+            "?d{",
+        ] {
+            assert!(parse_mask(mask).is_err(), "{}", mask);
+        }
+        assert!(parse_mask("?d{-012345678}").is_ok());
+    }
+
+    #[test]
+    fn test_parse_mask_literal_dash() {
+        assert_eq!(
+            parse_mask("20?d?d-01-01").unwrap(),
+            vec![
+                MaskOp::Char('2'),
+                MaskOp::Char('0'),
+                MaskOp::BuiltinCharset('d'),
+                MaskOp::BuiltinCharset('d'),
+                MaskOp::Char('-'),
+                MaskOp::Char('0'),
+                MaskOp::Char('1'),
+                MaskOp::Char('-'),
+                MaskOp::Char('0'),
+                MaskOp::Char('1'),
+            ]
+        );
+        let mask: Mask = "20?d?d-01-01".parse().unwrap();
+        assert_eq!(mask.to_string(), "20?d?d-01-01");
+    }
+
+    #[test]
+    fn test_parse_mask_max_word_size() {
+        let mask = "?d".repeat(MAX_WORD_SIZE - 1);
+        assert_eq!(parse_mask(&mask).unwrap().len(), MAX_WORD_SIZE - 1);
+
+        for mask in [
+            "?d".repeat(MAX_WORD_SIZE),
+            "?b".repeat(600),
+            "a".repeat(MAX_WORD_SIZE),
+            "?l{-aeiou}".repeat(MAX_WORD_SIZE),
+        ] {
+            let err = parse_mask(&mask).err().unwrap();
+            assert!(err.to_string().contains("exceeding the max word size"));
+        }
+    }
+
+    #[test]
+    fn test_mask_refs() {
+        let mask: Mask = "?w2?d?1?w2?3a".parse().unwrap();
+        assert_eq!(mask.len(), 6);
+        assert_eq!(mask.max_len(), None);
+        assert!(mask.has_wordlists());
+        assert_eq!(mask.wordlist_refs(), vec![1]);
+        assert_eq!(mask.custom_charset_refs(), vec![0, 2]);
+        assert!(mask.validate(3, 2).is_ok());
+        assert!(mask.validate(2, 2).is_err());
+        assert!(mask.validate(3, 1).is_err());
+
+        let mask: Mask = "?d?l".parse().unwrap();
+        assert_eq!(mask.max_len(), Some(2));
+        assert!(!mask.has_wordlists());
+        assert!("?x".parse::<Mask>().is_err());
+    }
+
+    #[test]
+    fn test_extract_inline_resources() {
+        let (mask, resources) =
+            extract_inline_resources("?w1{names.txt}?d?d?1{abc}?w1{names.txt}?w2").unwrap();
+        assert_eq!(mask, "?w1?d?d?1?w1?w2");
+        assert_eq!(
+            resources.wordlists.into_iter().collect::<Vec<_>>(),
+            vec![(0, "names.txt".to_owned())]
+        );
+        assert_eq!(
+            resources.custom_charsets.into_iter().collect::<Vec<_>>(),
+            vec![(0, "abc".to_owned())]
+        );
+
+        // escaped braces and masks without inline resources are kept as is
+        let (mask, resources) = extract_inline_resources(r"?2{a\}b}?d\{x}\?1{").unwrap();
+        assert_eq!(mask, r"?2?d\{x}\?1{");
+        assert_eq!(resources.custom_charsets[&1], "a}b");
+        for mask in ["?d?l", r"a\\?w1", "?d{x}", "?", "?w"] {
+            let (stripped, resources) = extract_inline_resources(mask).unwrap();
+            assert_eq!(stripped, mask);
+            assert!(resources.is_empty());
+        }
+
+        assert!(extract_inline_resources("?w1{a.txt}?w1{b.txt}").is_err());
+        assert!(extract_inline_resources("?1{abc").is_err());
+    }
 }
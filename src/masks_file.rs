@@ -0,0 +1,348 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::charsets::read_charset_file;
+use crate::helpers::{is_stdin_input, open_input};
+use crate::BoxResult;
+
+/// separates a mask from its custom charsets on a masks file line
+const CUSTOM_CHARSET_SEP: &str = " -c ";
+
+/// a single mask to generate with its own custom charsets and wordlists, if any
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskEntry {
+    pub mask: String,
+    pub custom_charsets: Option<Vec<String>>,
+    /// the wordlists of a masks file line given inline (e.g. `?w1{names.txt}`)
+    pub wordlists: Option<Vec<String>>,
+}
+
+impl MaskEntry {
+    /// a mask using the global custom charsets
+    pub fn new(mask: String) -> MaskEntry {
+        MaskEntry {
+            mask,
+            custom_charsets: None,
+            wordlists: None,
+        }
+    }
+}
+
+/// parses a masks file line of the format `<mask>[ -c <charset1>[ -c <charset2>...]]`.
+/// a mask containing a literal " -c " should escape it as " \-c "
+pub fn parse_masks_line(line: &str) -> MaskEntry {
+    let mut parts = line.split(CUSTOM_CHARSET_SEP);
+    let mask = parts.next().unwrap_or_default().to_owned();
+    let custom_charsets: Vec<_> = parts.map(|charset| charset.to_owned()).collect();
+
+    MaskEntry {
+        mask,
+        custom_charsets: if custom_charsets.is_empty() {
+            None
+        } else {
+            Some(custom_charsets)
+        },
+        wordlists: None,
+    }
+}
+
+/// parses a hashcat .hcmask line of the format `[charset1,[charset2,...]]mask`.
+/// commas may be escaped as `\,` and `??` is a literal `?` in the mask.
+/// returns None for empty lines and `#` comments
+pub fn parse_hcmask_line(line: &str) -> Option<MaskEntry> {
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    // split by unescaped commas
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' if matches!(chars.peek(), Some(',') | Some('#')) => {
+                field.push(chars.next().unwrap());
+            }
+            ',' => fields.push(std::mem::take(&mut field)),
+            _ => field.push(ch),
+        }
+    }
+
+    // convert hashcat mask escaping to cracken mask escaping
+    let mut mask = String::with_capacity(field.len());
+    let mut chars = field.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '?' if chars.peek() == Some(&'?') => {
+                chars.next();
+                mask.push_str("\\?");
+            }
+            '\\' => mask.push_str("\\\\"),
+            _ => mask.push(ch),
+        }
+    }
+
+    Some(MaskEntry {
+        mask,
+        custom_charsets: if fields.is_empty() {
+            None
+        } else {
+            Some(fields)
+        },
+        wordlists: None,
+    })
+}
+
+/// returns true if `fname` is a hashcat .hcmask file by its extension
+pub fn is_hcmask_file<P: AsRef<Path>>(fname: P) -> bool {
+    fname
+        .as_ref()
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("hcmask"))
+}
+
+/// returns the non blank lines of a masks file, trimmed of surrounding whitespace. lines may
+/// end with `\n`, `\r\n` or a lone `\r`. escape a space to keep it at the start or the end
+/// of a mask (e.g. `?d\ `)
+fn read_mask_lines<P: AsRef<Path>>(fname: P) -> BoxResult<Vec<String>> {
+    let mut content = String::new();
+    open_input(fname)?.read_to_string(&mut content)?;
+    Ok(content
+        .split(['\n', '\r'])
+        .map(trim_mask_line)
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_owned())
+        .collect())
+}
+
+/// trims the whitespace around a mask line, keeping an escaped trailing space
+fn trim_mask_line(line: &str) -> &str {
+    let line = line.trim_start();
+    let trimmed = line.trim_end();
+    // the number of backslashes before the trimmed whitespace, an odd one escapes it
+    let escapes = trimmed.len() - trimmed.trim_end_matches('\\').len();
+    if escapes % 2 == 1 && trimmed.len() < line.len() {
+        &line[..trimmed.len() + 1]
+    } else {
+        trimmed
+    }
+}
+
+/// reads all masks of a masks file, one mask per line
+pub fn read_masks_file<P: AsRef<Path>>(fname: P) -> BoxResult<Vec<MaskEntry>> {
+    Ok(read_mask_lines(fname)?
+        .iter()
+        .map(|line| parse_masks_line(line))
+        .collect())
+}
+
+/// reads all masks of a hashcat .hcmask file
+pub fn read_hcmask_file<P: AsRef<Path>>(fname: P) -> BoxResult<Vec<MaskEntry>> {
+    Ok(read_mask_lines(fname)?
+        .iter()
+        .filter_map(|line| parse_hcmask_line(line))
+        .collect())
+}
+
+/// returns the directory the files referenced by a masks file are relative to - the directory
+/// of the masks file, or the current directory for stdin
+pub fn masks_file_dir<P: AsRef<Path>>(fname: P) -> PathBuf {
+    let fname = fname.as_ref();
+    match fname.parent() {
+        Some(dir) if !is_stdin_input(fname) && !dir.as_os_str().is_empty() => dir.to_owned(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// resolves a file referenced by a masks file relative to `base_dir`. absolute paths and
+/// stdin are kept as is
+pub fn resolve_path<P: AsRef<Path>>(base_dir: P, fname: &str) -> String {
+    if is_stdin_input(fname) || Path::new(fname).is_absolute() {
+        return fname.to_owned();
+    }
+    base_dir.as_ref().join(fname).to_string_lossy().into_owned()
+}
+
+/// replaces the custom charsets of `entry` given as charset files (e.g. hashcat .hcmask lines
+/// referencing .hcchr files) by their content, the files relative to `base_dir`
+pub fn load_charset_files<P: AsRef<Path>>(entry: MaskEntry, base_dir: P) -> BoxResult<MaskEntry> {
+    let custom_charsets = match entry.custom_charsets {
+        Some(charsets) => Some(
+            charsets
+                .into_iter()
+                .map(|charset| {
+                    if !is_charset_file(&charset) {
+                        return Ok(charset);
+                    }
+                    let fname = resolve_path(&base_dir, &charset);
+                    read_charset_file(&fname)
+                        .map_err(|e| format!("cannot read charset file {}: {}", fname, e).into())
+                })
+                .collect::<BoxResult<Vec<_>>>()?,
+        ),
+        None => None,
+    };
+    Ok(MaskEntry {
+        custom_charsets,
+        ..entry
+    })
+}
+
+/// returns true if a masks file custom charset is a charset file name by its .hcchr extension
+fn is_charset_file(charset: &str) -> bool {
+    Path::new(charset)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("hcchr"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{
+        is_hcmask_file, load_charset_files, masks_file_dir, parse_hcmask_line, parse_masks_line,
+        read_hcmask_file, read_masks_file, resolve_path, trim_mask_line, MaskEntry,
+    };
+    use crate::test_util::wordlist_fname;
+
+    #[test]
+    fn test_parse_masks_line() {
+        assert_eq!(parse_masks_line("?d?1"), MaskEntry::new("?d?1".to_string()));
+        assert_eq!(
+            parse_masks_line("?1?2 -c abc -c x y"),
+            MaskEntry {
+                mask: "?1?2".to_string(),
+                custom_charsets: Some(vec!["abc".to_string(), "x y".to_string()]),
+                wordlists: None,
+            }
+        );
+        assert_eq!(
+            parse_masks_line("?d \\-c -c 01"),
+            MaskEntry {
+                mask: "?d \\-c".to_string(),
+                custom_charsets: Some(vec!["01".to_string()]),
+                wordlists: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_read_masks_file() {
+        let masks = read_masks_file(wordlist_fname("masks-charsets.txt")).unwrap();
+        assert_eq!(
+            masks,
+            vec![
+                MaskEntry::new("?1?d".to_string()),
+                MaskEntry {
+                    mask: "?1?2".to_string(),
+                    custom_charsets: Some(vec!["ab".to_string(), "XY".to_string()]),
+                    wordlists: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_masks_file_crlf() {
+        let masks = read_masks_file(wordlist_fname("masks-crlf.txt")).unwrap();
+        assert_eq!(
+            masks,
+            vec![
+                MaskEntry {
+                    mask: "?d?1".to_string(),
+                    custom_charsets: Some(vec!["ab".to_string()]),
+                    wordlists: None,
+                },
+                MaskEntry::new("?d?d".to_string()),
+                MaskEntry::new("?l".to_string()),
+                MaskEntry::new("?u\\ ".to_string()),
+            ]
+        );
+        let masks = read_hcmask_file(wordlist_fname("masks-crlf.txt")).unwrap();
+        assert_eq!(masks.len(), 4);
+        assert_eq!(masks[1], MaskEntry::new("?d?d".to_string()));
+    }
+
+    #[test]
+    fn test_trim_mask_line() {
+        assert_eq!(trim_mask_line("  ?d?d \t"), "?d?d");
+        assert_eq!(trim_mask_line("?d\\  "), "?d\\ ");
+        assert_eq!(trim_mask_line("?d\\\\  "), "?d\\\\");
+        assert_eq!(trim_mask_line("?d\\"), "?d\\");
+        assert_eq!(trim_mask_line(" \t "), "");
+    }
+
+    #[test]
+    fn test_parse_hcmask_line() {
+        let entry = |charsets: Option<Vec<&str>>, mask: &str| MaskEntry {
+            mask: mask.to_string(),
+            custom_charsets: charsets.map(|c| c.into_iter().map(String::from).collect()),
+            wordlists: None,
+        };
+        let cases = vec![
+            ("?d?d", Some(entry(None, "?d?d"))),
+            ("abc,?1?d", Some(entry(Some(vec!["abc"]), "?1?d"))),
+            ("ab,01,?1?2", Some(entry(Some(vec!["ab", "01"]), "?1?2"))),
+            ("a\\,b,?1\\,", Some(entry(Some(vec!["a,b"]), "?1,"))),
+            ("ab,??a?1", Some(entry(Some(vec!["ab"]), "\\?a?1"))),
+            ("a\\b", Some(entry(None, "a\\\\b"))),
+            ("\\#?d", Some(entry(None, "#?d"))),
+            ("# a comment", None),
+            ("", None),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(parse_hcmask_line(line), expected, "line: {}", line);
+        }
+    }
+
+    #[test]
+    fn test_read_hcmask_file() {
+        let fname = wordlist_fname("masks.hcmask");
+        assert!(is_hcmask_file(&fname));
+        assert!(!is_hcmask_file(wordlist_fname("masks-charsets.txt")));
+
+        let masks = read_hcmask_file(fname).unwrap();
+        assert_eq!(
+            masks,
+            vec![
+                MaskEntry::new("?d?d".to_string()),
+                MaskEntry {
+                    mask: "?1?2".to_string(),
+                    custom_charsets: Some(vec!["ab".to_string(), "X,".to_string()]),
+                    wordlists: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_masks_file_dir() {
+        assert_eq!(masks_file_dir("masks/a.hcmask"), PathBuf::from("masks"));
+        assert_eq!(masks_file_dir("a.hcmask"), PathBuf::from("."));
+        assert_eq!(masks_file_dir("-"), PathBuf::from("."));
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        assert_eq!(resolve_path("masks", "words.txt"), "masks/words.txt");
+        assert_eq!(resolve_path("masks", "/tmp/words.txt"), "/tmp/words.txt");
+        assert_eq!(resolve_path("masks", "-"), "-");
+    }
+
+    #[test]
+    fn test_load_charset_files() {
+        let base_dir = masks_file_dir(wordlist_fname("masks-relative.txt"));
+        let entry = parse_masks_line("?1?2 -c charset.hcchr -c abc");
+        assert_eq!(
+            load_charset_files(entry, &base_dir)
+                .unwrap()
+                .custom_charsets,
+            Some(vec!["?dab\\x5c\\xe4".to_string(), "abc".to_string()])
+        );
+
+        let entry = MaskEntry::new("?d".to_string());
+        assert_eq!(load_charset_files(entry.clone(), &base_dir).unwrap(), entry);
+        let entry = parse_masks_line("?1 -c missing.hcchr");
+        assert!(load_charset_files(entry, &base_dir).is_err());
+    }
+}
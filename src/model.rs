@@ -0,0 +1,148 @@
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::helpers::{open_input, RawFileReader};
+use crate::BoxResult;
+
+/// a character (unigram) probability model of passwords, trained by counting the bytes of
+/// a passwords corpus. used to order generated words from the most to the least probable
+#[derive(Debug, Clone)]
+pub struct CharModel {
+    counts: [u64; 256],
+    total: u64,
+}
+
+impl Default for CharModel {
+    fn default() -> Self {
+        CharModel {
+            counts: [0; 256],
+            total: 0,
+        }
+    }
+}
+
+/// english letters frequency per 100k letters, applied to both lowercase and uppercase
+const ENGLISH_LETTERS_FREQ: [u64; 26] = [
+    8167, 1492, 2782, 4253, 12702, 2228, 2015, 6094, 6966, 153, 772, 4025, 2406, 6749, 7507, 1929,
+    95, 5987, 6327, 9056, 2758, 978, 2360, 150, 1974, 74,
+];
+
+impl CharModel {
+    pub fn new() -> CharModel {
+        CharModel::default()
+    }
+
+    /// a model of the english letters frequency, other chars are left uniform
+    pub fn english() -> CharModel {
+        let mut model = CharModel::new();
+        for (i, &count) in ENGLISH_LETTERS_FREQ.iter().enumerate() {
+            model.counts[b'a' as usize + i] = count;
+            model.counts[b'A' as usize + i] = count;
+            model.total += 2 * count;
+        }
+        model
+    }
+
+    /// counts the bytes of every newline separated password of `reader`
+    pub fn train<R: Read>(&mut self, reader: R) -> BoxResult<()> {
+        for line in RawFileReader::new(reader) {
+            for &ch in line?.iter() {
+                self.counts[ch as usize] += 1;
+                self.total += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// loads a model file, one `<byte> <count>` line per byte seen in training
+    pub fn from_file<P: AsRef<Path>>(fname: P) -> BoxResult<CharModel> {
+        let mut model = CharModel::new();
+        for (lineno, line) in RawFileReader::new(open_input(fname)?).enumerate() {
+            let line = String::from_utf8(line?)?;
+            let mut fields = line.split_whitespace();
+            let (ch, count) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(ch), Some(count), None) => (ch.parse::<u8>(), count.parse::<u64>()),
+                (None, _, _) => continue,
+                _ => bail!("invalid model line {}: {:?}", lineno + 1, line),
+            };
+            match (ch, count) {
+                (Ok(ch), Ok(count)) => {
+                    model.counts[ch as usize] += count;
+                    model.total += count;
+                }
+                _ => bail!("invalid model line {}: {:?}", lineno + 1, line),
+            }
+        }
+        Ok(model)
+    }
+
+    pub fn to_file<P: AsRef<Path>>(&self, fname: P) -> BoxResult<()> {
+        let mut fp = BufWriter::new(File::create(fname)?);
+        for (ch, &count) in self.counts.iter().enumerate().filter(|(_, &c)| c > 0) {
+            writeln!(fp, "{} {}", ch, count)?;
+        }
+        fp.flush()?;
+        Ok(())
+    }
+
+    /// returns the cost (negative log2 probability) of `ch`. add-one smoothed so chars never
+    /// seen in training are the least probable but still generated
+    #[inline]
+    pub fn cost(&self, ch: u8) -> f64 {
+        let prob = (self.counts[ch as usize] + 1) as f64 / (self.total + 256) as f64;
+        -prob.log2()
+    }
+
+    /// returns the cost of `ch` given it is one of the chars of `charset`. add-one smoothed, so
+    /// it is `log2(charset.len())` when none of the charset chars were seen in training
+    pub fn charset_cost(&self, ch: u8, charset: &[u8]) -> f64 {
+        let total: u64 = charset.iter().map(|&c| self.counts[c as usize]).sum();
+        let prob = (self.counts[ch as usize] + 1) as f64 / (total + charset.len() as u64) as f64;
+        -prob.log2()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::CharModel;
+
+    #[test]
+    fn test_char_model() {
+        let mut model = CharModel::new();
+        model.train(&b"aab\nab\n"[..]).unwrap();
+        assert!(model.cost(b'a') < model.cost(b'b'));
+        assert!(model.cost(b'b') < model.cost(b'c'));
+        assert_eq!(model.cost(b'c'), model.cost(0));
+
+        let fname = env::temp_dir().join("cracken-test-char-model.txt");
+        model.to_file(&fname).unwrap();
+        assert_eq!(fs::read_to_string(&fname).unwrap(), "97 3\n98 2\n");
+
+        let loaded = CharModel::from_file(&fname).unwrap();
+        fs::remove_file(&fname).unwrap();
+        assert_eq!(loaded.counts[..], model.counts[..]);
+        assert_eq!(loaded.total, 5);
+    }
+
+    #[test]
+    fn test_char_model_charset_cost() {
+        let mut model = CharModel::new();
+        model.train(&b"aab\n"[..]).unwrap();
+        // (2 + 1) / (3 + 4) and (0 + 1) / (3 + 4)
+        assert_eq!(model.charset_cost(b'a', b"abcd"), -(3f64 / 7f64).log2());
+        assert_eq!(model.charset_cost(b'c', b"abcd"), -(1f64 / 7f64).log2());
+        // uniform for chars never seen
+        assert_eq!(model.charset_cost(b'1', b"0123456789"), 10f64.log2());
+
+        let english = CharModel::english();
+        assert!(english.charset_cost(b'e', b"etaz") < english.charset_cost(b'z', b"etaz"));
+        assert_eq!(
+            english.charset_cost(b'E', b"ET"),
+            english.charset_cost(b'e', b"et")
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+use std::io::{Error, Read};
+
+use crate::helpers::RawFileReader;
+
+/// the order of the normalized words
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortOrder {
+    /// the order of the input lines
+    #[default]
+    Input,
+    /// shortest words first, lexicographically (by bytes) within a length
+    Length,
+    /// lexicographically by bytes, like `LC_ALL=C sort`
+    Lex,
+}
+
+/// options of canonicalizing a wordlist with `normalize_words`
+#[derive(Debug, Clone, Default)]
+pub struct NormalizeOptions {
+    dedup: bool,
+    sort: SortOrder,
+    min_len: Option<usize>,
+    max_len: Option<usize>,
+}
+
+impl NormalizeOptions {
+    pub fn new() -> NormalizeOptions {
+        NormalizeOptions::default()
+    }
+
+    /// keep only the first occurrence of each word
+    pub fn dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    pub fn sort(mut self, sort: SortOrder) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// skip words shorter than this number of bytes, after trimming
+    pub fn min_len(mut self, min_len: Option<usize>) -> Self {
+        self.min_len = min_len;
+        self
+    }
+
+    /// skip words longer than this number of bytes, after trimming
+    pub fn max_len(mut self, max_len: Option<usize>) -> Self {
+        self.max_len = max_len;
+        self
+    }
+}
+
+/// returns the canonical words of the newline separated lines of `reader` - each line trimmed
+/// of surrounding whitespace (including a CRLF `\r`), blank lines dropped, then filtered, deduped
+/// and sorted by `options`
+pub fn normalize_words<R: Read>(
+    reader: R,
+    options: &NormalizeOptions,
+) -> Result<Vec<Vec<u8>>, Error> {
+    let mut seen = HashSet::new();
+    let mut words = vec![];
+    for line in RawFileReader::new(reader) {
+        let line = line?;
+        let word = line.trim_ascii();
+        if word.is_empty()
+            || options.min_len.is_some_and(|min_len| word.len() < min_len)
+            || options.max_len.is_some_and(|max_len| word.len() > max_len)
+        {
+            continue;
+        }
+        // sorted words are deduped once adjacent
+        if options.dedup && options.sort == SortOrder::Input && !seen.insert(word.to_vec()) {
+            continue;
+        }
+        words.push(word.to_vec());
+    }
+
+    match options.sort {
+        SortOrder::Input => {}
+        SortOrder::Length => words.sort_unstable_by(|a, b| (a.len(), a).cmp(&(b.len(), b))),
+        SortOrder::Lex => words.sort_unstable(),
+    }
+    if options.dedup {
+        words.dedup();
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use crate::test_util::wordlist_fname;
+
+    use super::{normalize_words, NormalizeOptions, SortOrder};
+
+    fn normalize(data: &[u8], options: NormalizeOptions) -> String {
+        let words = normalize_words(data, &options).unwrap();
+        String::from_utf8(words.join(&b'\n')).unwrap()
+    }
+
+    #[test]
+    fn test_normalize_words() {
+        let data = b"  beta\r\nab\n\n\t\nalpha \r\nab\r\nbeta";
+        assert_eq!(
+            normalize(data, NormalizeOptions::new()),
+            "beta\nab\nalpha\nab\nbeta"
+        );
+        assert_eq!(
+            normalize(data, NormalizeOptions::new().dedup(true)),
+            "beta\nab\nalpha"
+        );
+        assert_eq!(
+            normalize(data, NormalizeOptions::new().sort(SortOrder::Lex)),
+            "ab\nab\nalpha\nbeta\nbeta"
+        );
+        assert_eq!(
+            normalize(
+                data,
+                NormalizeOptions::new().dedup(true).sort(SortOrder::Length)
+            ),
+            "ab\nbeta\nalpha"
+        );
+        assert_eq!(
+            normalize(
+                data,
+                NormalizeOptions::new()
+                    .dedup(true)
+                    .min_len(Some(3))
+                    .max_len(Some(4))
+            ),
+            "beta"
+        );
+        // inner whitespace is kept
+        assert_eq!(
+            normalize(b" hello world \n", NormalizeOptions::new()),
+            "hello world"
+        );
+
+        let fname = wordlist_fname("wordlist-blank-lines.txt");
+        let words = normalize_words(File::open(fname).unwrap(), &NormalizeOptions::new()).unwrap();
+        assert!(words.iter().all(|word| !word.is_empty()));
+    }
+}
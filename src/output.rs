@@ -0,0 +1,718 @@
+use std::collections::HashSet;
+use std::io::{Error, ErrorKind, Write};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::BUFFER_SIZE;
+
+/// prefixes every word with its zero padded index, e.g. `000042:word`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberLines {
+    pub width: usize,
+    pub separator: String,
+}
+
+/// adds the mask generating every word to it, e.g. `?d?d\t42`. the mask is set on the
+/// writer with `WordsWriter::set_mask` before generating each mask
+#[derive(Debug, Clone, Default)]
+pub struct Annotation {
+    separator: Vec<u8>,
+    suffix: bool,
+}
+
+/// encoding of the words bytes (including prefix and suffix) into printable text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Hex,
+    Base64,
+}
+
+/// case conversion of the ascii letters of every word, other bytes are kept as is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    Upper,
+    Lower,
+    /// uppercases the first letter of the word
+    Capitalize,
+    /// uppercases the first letter of every run of letters
+    Title,
+}
+
+impl Case {
+    /// appends `word` converted to this case to `out`
+    pub fn convert_into(&self, word: &[u8], out: &mut Vec<u8>) {
+        match self {
+            Case::Upper => out.extend(word.iter().map(u8::to_ascii_uppercase)),
+            Case::Lower => out.extend(word.iter().map(u8::to_ascii_lowercase)),
+            Case::Capitalize => {
+                out.extend_from_slice(word);
+                let start = out.len() - word.len();
+                if let Some(ch) = out[start..].iter_mut().find(|ch| ch.is_ascii_alphabetic()) {
+                    ch.make_ascii_uppercase();
+                }
+            }
+            Case::Title => {
+                let mut prev_alpha = false;
+                out.extend(word.iter().map(|&ch| {
+                    let is_alpha = ch.is_ascii_alphabetic();
+                    let ch = if is_alpha && !prev_alpha {
+                        ch.to_ascii_uppercase()
+                    } else {
+                        ch
+                    };
+                    prev_alpha = is_alpha;
+                    ch
+                }));
+            }
+        }
+    }
+}
+
+/// transforms applied to every generated word on the write path
+#[derive(Debug, Clone, Default)]
+pub struct OutputOptions {
+    number_lines: Option<NumberLines>,
+    prefix: Vec<u8>,
+    suffix: Vec<u8>,
+    encoding: Option<Encoding>,
+    /// shared by the clones of the options, e.g. the writer of each mask with --output-dir
+    exclude: Arc<HashSet<Vec<u8>>>,
+    jsonl: bool,
+    case: Option<Case>,
+    annotation: Option<Annotation>,
+}
+
+const HEX_CHARS: &[u8; 16] = b"0123456789abcdef";
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl Encoding {
+    /// appends the encoding of `data` to `out`
+    pub fn encode_into(&self, data: &[u8], out: &mut Vec<u8>) {
+        match self {
+            Encoding::Hex => {
+                for &ch in data {
+                    out.push(HEX_CHARS[(ch >> 4) as usize]);
+                    out.push(HEX_CHARS[(ch & 0xf) as usize]);
+                }
+            }
+            Encoding::Base64 => {
+                for chunk in data.chunks(3) {
+                    let n = chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0u32, |n, (i, &ch)| n | (ch as u32) << (16 - 8 * i));
+                    for i in 0..4 {
+                        if i <= chunk.len() {
+                            out.push(BASE64_CHARS[(n >> (18 - 6 * i) & 0x3f) as usize]);
+                        } else {
+                            out.push(b'=');
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl NumberLines {
+    pub fn new(width: usize, separator: &str) -> NumberLines {
+        NumberLines {
+            width,
+            separator: separator.to_owned(),
+        }
+    }
+}
+
+impl Annotation {
+    /// annotates words with their mask and `separator` before them, or after them if `suffix`
+    pub fn new(separator: &str, suffix: bool) -> Annotation {
+        Annotation {
+            separator: separator.as_bytes().to_vec(),
+            suffix,
+        }
+    }
+}
+
+impl OutputOptions {
+    pub fn new() -> OutputOptions {
+        OutputOptions::default()
+    }
+    pub fn number_lines(mut self, number_lines: Option<NumberLines>) -> Self {
+        self.number_lines = number_lines;
+        self
+    }
+
+    /// a fixed string written before every word
+    pub fn prefix(mut self, prefix: &[u8]) -> Self {
+        self.prefix = prefix.to_vec();
+        self
+    }
+    /// a fixed string written after every word
+    pub fn suffix(mut self, suffix: &[u8]) -> Self {
+        self.suffix = suffix.to_vec();
+        self
+    }
+
+    /// encodes every word (with its prefix and suffix) as hex or base64
+    pub fn encoding(mut self, encoding: Option<Encoding>) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// words to skip, e.g. words already written from a priority file
+    pub fn exclude(mut self, exclude: HashSet<Vec<u8>>) -> Self {
+        self.exclude = Arc::new(exclude);
+        self
+    }
+
+    /// writes every word as a json line - `{"word":"...","index":N,"length":L}`. words that are
+    /// not valid utf8 (e.g. from ?b) are base64 encoded with an additional `"encoding":"base64"`
+    pub fn jsonl(mut self, jsonl: bool) -> Self {
+        self.jsonl = jsonl;
+        self
+    }
+
+    /// converts the case of every word, before excluding words and adding the prefix and suffix
+    pub fn case(mut self, case: Option<Case>) -> Self {
+        self.case = case;
+        self
+    }
+
+    /// adds the mask generating every word to it, after all other transforms. with jsonl the
+    /// mask is written as a `"mask"` key
+    pub fn annotation(mut self, annotation: Option<Annotation>) -> Self {
+        self.annotation = annotation;
+        self
+    }
+
+    /// the number of bytes added to every word by the prefix and suffix
+    pub fn added_word_len(&self) -> usize {
+        self.prefix.len() + self.suffix.len()
+    }
+
+    /// returns true if no transform is set, so words can be written as is
+    pub fn is_empty(&self) -> bool {
+        self.number_lines.is_none()
+            && self.prefix.is_empty()
+            && self.suffix.is_empty()
+            && self.encoding.is_none()
+            && self.exclude.is_empty()
+            && !self.jsonl
+            && self.case.is_none()
+            && self.annotation.is_none()
+    }
+}
+
+/// appends the decimal digits of `n` to `buf` zero padded to `width` digits, faster than
+/// `write!` on the per word path
+fn push_decimal(buf: &mut Vec<u8>, mut n: u64, width: usize) {
+    let mut digits = [0u8; 20];
+    let mut i = digits.len();
+    loop {
+        i -= 1;
+        digits[i] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    let len = digits.len() - i;
+    buf.resize(buf.len() + width.saturating_sub(len), b'0');
+    buf.extend_from_slice(&digits[i..]);
+}
+
+/// the writer generators write their newline terminated words to, applying `OutputOptions`
+/// to each word. words are written either with `write`, split by their newline, or with
+/// `write_words` passing their length - required for words containing a newline byte (e.g.
+/// from ?b). words split across writes are kept until their newline is written, and the
+/// output is buffered until `flush`
+pub struct WordsWriter<'a> {
+    inner: Box<dyn Write + 'a>,
+    options: OutputOptions,
+    deadline: Option<Instant>,
+    /// the mask of the words written, see `set_mask`
+    mask: String,
+    pending: Vec<u8>,
+    buf: Vec<u8>,
+    encode_buf: Vec<u8>,
+    case_buf: Vec<u8>,
+    /// the number of words written, excluded words are not counted
+    index: u64,
+}
+
+impl<'a> WordsWriter<'a> {
+    pub fn new(inner: Box<dyn Write + 'a>, options: OutputOptions) -> WordsWriter<'a> {
+        WordsWriter {
+            inner,
+            options,
+            deadline: None,
+            mask: String::new(),
+            pending: vec![],
+            buf: Vec::with_capacity(BUFFER_SIZE * 2),
+            encode_buf: vec![],
+            case_buf: vec![],
+            index: 0,
+        }
+    }
+
+    /// fails every write after `deadline` with a `TimedOut` error, the words written before
+    /// it are kept and written on `flush`
+    pub fn deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// returns true iff the writes fail after a deadline, see `deadline`
+    pub fn has_deadline(&self) -> bool {
+        self.deadline.is_some()
+    }
+
+    /// sets the mask of the following words, written with them by `OutputOptions::annotation`
+    pub fn set_mask(&mut self, mask: &str) {
+        self.mask.clear();
+        self.mask.push_str(mask);
+    }
+
+    /// returns the number of words written so far, excluded words are not counted
+    pub fn words_written(&self) -> u64 {
+        self.index
+    }
+
+    /// writes newline terminated `words` all of `word_len` bytes (without their newline), so
+    /// words containing a newline byte are kept whole
+    pub fn write_words(&mut self, words: &[u8], word_len: usize) -> Result<(), Error> {
+        self.check_deadline()?;
+        debug_assert!(self.pending.is_empty(), "a word was partially written");
+        debug_assert_eq!(words.len() % (word_len + 1), 0);
+        if self.options.is_empty() {
+            self.index += (words.len() / (word_len + 1)) as u64;
+            return self.write_through(words);
+        }
+        for word in words.chunks_exact(word_len + 1) {
+            self.transform_word(&word[..word_len]);
+        }
+        self.write_full_buf()
+    }
+
+    #[inline]
+    fn check_deadline(&self) -> Result<(), Error> {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                Err(Error::new(ErrorKind::TimedOut, "deadline reached"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// writes `data` as is, large chunks directly instead of copying them to the buffer
+    fn write_through(&mut self, data: &[u8]) -> Result<(), Error> {
+        if data.len() >= BUFFER_SIZE / 2 {
+            self.write_buf()?;
+            return self.inner.write_all(data);
+        }
+        self.buf.extend_from_slice(data);
+        self.write_full_buf()
+    }
+
+    #[inline]
+    fn write_full_buf(&mut self) -> Result<(), Error> {
+        if self.buf.len() >= BUFFER_SIZE {
+            self.write_buf()?;
+        }
+        Ok(())
+    }
+
+    fn write_buf(&mut self) -> Result<(), Error> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        // cleared on errors too, so the words are not written twice by a following flush
+        let written = self.inner.write_all(&self.buf);
+        self.buf.clear();
+        written
+    }
+
+    fn transform_word(&mut self, word: &[u8]) {
+        match self.options.case {
+            Some(case) => {
+                let mut case_buf = std::mem::take(&mut self.case_buf);
+                case_buf.clear();
+                case.convert_into(word, &mut case_buf);
+                self.transform_cased_word(&case_buf);
+                self.case_buf = case_buf;
+            }
+            None => self.transform_cased_word(word),
+        }
+    }
+
+    fn transform_cased_word(&mut self, word: &[u8]) {
+        if self.options.exclude.contains(word) {
+            return;
+        }
+        if self.options.jsonl {
+            self.write_json_word(word);
+            self.index += 1;
+            return;
+        }
+        if let Some(number_lines) = &self.options.number_lines {
+            push_decimal(&mut self.buf, self.index, number_lines.width);
+            self.buf
+                .extend_from_slice(number_lines.separator.as_bytes());
+        }
+        if let Some(annotation) = self.options.annotation.as_ref().filter(|a| !a.suffix) {
+            self.buf.extend_from_slice(self.mask.as_bytes());
+            self.buf.extend_from_slice(&annotation.separator);
+        }
+        match &self.options.encoding {
+            Some(encoding) => {
+                self.encode_buf.clear();
+                self.encode_buf.extend_from_slice(&self.options.prefix);
+                self.encode_buf.extend_from_slice(word);
+                self.encode_buf.extend_from_slice(&self.options.suffix);
+                encoding.encode_into(&self.encode_buf, &mut self.buf);
+            }
+            None => {
+                self.buf.extend_from_slice(&self.options.prefix);
+                self.buf.extend_from_slice(word);
+                self.buf.extend_from_slice(&self.options.suffix);
+            }
+        }
+        if let Some(annotation) = self.options.annotation.as_ref().filter(|a| a.suffix) {
+            self.buf.extend_from_slice(&annotation.separator);
+            self.buf.extend_from_slice(self.mask.as_bytes());
+        }
+        self.buf.push(b'\n');
+        self.index += 1;
+    }
+
+    fn write_json_word(&mut self, word: &[u8]) {
+        self.encode_buf.clear();
+        self.encode_buf.extend_from_slice(&self.options.prefix);
+        self.encode_buf.extend_from_slice(word);
+        self.encode_buf.extend_from_slice(&self.options.suffix);
+
+        // written by hand to keep the keys order, serde_json escapes the word if needed
+        self.buf.extend_from_slice(b"{\"word\":");
+        let is_plain = |&ch: &u8| (b' '..=b'~').contains(&ch) && ch != b'"' && ch != b'\\';
+        if self.encode_buf.iter().all(is_plain) {
+            self.buf.push(b'"');
+            self.buf.extend_from_slice(&self.encode_buf);
+            self.buf.push(b'"');
+        } else {
+            match std::str::from_utf8(&self.encode_buf) {
+                Ok(word) => serde_json::to_writer(&mut self.buf, word).unwrap(),
+                Err(_) => {
+                    self.buf.push(b'"');
+                    Encoding::Base64.encode_into(&self.encode_buf, &mut self.buf);
+                    self.buf.extend_from_slice(b"\",\"encoding\":\"base64\"");
+                }
+            }
+        }
+        if self.options.annotation.is_some() {
+            self.buf.extend_from_slice(b",\"mask\":");
+            serde_json::to_writer(&mut self.buf, &self.mask).unwrap();
+        }
+        self.buf.extend_from_slice(b",\"index\":");
+        push_decimal(&mut self.buf, self.index, 0);
+        self.buf.extend_from_slice(b",\"length\":");
+        push_decimal(&mut self.buf, self.encode_buf.len() as u64, 0);
+        self.buf.extend_from_slice(b"}\n");
+    }
+}
+
+impl<'a> Write for WordsWriter<'a> {
+    /// writes newline terminated words, split by their newline
+    fn write(&mut self, data: &[u8]) -> Result<usize, Error> {
+        self.check_deadline()?;
+        if self.options.is_empty() {
+            self.index += data.iter().filter(|&&ch| ch == b'\n').count() as u64;
+            self.write_through(data)?;
+            return Ok(data.len());
+        }
+
+        let mut start = 0;
+        while let Some(end) = data[start..].iter().position(|&ch| ch == b'\n') {
+            let i = start + end;
+            if self.pending.is_empty() {
+                self.transform_word(&data[start..i]);
+            } else {
+                let mut word = std::mem::take(&mut self.pending);
+                word.extend_from_slice(&data[start..i]);
+                self.transform_word(&word);
+            }
+            start = i + 1;
+        }
+        self.pending.extend_from_slice(&data[start..]);
+
+        self.write_full_buf()?;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.write_buf()?;
+        self.inner.flush()
+    }
+}
+
+impl Drop for WordsWriter<'_> {
+    fn drop(&mut self) {
+        // like `BufWriter`, errors writing the buffered words on drop are ignored
+        let _ = self.write_buf();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{ErrorKind, Write};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use super::{
+        push_decimal, Annotation, Case, Encoding, NumberLines, OutputOptions, WordsWriter,
+    };
+
+    fn write_words(options: OutputOptions, chunks: &[&[u8]]) -> String {
+        let mut out = vec![];
+        {
+            let mut writer = WordsWriter::new(Box::new(&mut out), options);
+            for chunk in chunks {
+                writer.write_all(chunk).unwrap();
+            }
+            writer.flush().unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_words_writer_no_options() {
+        let output = write_words(OutputOptions::new(), &[b"a\nb", b"c\n", b"d\n"]);
+        assert_eq!(output, "a\nbc\nd\n");
+    }
+
+    #[test]
+    fn test_words_writer_number_lines() {
+        let options = OutputOptions::new().number_lines(Some(NumberLines::new(3, ":")));
+        let output = write_words(options, &[b"hello\nwor", b"ld\n\n", b"x\n"]);
+        assert_eq!(output, "000:hello\n001:world\n002:\n003:x\n");
+
+        let options = OutputOptions::new().number_lines(Some(NumberLines::new(0, "\t")));
+        let output = write_words(options, &[b"a\nb\n"]);
+        assert_eq!(output, "0\ta\n1\tb\n");
+    }
+
+    #[test]
+    fn test_words_writer_prefix_suffix() {
+        let options = OutputOptions::new().prefix(b"https://").suffix(b".com");
+        assert_eq!(options.added_word_len(), 12);
+        let output = write_words(options, &[b"a\nb", b"c\n"]);
+        assert_eq!(output, "https://a.com\nhttps://bc.com\n");
+
+        let options = OutputOptions::new()
+            .suffix(b"!")
+            .number_lines(Some(NumberLines::new(1, ":")));
+        let output = write_words(options, &[b"a\nb\n"]);
+        assert_eq!(output, "0:a!\n1:b!\n");
+    }
+
+    #[test]
+    fn test_words_writer_annotation() {
+        let write_masks = |options: OutputOptions, masks: &[(&str, &[u8])]| {
+            let mut out = vec![];
+            {
+                let mut writer = WordsWriter::new(Box::new(&mut out), options);
+                for (mask, words) in masks {
+                    writer.set_mask(mask);
+                    writer.write_all(words).unwrap();
+                }
+            }
+            String::from_utf8(out).unwrap()
+        };
+
+        let options = OutputOptions::new()
+            .prefix(b"<")
+            .annotation(Some(Annotation::new("\t", false)));
+        assert!(!options.is_empty());
+        assert_eq!(
+            write_masks(options, &[("?d?d", b"42\n07\n"), ("?l", b"a\n")]),
+            "?d?d\t<42\n?d?d\t<07\n?l\t<a\n"
+        );
+
+        let options = OutputOptions::new()
+            .encoding(Some(Encoding::Hex))
+            .annotation(Some(Annotation::new(" # ", true)));
+        assert_eq!(write_masks(options, &[("?d", b"1\n")]), "31 # ?d\n");
+
+        let options = OutputOptions::new()
+            .jsonl(true)
+            .annotation(Some(Annotation::new("\t", false)));
+        assert_eq!(
+            write_masks(options, &[("?w1\"", b"ab\n")]),
+            "{\"word\":\"ab\",\"mask\":\"?w1\\\"\",\"index\":0,\"length\":2}\n"
+        );
+    }
+
+    #[test]
+    fn test_output_options_send() {
+        fn assert_send<T: Send>(_: &T) {}
+        let options = OutputOptions::new()
+            .annotation(Some(Annotation::new("\t", false)))
+            .exclude(vec![b"a".to_vec()].into_iter().collect());
+        assert_send(&options);
+        // the excluded words are shared, not copied, by the clones
+        assert!(Arc::ptr_eq(&options.exclude, &options.clone().exclude));
+    }
+
+    #[test]
+    fn test_encoding() {
+        let encode = |encoding: Encoding, data: &[u8]| {
+            let mut out = vec![];
+            encoding.encode_into(data, &mut out);
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(encode(Encoding::Hex, b"\x00\n\xffa"), "000aff61");
+        assert_eq!(encode(Encoding::Base64, b""), "");
+        assert_eq!(encode(Encoding::Base64, b"f"), "Zg==");
+        assert_eq!(encode(Encoding::Base64, b"fo"), "Zm8=");
+        assert_eq!(encode(Encoding::Base64, b"foo"), "Zm9v");
+        assert_eq!(encode(Encoding::Base64, b"foobar\xff"), "Zm9vYmFy/w==");
+    }
+
+    #[test]
+    fn test_words_writer_encoding() {
+        let options = OutputOptions::new()
+            .encoding(Some(Encoding::Hex))
+            .prefix(b"a");
+        let output = write_words(options, &[b"b\nc", b"\n"]);
+        assert_eq!(output, "6162\n6163\n");
+
+        let options = OutputOptions::new()
+            .encoding(Some(Encoding::Base64))
+            .number_lines(Some(NumberLines::new(1, ":")));
+        let output = write_words(options, &[b"foo\nfo\n"]);
+        assert_eq!(output, "0:Zm9v\n1:Zm8=\n");
+    }
+
+    #[test]
+    fn test_words_writer_write_words() {
+        let options = OutputOptions::new().encoding(Some(Encoding::Hex));
+        let mut out = vec![];
+        {
+            let mut writer = WordsWriter::new(Box::new(&mut out), options);
+            writer.write_words(b"a\n\n\n\n\n", 2).unwrap();
+            writer.write_all(b"bc\nd").unwrap();
+            writer.write_all(b"\n").unwrap();
+            writer.write_words(b"\n\n", 1).unwrap();
+            assert_eq!(writer.words_written(), 5);
+        }
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "610a\n0a0a\n6263\n64\n0a\n"
+        );
+
+        // without transforms the words are written as is
+        let mut out = vec![];
+        {
+            let mut writer = WordsWriter::new(Box::new(&mut out), OutputOptions::new());
+            writer.write_words(b"\n\n\n\n", 1).unwrap();
+            writer.write_all(b"a\nb\n").unwrap();
+            assert_eq!(writer.words_written(), 4);
+            writer.flush().unwrap();
+        }
+        assert_eq!(out, b"\n\n\n\na\nb\n");
+    }
+
+    #[test]
+    fn test_words_writer_deadline() {
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let mut out = vec![];
+        {
+            let mut writer =
+                WordsWriter::new(Box::new(&mut out), OutputOptions::new()).deadline(Some(deadline));
+            writer.write_all(b"a\nb\n").unwrap();
+            assert_eq!(writer.words_written(), 2);
+        }
+        assert_eq!(out, b"a\nb\n");
+
+        let mut out = vec![];
+        {
+            let options = OutputOptions::new().prefix(b"x");
+            let mut writer =
+                WordsWriter::new(Box::new(&mut out), options).deadline(Some(Instant::now()));
+            let err = writer.write_all(b"c\n").unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::TimedOut);
+            let err = writer.write_words(b"d\n", 1).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::TimedOut);
+            assert_eq!(writer.words_written(), 0);
+            writer.flush().unwrap();
+        }
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_words_writer_exclude() {
+        let exclude = vec![b"b".to_vec(), b"cd".to_vec()].into_iter().collect();
+        let options = OutputOptions::new()
+            .exclude(exclude)
+            .number_lines(Some(NumberLines::new(1, ":")));
+        let output = write_words(options, &[b"a\nb\nc", b"d\nbc\n"]);
+        assert_eq!(output, "0:a\n1:bc\n");
+    }
+
+    #[test]
+    fn test_case() {
+        let convert = |case: Case, word: &[u8]| {
+            let mut out = b"x".to_vec();
+            case.convert_into(word, &mut out);
+            out
+        };
+        let word = b"hello wORLD-2nd\xff\xe9a";
+        assert_eq!(convert(Case::Upper, word), b"xHELLO WORLD-2ND\xff\xe9A");
+        assert_eq!(convert(Case::Lower, word), b"xhello world-2nd\xff\xe9a");
+        assert_eq!(
+            convert(Case::Capitalize, word),
+            b"xHello wORLD-2nd\xff\xe9a"
+        );
+        assert_eq!(convert(Case::Title, word), b"xHello WORLD-2Nd\xff\xe9A");
+        assert_eq!(convert(Case::Capitalize, b"123abc"), b"x123Abc");
+        assert_eq!(convert(Case::Title, b""), b"x");
+    }
+
+    #[test]
+    fn test_words_writer_case() {
+        let exclude = vec![b"Beta".to_vec()].into_iter().collect();
+        let options = OutputOptions::new()
+            .case(Some(Case::Capitalize))
+            .exclude(exclude)
+            .prefix(b"1");
+        assert!(!OutputOptions::new().case(Some(Case::Upper)).is_empty());
+        let output = write_words(options, &[b"alpha\nbe", b"ta\ngamma\n"]);
+        assert_eq!(output, "1Alpha\n1Gamma\n");
+    }
+
+    #[test]
+    fn test_push_decimal() {
+        for n in [0, 7, 10, 12345, u64::MAX] {
+            for width in [0, 1, 3, 22] {
+                let mut buf = b"x".to_vec();
+                push_decimal(&mut buf, n, width);
+                assert_eq!(buf, format!("x{:0width$}", n, width = width).as_bytes());
+            }
+        }
+    }
+
+    #[test]
+    fn test_words_writer_jsonl() {
+        let options = OutputOptions::new().jsonl(true).suffix(b"!");
+        assert!(!options.is_empty());
+        let output = write_words(options, &[b"a\"b\nc", b"d\n\xff\xfe\n"]);
+        assert_eq!(
+            output,
+            "{\"word\":\"a\\\"b!\",\"index\":0,\"length\":4}\n\
+             {\"word\":\"cd!\",\"index\":1,\"length\":3}\n\
+             {\"word\":\"//4h\",\"encoding\":\"base64\",\"index\":2,\"length\":3}\n"
+        );
+        for line in output.lines() {
+            assert!(serde_json::from_str::<serde_json::Value>(line).is_ok());
+        }
+    }
+}
@@ -1,19 +1,46 @@
 use std::collections::HashSet;
-use std::fs::File;
 use std::path::Path;
 
+use num_bigint::{BigUint, ToBigUint};
 use ordered_float::OrderedFloat;
 use pathfinding::astar;
-use simple_error::SimpleError;
 
-use crate::charsets::SYMBOL2CHARSET;
-use crate::helpers::RawFileReader;
+use crate::charsets::{find_symbol, SYMBOL2CHARSET};
+use crate::helpers::{escape_byte, open_input};
+use crate::model::CharModel;
+use crate::wordlists::WordlistOptions;
 use crate::BoxResult;
 
 const SYMBOLS_SPACE: &[u8; 32] = b"!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+const ALL_BYTES: [u8; 256] = all_bytes();
+
+const fn all_bytes() -> [u8; 256] {
+    let mut bytes = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        bytes[i] = i as u8;
+        i += 1;
+    }
+    bytes
+}
+
+/// a set of words matched as a single mask symbol (e.g. `?d` or `?w1`)
+struct Vocab {
+    symbol: String,
+    /// the filename the words were loaded from, if any
+    source: Option<String>,
+    words: HashSet<Vec<u8>>,
+}
+
+/// a subword of a password with the vocab it is matched by
+type Subword<'a> = (&'a [u8], &'a Vocab);
 
 pub struct EntropyEstimator {
-    words: Vec<(String, HashSet<Vec<u8>>)>,
+    words: Vec<Vocab>,
+    verbose: bool,
+    codepoints: bool,
+    char_model: Option<CharModel>,
+    exact_match: bool,
 }
 
 #[derive(PartialEq, Debug)]
@@ -27,6 +54,29 @@ pub struct PasswordEntropyResult {
 
 impl EntropyEstimator {
     pub fn from_files<P: AsRef<Path>>(filenames: &[P]) -> BoxResult<Self> {
+        Self::from_files_with_options(filenames, &WordlistOptions::default())
+    }
+
+    /// like `from_files` but only the builtin charsets of `symbols` (e.g. `&['d']` for digits)
+    /// are used as subwords
+    pub fn from_files_with_charsets<P: AsRef<Path>>(
+        filenames: &[P],
+        symbols: &[char],
+    ) -> BoxResult<Self> {
+        if let Some(symbol) = symbols.iter().find(|&&ch| find_symbol(ch).is_none()) {
+            bail!("unknown builtin charset symbol: {:?}", symbol);
+        }
+        let mut est = Self::from_files(filenames)?;
+        est.words.retain(|vocab| {
+            vocab.source.is_some() || vocab.symbol.chars().all(|ch| symbols.contains(&ch))
+        });
+        Ok(est)
+    }
+
+    pub fn from_files_with_options<P: AsRef<Path>>(
+        filenames: &[P],
+        options: &WordlistOptions,
+    ) -> BoxResult<Self> {
         let mut words = Vec::with_capacity(filenames.len() + SYMBOL2CHARSET.len());
 
         // add common charsets as wordlists
@@ -36,21 +86,117 @@ impl EntropyEstimator {
                 .iter()
                 .map(|ch| vec![ch.to_owned()])
                 .collect::<HashSet<_>>();
-            words.push((charset.symbol.to_string(), set));
+            words.push(Vocab {
+                symbol: charset.symbol.to_string(),
+                source: None,
+                words: set,
+            });
         }
 
         for (i, filename) in filenames.iter().enumerate() {
-            words.push((format!("w{}", i + 1), Self::load_vocab(filename)?));
+            let filename = filename.as_ref();
+            let source = filename
+                .file_name()
+                .unwrap_or(filename.as_os_str())
+                .to_string_lossy()
+                .to_string();
+            words.push(Vocab {
+                symbol: format!("w{}", i + 1),
+                source: Some(source),
+                words: Self::load_vocab(filename, options)?,
+            });
         }
 
-        words.sort_by_key(|(_, set)| set.len());
-        Ok(EntropyEstimator { words })
+        words.sort_by_key(|vocab| vocab.words.len());
+        Ok(EntropyEstimator {
+            words,
+            verbose: false,
+            codepoints: false,
+            char_model: None,
+            exact_match: false,
+        })
+    }
+
+    /// adds ad-hoc vocab words (e.g. a company name) as an additional wordlist symbol after
+    /// the loaded files, with `inline` as its source
+    pub fn inline_words<S: AsRef<[u8]>>(mut self, words: &[S]) -> Self {
+        let words: HashSet<Vec<u8>> = words
+            .iter()
+            .map(|word| word.as_ref().to_vec())
+            .filter(|word| !word.is_empty())
+            .collect();
+        if words.is_empty() {
+            return self;
+        }
+        let wordlists_count = self
+            .words
+            .iter()
+            .filter(|vocab| vocab.source.is_some())
+            .count();
+        self.words.push(Vocab {
+            symbol: format!("w{}", wordlists_count + 1),
+            source: Some("inline".to_owned()),
+            words,
+        });
+        self.words.sort_by_key(|vocab| vocab.words.len());
+        self
+    }
+
+    /// when disabled, the builtin charsets are not used as subwords so only the loaded
+    /// wordlists can match
+    pub fn charset_words(mut self, enabled: bool) -> Self {
+        if !enabled {
+            self.words.retain(|vocab| vocab.source.is_some());
+        }
+        self
+    }
+
+    /// when set, wordlist symbols in masks include their source filename (e.g. `?w1[names.txt]`)
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// when set, passwords are treated as utf8 and each codepoint is a single unit - subwords
+    /// are split only on char boundaries and a non ascii char matches `?b` once
+    pub fn codepoints(mut self, codepoints: bool) -> Self {
+        self.codepoints = codepoints;
+        self
+    }
+
+    /// when set, a builtin charset char costs `-log2(p)` by its frequency among the charset
+    /// chars instead of `log2(charset size)`, for both the charset mask and hybrid subwords
+    pub fn char_model(mut self, char_model: Option<CharModel>) -> Self {
+        self.char_model = char_model;
+        self
+    }
+
+    /// when set, a password found whole in a vocab is reported as that single word without
+    /// searching for a cheaper split - faster for dumps of dictionary words, but may overestimate
+    pub fn exact_match(mut self, exact_match: bool) -> Self {
+        self.exact_match = exact_match;
+        self
+    }
+
+    /// returns the entropy of `pwd` as a single word of the smallest vocab containing it whole,
+    /// or `None` if no vocab contains it
+    pub fn contains_exact(&self, pwd: &[u8]) -> Option<f64> {
+        self.exact_vocab(pwd)
+            .map(|vocab| self.subword_cost(vocab, pwd))
+    }
+
+    fn exact_vocab(&self, pwd: &[u8]) -> Option<&Vocab> {
+        self.words
+            .iter()
+            .filter(|vocab| vocab.words.contains(pwd))
+            .min_by_key(|vocab| OrderedFloat(self.subword_cost(vocab, pwd)))
     }
 
     pub fn estimate_password_entropy(&self, pwd: &[u8]) -> BoxResult<PasswordEntropyResult> {
         let (subword_entropy, subword_entropy_min_split, min_subword_mask) =
             self.compute_password_subword_entropy(pwd)?;
-        let (mask_entropy, charset_mask) = password_mask_entropy(pwd);
+        let (mask_entropy, charset_mask) =
+            mask_entropy_with_model(pwd, self.char_model.as_ref(), self.codepoints);
         Ok(PasswordEntropyResult {
             mask_entropy,
             charset_mask,
@@ -60,65 +206,190 @@ impl EntropyEstimator {
         })
     }
 
+    /// lazily estimates the entropy of every password of `passwords`, e.g. for library users
+    /// scoring a stream of passwords
+    ///
+    /// ```
+    /// use cracken::password_entropy::EntropyEstimator;
+    ///
+    /// let vocab = concat!(env!("CARGO_MANIFEST_DIR"), "/test-resources/vocab.txt");
+    /// let est = EntropyEstimator::from_files(&[vocab]).unwrap();
+    /// let passwords = vec![b"hello".to_vec(), b"hello123!".to_vec(), b"Xy7!".to_vec()];
+    ///
+    /// let results: Vec<_> = est
+    ///     .estimate_stream(passwords.into_iter())
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(results.len(), 3);
+    /// assert!(results[0].subword_entropy < results[1].subword_entropy);
+    /// assert_eq!(results[1].charset_mask, "?l?l?l?l?l?d?d?d?s");
+    /// assert_eq!(results[2].charset_mask, "?u?l?d?s");
+    /// ```
+    pub fn estimate_stream<'a, I: Iterator<Item = Vec<u8>> + 'a>(
+        &'a self,
+        passwords: I,
+    ) -> impl Iterator<Item = BoxResult<PasswordEntropyResult>> + 'a {
+        passwords.map(move |pwd| self.estimate_password_entropy(&pwd))
+    }
+
     pub fn compute_password_subword_entropy(
         &self,
         pwd: &[u8],
     ) -> BoxResult<(f64, Vec<String>, String)> {
-        // load vocab file
+        let (entropy, subwords) = self.min_split(pwd)?;
+        let mut best_split = Vec::with_capacity(subwords.len());
+        let mut best_mask = String::with_capacity(subwords.len() * 2);
+        for (word_i, vocab) in subwords {
+            best_mask.push('?');
+            best_mask.push_str(vocab.symbol.as_str());
+            if let (true, Some(source)) = (self.verbose, &vocab.source) {
+                best_mask.push('[');
+                best_mask.push_str(source);
+                best_mask.push(']');
+            }
+            best_split.push(String::from_utf8_lossy(word_i).to_string());
+        }
+        Ok((entropy, best_split, best_mask))
+    }
+
+    /// returns the number of passwords of the minimal entropy split of `pwd` - the product of
+    /// the sizes of its subwords vocabs (i.e. 2^entropy as an exact number)
+    pub fn subword_keyspace(&self, pwd: &[u8]) -> BoxResult<BigUint> {
+        let (_, subwords) = self.min_split(pwd)?;
+        Ok(subwords
+            .iter()
+            .map(|(_, vocab)| vocab.words.len().to_biguint().unwrap())
+            .product())
+    }
+
+    /// finds the minimal entropy split of `pwd` into subwords, returning its entropy and every
+    /// subword with its vocab
+    fn min_split<'a>(&'a self, pwd: &'a [u8]) -> BoxResult<(f64, Vec<Subword<'a>>)> {
+        if self.exact_match {
+            if let Some(vocab) = self.exact_vocab(pwd) {
+                return Ok((self.subword_cost(vocab, pwd), vec![(pwd, vocab)]));
+            }
+        }
         let amatch = astar(
             &0usize,
             |&n| {
-                let mut neighbours = vec![];
-                for (_, set) in self.words.iter() {
-                    for i in (n..=pwd.len()).rev() {
-                        if set.contains(&pwd[n..i]) {
-                            neighbours.push((i, OrderedFloat::<f64>((set.len() as f64).log2())));
-                        }
-                    }
-                }
-                neighbours
+                self.subwords_at(pwd, n)
+                    .into_iter()
+                    .map(|(i, vocab)| (i, OrderedFloat(self.subword_cost(vocab, &pwd[n..i]))))
+                    .collect::<Vec<_>>()
             },
             |_| OrderedFloat::<f64>(0f64),
             |&n| n == pwd.len(),
         );
-        let (best_path, entropy) =
-            amatch.ok_or_else(|| SimpleError::new("bad characters in password"))?;
+        let (best_path, entropy) = match amatch {
+            Some(amatch) => amatch,
+            None => {
+                let (start, end) = self.uncovered_span(pwd);
+                bail!(
+                    "no subword matches password {:?} at offset {} (byte 0x{:02x}), uncovered span {}..{}: {:?}",
+                    String::from_utf8_lossy(pwd),
+                    start,
+                    pwd[start],
+                    start,
+                    end,
+                    String::from_utf8_lossy(&pwd[start..end])
+                );
+            }
+        };
 
-        let mut best_split = Vec::with_capacity(best_path.len() - 1);
-        let mut best_mask = String::with_capacity(best_path.len() - 1);
+        let mut subwords = Vec::with_capacity(best_path.len() - 1);
         let mut prev = 0usize;
         for i in best_path.into_iter().skip(1) {
             let word_i = &pwd[prev..i];
-            let mut found = false;
-            for (symbol, set) in self.words.iter() {
-                if set.contains(word_i) {
-                    found = true;
-                    best_mask.push('?');
-                    best_mask.push_str(symbol.as_str());
-                    break;
+            // vocabs are sorted by size so the first of the cheapest matches is the smallest
+            let vocab = match self
+                .subwords_at(pwd, prev)
+                .into_iter()
+                .filter(|&(end, _)| end == i)
+                .min_by_key(|(_, vocab)| OrderedFloat(self.subword_cost(vocab, word_i)))
+            {
+                Some((_, vocab)) => vocab,
+                None => panic!("cannot find a matched subword {:?}", word_i),
+            };
+            subwords.push((word_i, vocab));
+            prev = i;
+        }
+        Ok((entropy.into_inner(), subwords))
+    }
+
+    /// returns the entropy of `word` matched by `vocab` - log2 of the vocab size, or by the
+    /// char model frequency for single chars of builtin charsets
+    fn subword_cost(&self, vocab: &Vocab, word: &[u8]) -> f64 {
+        if let (Some(model), None, [ch]) = (&self.char_model, &vocab.source, word) {
+            let symbol = vocab.symbol.chars().next().unwrap();
+            if let Some(charset) = find_symbol(symbol) {
+                return model.charset_cost(*ch, charset.chars);
+            }
+        }
+        (vocab.words.len() as f64).log2()
+    }
+
+    /// returns the end positions of all subwords matching `pwd` at `pos` with their vocab
+    fn subwords_at(&self, pwd: &[u8], pos: usize) -> Vec<(usize, &Vocab)> {
+        let mut subwords = vec![];
+        for vocab in self.words.iter() {
+            for i in (pos + 1..=pwd.len()).rev() {
+                if self.is_boundary(pwd, i) && vocab.words.contains(&pwd[pos..i]) {
+                    subwords.push((i, vocab));
                 }
             }
-            if !found {
-                panic!("cannot find a matched subword {:?}", word_i);
+
+            // a non ascii codepoint counts as a single ?b char
+            if self.codepoints && vocab.source.is_none() && vocab.symbol == "b" {
+                let end = (pos + 1..=pwd.len())
+                    .find(|&i| self.is_boundary(pwd, i))
+                    .unwrap_or(pwd.len());
+                if end - pos > 1 {
+                    subwords.push((end, vocab));
+                }
             }
-            best_split.push(String::from_utf8_lossy(word_i).to_string());
-            prev = i;
         }
-        Ok((entropy.into_inner(), best_split, best_mask))
+        subwords
     }
 
-    fn load_vocab<P: AsRef<Path>>(fname: P) -> BoxResult<HashSet<Vec<u8>>> {
+    /// returns whether a subword may start or end at `pos`
+    #[inline]
+    fn is_boundary(&self, pwd: &[u8], pos: usize) -> bool {
+        // utf8 continuation bytes are 0b10xxxxxx
+        !self.codepoints || pos >= pwd.len() || pwd[pos] & 0xc0 != 0x80
+    }
+
+    /// finds the first span of `pwd` that cannot be segmented into subwords. the span starts at
+    /// the furthest position reachable by subwords and ends where the next subword starts
+    fn uncovered_span(&self, pwd: &[u8]) -> (usize, usize) {
+        let mut reachable = vec![false; pwd.len() + 1];
+        reachable[0] = true;
+        for pos in 0..pwd.len() {
+            if reachable[pos] {
+                for (end, _) in self.subwords_at(pwd, pos) {
+                    reachable[end] = true;
+                }
+            }
+        }
+        let start = (0..pwd.len()).rev().find(|&i| reachable[i]).unwrap_or(0);
+        let end = (start + 1..pwd.len())
+            .find(|&i| self.is_boundary(pwd, i) && !self.subwords_at(pwd, i).is_empty())
+            .unwrap_or(pwd.len());
+        (start, end)
+    }
+
+    fn load_vocab<P: AsRef<Path>>(
+        fname: P,
+        options: &WordlistOptions,
+    ) -> BoxResult<HashSet<Vec<u8>>> {
         let mut words: HashSet<_> = HashSet::new();
 
-        let file = File::open(fname)?;
-        let reader = RawFileReader::new(file);
-        for word in reader
-            .into_iter()
-            .filter(|s| s.is_err() || !s.as_ref().unwrap().is_empty())
-        {
-            let mut word = word?;
-            word.shrink_to_fit();
-            words.insert(word);
+        for line in options.records(open_input(fname)?) {
+            let line = line?;
+            let word = options.parse_line(&line);
+            if !word.is_empty() {
+                words.insert(word.to_vec());
+            }
         }
 
         words.shrink_to_fit();
@@ -127,37 +398,171 @@ impl EntropyEstimator {
 }
 
 pub fn password_mask_entropy(pwd: &[u8]) -> (f64, String) {
+    mask_entropy_with_model(pwd, None, false)
+}
+
+/// like `password_mask_entropy` but each utf8 codepoint is a single mask char. non ascii
+/// codepoints are counted as a single `?b` char
+pub fn password_mask_entropy_codepoints(pwd: &[u8]) -> (f64, String) {
+    mask_entropy_with_model(pwd, None, true)
+}
+
+/// like `password_mask_entropy` (or `password_mask_entropy_codepoints`) but each char costs
+/// `-log2(p)` by its frequency among the chars of its charset according to `model`
+pub fn password_mask_entropy_with_model(
+    pwd: &[u8],
+    model: &CharModel,
+    codepoints: bool,
+) -> (f64, String) {
+    mask_entropy_with_model(pwd, Some(model), codepoints)
+}
+
+fn mask_entropy_with_model(
+    pwd: &[u8],
+    model: Option<&CharModel>,
+    codepoints: bool,
+) -> (f64, String) {
     let mut mask = String::with_capacity(pwd.len() * 2);
-    let mask_entropy = pwd
-        .iter()
-        .map(|ch| {
-            mask.push('?');
-            if ch.is_ascii_digit() {
-                mask.push('d');
-                10f64.log2()
-            } else if ch.is_ascii_lowercase() {
-                mask.push('l');
-                26f64.log2()
-            } else if ch.is_ascii_uppercase() {
-                mask.push('u');
-                26f64.log2()
-            } else if SYMBOLS_SPACE.contains(ch) {
-                mask.push('s');
-                (SYMBOLS_SPACE.len() as f64).log2()
-            } else {
-                mask.push('b');
-                256f64.log2()
-            }
-        })
-        .sum();
+    let mut push_symbol = |(entropy, symbol): (f64, char)| {
+        mask.push('?');
+        mask.push(symbol);
+        entropy
+    };
+    let mask_entropy = if codepoints {
+        String::from_utf8_lossy(pwd)
+            .chars()
+            .map(|ch| push_symbol(codepoint_mask_entropy(ch, model)))
+            .sum()
+    } else {
+        pwd.iter()
+            .map(|&ch| push_symbol(char_mask_entropy(ch, model)))
+            .sum()
+    };
     (mask_entropy, mask)
 }
 
+/// returns every char of `pwd` with its entropy and mask symbol (e.g. `("P", 4.70, 'u')`),
+/// the parts summed by `password_mask_entropy`. non printable bytes are shown escaped (e.g.
+/// `\xff`)
+pub fn password_mask_entropy_per_char(
+    pwd: &[u8],
+    model: Option<&CharModel>,
+    codepoints: bool,
+) -> Vec<(String, f64, char)> {
+    if codepoints {
+        String::from_utf8_lossy(pwd)
+            .chars()
+            .map(|ch| {
+                let (entropy, symbol) = codepoint_mask_entropy(ch, model);
+                (ch.to_string(), entropy, symbol)
+            })
+            .collect()
+    } else {
+        pwd.iter()
+            .map(|&ch| {
+                let (entropy, symbol) = char_mask_entropy(ch, model);
+                (escape_byte(ch), entropy, symbol)
+            })
+            .collect()
+    }
+}
+
+/// returns the number of passwords of a charset mask returned by `password_mask_entropy`
+/// (i.e. 2^entropy as an exact number)
+pub fn charset_mask_keyspace(charset_mask: &str) -> BigUint {
+    charset_mask
+        .chars()
+        .filter(|&ch| ch != '?')
+        .map(|symbol| match symbol {
+            'd' => 10u32,
+            'l' | 'u' => 26,
+            's' => SYMBOLS_SPACE.len() as u32,
+            _ => 256,
+        })
+        .map(BigUint::from)
+        .product()
+}
+
+/// entropy bits below which a password is weak
+pub const WEAK_ENTROPY: f64 = 28.0;
+/// entropy bits from which a password is strong
+pub const STRONG_ENTROPY: f64 = 60.0;
+/// the (entropy bits, score) points of the strength score curve
+const SCORE_CURVE: [(f64, f64); 4] = [
+    (0.0, 0.0),
+    (WEAK_ENTROPY, 33.0),
+    (STRONG_ENTROPY, 66.0),
+    (100.0, 100.0),
+];
+
+/// returns a 0-100 strength score of an entropy with its label - weak, ok or strong.
+/// the score is linear between the points of the curve: 0-28 bits maps to 0-33 (weak),
+/// 28-60 bits to 33-66 (ok) and 60-100 bits to 66-100 (strong), 100 bits and above score 100
+pub fn strength_score(entropy: f64) -> (u8, &'static str) {
+    let label = if entropy < WEAK_ENTROPY {
+        "weak"
+    } else if entropy < STRONG_ENTROPY {
+        "ok"
+    } else {
+        "strong"
+    };
+    let score = SCORE_CURVE
+        .windows(2)
+        .find(|points| entropy < points[1].0)
+        .map(|points| {
+            let ((low, low_score), (high, high_score)) = (points[0], points[1]);
+            low_score + (entropy.max(low) - low) / (high - low) * (high_score - low_score)
+        })
+        .unwrap_or(100.0);
+    (score.floor() as u8, label)
+}
+
+/// returns the entropy of a utf8 codepoint and its mask symbol, non ascii ones are a single `?b`
+/// char
+fn codepoint_mask_entropy(ch: char, model: Option<&CharModel>) -> (f64, char) {
+    if ch.is_ascii() {
+        char_mask_entropy(ch as u8, model)
+    } else {
+        char_mask_entropy(0x80, None)
+    }
+}
+
+/// returns the entropy of `ch` and its mask symbol (e.g. `d` for `?d`)
+fn char_mask_entropy(ch: u8, model: Option<&CharModel>) -> (f64, char) {
+    let (symbol, charset): (char, &[u8]) = if ch.is_ascii_digit() {
+        ('d', find_symbol('d').unwrap().chars)
+    } else if ch.is_ascii_lowercase() {
+        ('l', find_symbol('l').unwrap().chars)
+    } else if ch.is_ascii_uppercase() {
+        ('u', find_symbol('u').unwrap().chars)
+    } else if SYMBOLS_SPACE.contains(&ch) {
+        ('s', SYMBOLS_SPACE)
+    } else {
+        ('b', &ALL_BYTES)
+    };
+    let entropy = match model {
+        Some(model) => model.charset_cost(ch, charset),
+        None => (charset.len() as f64).log2(),
+    };
+    (entropy, symbol)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::fs::File;
+
+    use num_bigint::BigUint;
+
+    use crate::helpers::RawFileReader;
+    use crate::model::CharModel;
     use crate::password_entropy::EntropyEstimator;
-    use crate::password_entropy::{password_mask_entropy, PasswordEntropyResult};
+    use crate::password_entropy::{
+        charset_mask_keyspace, password_mask_entropy, password_mask_entropy_codepoints,
+        password_mask_entropy_per_char, password_mask_entropy_with_model, strength_score,
+        PasswordEntropyResult,
+    };
     use crate::test_util::wordlist_fname;
+    use crate::wordlists::{WordlistFormat, WordlistOptions};
 
     #[test]
     fn test_compute_password_entropy() {
@@ -280,4 +685,317 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_password_with_newline() {
+        let est = EntropyEstimator::from_files(&[wordlist_fname("vocab.txt")]).unwrap();
+        let file = File::open(wordlist_fname("passwords-null.txt")).unwrap();
+        let masks = RawFileReader::with_delimiter(file, b'\0')
+            .map(|pwd| {
+                let pwd = pwd.unwrap();
+                est.estimate_password_entropy(&pwd).unwrap().charset_mask
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            masks,
+            vec![
+                "?l?l?l?l?l",
+                "?l?l?l?l?l?b?l?l?l?l",
+                "?l?l?l?l?d?d?d",
+                "?l?l?l?l"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_files_with_format() {
+        // smartlist words may contain newlines with delimiters other than newlines
+        for (fname, format) in [
+            ("wordlist-nul.txt", WordlistFormat::Nul),
+            ("wordlist-netstring.txt", WordlistFormat::Netstring),
+        ] {
+            let options = WordlistOptions::new().format(format);
+            let est = EntropyEstimator::from_files_with_options(&[wordlist_fname(fname)], &options)
+                .unwrap();
+            let (_, split, mask) = est.compute_password_subword_entropy(b"be\nta7").unwrap();
+            assert_eq!(split, vec!["be\nta", "7"]);
+            assert_eq!(mask, "?w1?d");
+        }
+    }
+
+    #[test]
+    fn test_compute_password_entropy_verbose() {
+        let est = EntropyEstimator::from_files(&[wordlist_fname("vocab.txt")])
+            .unwrap()
+            .verbose(true);
+        let (_, _, mask) = est
+            .compute_password_subword_entropy(b"helloworld123!")
+            .unwrap();
+        assert_eq!(mask, "?w1[vocab.txt]?d?d?d?s");
+    }
+
+    #[test]
+    fn test_compute_password_entropy_no_charset_words() {
+        let est = EntropyEstimator::from_files(&[wordlist_fname("wordlist-3words.txt")])
+            .unwrap()
+            .charset_words(false);
+        let (entropy, split, mask) = est.compute_password_subword_entropy(b"alphabeta").unwrap();
+        assert_eq!(entropy, 2f64 * 3f64.log2());
+        assert_eq!(split, vec!["alpha", "beta"]);
+        assert_eq!(mask, "?w1?w1");
+
+        let err = est
+            .compute_password_subword_entropy(b"alpha123beta")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no subword matches password \"alpha123beta\" at offset 5 (byte 0x31), uncovered span 5..8: \"123\""
+        );
+    }
+
+    #[test]
+    fn test_from_files_with_charsets() {
+        let est = EntropyEstimator::from_files_with_charsets(
+            &[wordlist_fname("wordlist-3words.txt")],
+            &['d'],
+        )
+        .unwrap();
+        let (entropy, split, mask) = est.compute_password_subword_entropy(b"beta42").unwrap();
+        assert_eq!(split, vec!["beta", "4", "2"]);
+        assert_eq!(mask, "?w1?d?d");
+        assert_eq!(entropy, 3f64.log2() + 10f64.log2() + 10f64.log2());
+
+        // lowercase chars are not a subword anymore
+        let err = est.compute_password_subword_entropy(b"betax").unwrap_err();
+        assert!(err.to_string().contains("uncovered span 4..5"), "{}", err);
+
+        let err =
+            EntropyEstimator::from_files_with_charsets(&[wordlist_fname("vocab.txt")], &['d', 'x'])
+                .err()
+                .unwrap();
+        assert_eq!(err.to_string(), "unknown builtin charset symbol: 'x'");
+    }
+
+    #[test]
+    fn test_compute_password_entropy_inline_words() {
+        let est = EntropyEstimator::from_files(&[wordlist_fname("wordlist-3words.txt")])
+            .unwrap()
+            .inline_words(&["acme", "company", ""])
+            .verbose(true);
+        let (entropy, split, mask) = est.compute_password_subword_entropy(b"acmebeta1").unwrap();
+        assert_eq!(split, vec!["acme", "beta", "1"]);
+        assert_eq!(mask, "?w2[inline]?w1[wordlist-3words.txt]?d");
+        assert_eq!(entropy, 2f64.log2() + 3f64.log2() + 10f64.log2());
+
+        // inline words only
+        let no_files: &[&str] = &[];
+        let est = EntropyEstimator::from_files(no_files)
+            .unwrap()
+            .inline_words(&["acme"])
+            .charset_words(false);
+        let (entropy, _, mask) = est.compute_password_subword_entropy(b"acmeacme").unwrap();
+        assert_eq!(entropy, 0f64);
+        assert_eq!(mask, "?w1?w1");
+    }
+
+    #[test]
+    fn test_contains_exact() {
+        let est = EntropyEstimator::from_files(&[wordlist_fname("vocab.txt")]).unwrap();
+        assert_eq!(est.contains_exact(b"helloworld"), Some(99809f64.log2()));
+        assert_eq!(est.contains_exact(b"7"), Some(10f64.log2()));
+        assert_eq!(est.contains_exact(b"helloworld123!"), None);
+        assert_eq!(est.contains_exact(b""), None);
+    }
+
+    #[test]
+    fn test_compute_password_entropy_exact_match() {
+        let est = EntropyEstimator::from_files(&[wordlist_fname("wordlist-3words.txt")])
+            .unwrap()
+            .inline_words(&[
+                "alphabeta",
+                "a",
+                "b",
+                "c",
+                "d",
+                "e",
+                "f",
+                "g",
+                "h",
+                "i",
+                "j",
+            ]);
+        // two words of the smaller vocab are cheaper than the whole password word
+        let (entropy, split, _) = est.compute_password_subword_entropy(b"alphabeta").unwrap();
+        assert_eq!(split, vec!["alpha", "beta"]);
+        assert_eq!(entropy, 2.0 * 3f64.log2());
+
+        let est = est.exact_match(true);
+        let (entropy, split, mask) = est.compute_password_subword_entropy(b"alphabeta").unwrap();
+        assert_eq!(split, vec!["alphabeta"]);
+        assert_eq!(mask, "?w2");
+        assert_eq!(entropy, 11f64.log2());
+        assert_eq!(est.subword_keyspace(b"alphabeta").unwrap(), 11u32.into());
+        // not a single word - falls back to the minimal split
+        let (_, split, _) = est.compute_password_subword_entropy(b"alphabeta1").unwrap();
+        assert_eq!(split, vec!["alpha", "beta", "1"]);
+    }
+
+    #[test]
+    fn test_password_mask_entropy_per_char() {
+        let per_char = password_mask_entropy_per_char(b"Pa0! \xff", None, false);
+        let expected = vec![
+            ("P", 26f64.log2(), 'u'),
+            ("a", 26f64.log2(), 'l'),
+            ("0", 10f64.log2(), 'd'),
+            ("!", 32f64.log2(), 's'),
+            (" ", 8f64, 'b'),
+            ("\\xff", 8f64, 'b'),
+        ];
+        assert_eq!(per_char.len(), expected.len());
+        for ((ch, entropy, symbol), (exp_ch, exp_entropy, exp_symbol)) in
+            per_char.iter().zip(expected)
+        {
+            assert_eq!(
+                (ch.as_str(), *entropy, *symbol),
+                (exp_ch, exp_entropy, exp_symbol)
+            );
+        }
+        let total: f64 = per_char.iter().map(|(_, entropy, _)| entropy).sum();
+        assert_eq!(total, password_mask_entropy(b"Pa0! \xff").0);
+
+        let per_char = password_mask_entropy_per_char("ü1".as_bytes(), None, true);
+        assert_eq!(
+            per_char,
+            vec![
+                ("ü".to_string(), 8f64, 'b'),
+                ("1".to_string(), 10f64.log2(), 'd'),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_password_mask_entropy_with_model() {
+        let english = CharModel::english();
+        let (uniform, mask) = password_mask_entropy(b"ez1");
+        let (weighted, weighted_mask) = password_mask_entropy_with_model(b"ez1", &english, false);
+        assert_eq!(mask, weighted_mask);
+        // e is more frequent and z less frequent than uniform, digits stay uniform
+        let e_cost = english.charset_cost(b'e', b"abcdefghijklmnopqrstuvwxyz");
+        let z_cost = english.charset_cost(b'z', b"abcdefghijklmnopqrstuvwxyz");
+        assert!(e_cost < 26f64.log2() && 26f64.log2() < z_cost);
+        assert!((weighted - (e_cost + z_cost + 10f64.log2())).abs() < 1e-9);
+        assert!((uniform - (2f64 * 26f64.log2() + 10f64.log2())).abs() < 1e-9);
+
+        // an empty model is uniform
+        let (entropy, _) = password_mask_entropy_with_model(b"ez1", &CharModel::new(), false);
+        assert!((entropy - uniform).abs() < 1e-9);
+
+        // subwords of builtin charsets are weighted too
+        let est = EntropyEstimator::from_files(&[wordlist_fname("wordlist-3words.txt")])
+            .unwrap()
+            .char_model(Some(english.clone()));
+        let result = est.estimate_password_entropy(b"betae").unwrap();
+        assert_eq!(result.min_subword_mask, "?w1?l");
+        assert!((result.subword_entropy - (3f64.log2() + e_cost)).abs() < 1e-9);
+        assert_eq!(
+            result.mask_entropy,
+            password_mask_entropy_with_model(b"betae", &english, false).0
+        );
+    }
+
+    #[test]
+    fn test_password_keyspace() {
+        let (entropy, mask) = password_mask_entropy(b"Ab1!");
+        let keyspace = charset_mask_keyspace(&mask);
+        assert_eq!(keyspace, BigUint::from(26u32 * 26 * 10 * 32));
+        assert!((entropy - (26f64 * 26f64 * 10f64 * 32f64).log2()).abs() < 1e-9);
+
+        let est = EntropyEstimator::from_files(&[wordlist_fname("wordlist-3words.txt")])
+            .unwrap()
+            .charset_words(false);
+        assert_eq!(
+            est.subword_keyspace(b"alphabeta").unwrap(),
+            BigUint::from(9u32)
+        );
+        assert!(est.subword_keyspace(b"alpha1").is_err());
+    }
+
+    #[test]
+    fn test_strength_score() {
+        for (entropy, expected) in [
+            (0.0, (0, "weak")),
+            (14.0, (16, "weak")),
+            (27.99, (32, "weak")),
+            (28.0, (33, "ok")),
+            (44.0, (49, "ok")),
+            (59.99, (65, "ok")),
+            (60.0, (66, "strong")),
+            (80.0, (83, "strong")),
+            (100.0, (100, "strong")),
+            (200.0, (100, "strong")),
+        ] {
+            assert_eq!(strength_score(entropy), expected, "entropy {}", entropy);
+        }
+    }
+
+    #[test]
+    fn test_compute_password_entropy_uncovered_error() {
+        let est = EntropyEstimator::from_files(&[wordlist_fname("wordlist-3words.txt")])
+            .unwrap()
+            .charset_words(false);
+        let cases: Vec<(&[u8], &str)> = vec![
+            (b"x", "at offset 0 (byte 0x78), uncovered span 0..1: \"x\""),
+            (
+                b"gammax",
+                "at offset 5 (byte 0x78), uncovered span 5..6: \"x\"",
+            ),
+            (
+                b"beta\xffalpha",
+                "at offset 4 (byte 0xff), uncovered span 4..5: \"\u{fffd}\"",
+            ),
+            (
+                b"alpha!!beta??",
+                "at offset 5 (byte 0x21), uncovered span 5..7: \"!!\"",
+            ),
+        ];
+        for (pwd, expected) in cases {
+            let err = est.compute_password_subword_entropy(pwd).unwrap_err();
+            assert!(err.to_string().ends_with(expected), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_password_mask_cost_codepoints() {
+        let cases: Vec<(&str, (f64, &str))> = vec![
+            ("Aa1!", (password_mask_entropy(b"Aa1!").0, "?u?l?d?s")),
+            ("😃", (8.0, "?b")),
+            ("a😃é", (26f64.log2() + 16.0, "?l?b?b")),
+        ];
+        for (pwd, (expected_cost, expected_mask)) in cases {
+            assert_eq!(
+                password_mask_entropy_codepoints(pwd.as_bytes()),
+                (expected_cost, expected_mask.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_compute_password_entropy_codepoints() {
+        let est = EntropyEstimator::from_files(&[wordlist_fname("wordlist-3words.txt")])
+            .unwrap()
+            .codepoints(true);
+        let (entropy, split, mask) = est
+            .compute_password_subword_entropy("alpha😃1".as_bytes())
+            .unwrap();
+        assert_eq!(mask, "?w1?b?d");
+        assert_eq!(split, vec!["alpha", "😃", "1"]);
+        assert_eq!(entropy, 3f64.log2() + 8.0 + 10f64.log2());
+
+        // byte mode splits the emoji into 4 bytes
+        let est = EntropyEstimator::from_files(&[wordlist_fname("wordlist-3words.txt")]).unwrap();
+        let (_, _, mask) = est
+            .compute_password_subword_entropy("alpha😃1".as_bytes())
+            .unwrap();
+        assert_eq!(mask, "?w1?b?b?b?b?d");
+    }
 }
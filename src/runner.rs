@@ -1,14 +1,57 @@
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::env;
-use std::fs::File;
-use std::io::{stdout, BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::fs::{self, File};
+use std::io::{stderr, stdout, BufWriter, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::thread::{self, Scope};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use num_bigint::{BigUint, ToBigUint};
+use ordered_float::OrderedFloat;
+use regex::Regex;
+use serde_json::{json, Map, Value};
 
-use crate::create_smartlist::{SmartlistBuilder, SmartlistTokenizer, DEFAULT_VOCAB_SIZE};
-use crate::generators::get_word_generator;
-use crate::helpers::RawFileReader;
-use crate::password_entropy::EntropyEstimator;
-use crate::{built_info, BoxResult};
+use crate::charsets::{
+    custom_charset_duplicates, expand_custom_charset, find_symbol, read_charset_file, Charset,
+};
+use crate::checksum::{Checksum, ChecksumWriter};
+use crate::config::{find_config_file, GenerateConfig, CONFIG_ENV_VAR};
+use crate::create_smartlist::{
+    read_vocab_file, SmartlistBuilder, SmartlistSortOrder, SmartlistTokenizer, DEFAULT_VOCAB_SIZE,
+};
+use crate::generators::{
+    get_word_generator_with_options, mask_combinations, CombineGenerator, GeneratorOptions,
+    ModelGenerator, NoRepeat, WordGenerator,
+};
+use crate::helpers::{
+    escape_byte, is_regular_file, is_stdin_input, open_input, HeadWriter, NoTrailingNewlineWriter,
+    RawFileReader, SampleWriter, TeeWriter, ThreadedWriter, TopN,
+};
+use crate::manifest::{read_manifest_wordlists, records_digest, Manifest};
+use crate::mask::{extract_inline_resources, insert_inline, InlineResources, Mask};
+use crate::masks_file::{
+    is_hcmask_file, load_charset_files, masks_file_dir, read_hcmask_file, read_masks_file,
+    resolve_path, MaskEntry,
+};
+use crate::model::CharModel;
+use crate::normalize::{normalize_words, NormalizeOptions, SortOrder};
+use crate::output::{Annotation, Case, Encoding, NumberLines, OutputOptions, WordsWriter};
+use crate::password_entropy::{
+    charset_mask_keyspace, password_mask_entropy, password_mask_entropy_codepoints,
+    password_mask_entropy_per_char, password_mask_entropy_with_model, strength_score,
+    EntropyEstimator,
+};
+use crate::sample::reservoir_sample;
+use crate::substrings::write_substrings;
+use crate::wordlists::{ColumnSelector, Wordlist, WordlistFormat, WordlistOptions, WordlistStats};
+use crate::{built_info, BoxResult, MAX_WORD_SIZE};
+
+/// chunks pending to be written with --async-write before generation blocks
+const ASYNC_WRITE_CHUNKS: usize = 2;
 
 const EXAMPLE_USAGE: &str = r#"
 For specific subcommand help run: cracken <subcommand> --help
@@ -24,6 +67,91 @@ Example Usage:
   # all digits from 0 to 99999999
   cracken -m 1 ?d?d?d?d?d?d?d?d
 
+  # the first word of every length to eyeball a mask - A, Aa, Aa0 to Aa0000000
+  cracken --one-per-length -m 1 ?u?l?d?d?d?d?d?d?d?d
+
+  # the first 100 words of each length from 1 to 8, a length balanced sample of a mask
+  cracken --max-per-length 100 -m 1 ?l?l?l?l?l?l?l?l
+
+  # 8 chars words of at least 3 of the 4 char classes (lower, upper, digit, symbol), a common password policy
+  cracken --min-classes 3 ?a?a?a?a?a?a?a?a
+
+  # all digits from 0000 to 9999 prefixed with their index - 0000:0000 to 9999:9999
+  cracken --number-lines ?d?d?d?d
+
+  # keeps generating while a slow consumer reads the previous output chunks
+  cracken --async-write ?l?l?l?l?l?l | ./slow-consumer
+
+  # all 8 digit passwords to digits.txt, printing their sha256 to verify a regenerated copy
+  cracken --checksum sha256 -o digits.txt ?d?d?d?d?d?d?d?d
+
+  # all 6 lowercase letters passwords to lower.txt, watching the first ones on stdout
+  cracken --tee -o lower.txt ?l?l?l?l?l?l | head
+
+  # the 3 char words not matching ?l?l?d on any position, to study which words a mask misses
+  cracken --anti-mask '?l?u?d?s' ?l?l?d
+
+  # the first and last 5 words of a mask with its number of words, to check it before a long run
+  cracken --preview 5 ?u?l?l?l?l?d?d?d?d
+
+  # every 4th word of a mask starting from the second - one of 4 workers splitting its keyspace evenly
+  cracken --step 4 --offset 1 ?d?d?d
+
+  # all 4 digit subdomains of example.com - https://0000.example.com to https://9999.example.com
+  cracken --prefix https:// --suffix .example.com ?d?d?d?d
+
+  # all 2 digits words without a newline after the last one, for consumers failing on a trailing newline
+  cracken --no-trailing-separator -o digits.txt ?d?d
+
+  # wordlists and custom charsets given inline - names.txt words followed by a, b or c
+  cracken '?w1{names.txt}?1{abc}'
+
+  # all concatenations of any number of words from words.txt with a total length of 8 to 10
+  cracken --combine -w words.txt --total-min 8 --total-max 10
+
+  # all 2 byte binary values hex encoded - 0000 to ffff
+  cracken --encode hex ?b?b
+
+  # capitalized names from wordlist followed by 2 digits - Alice00 to Bob99
+  cracken --case capitalize -w names.txt ?w1?d?d
+
+  # all 4 digit pins as json lines - {"word":"0000","index":0,"length":4} etc. (slower than plain output)
+  cracken --format jsonl ?d?d?d?d
+
+  # the words of top100.txt first, then all 6 lowercase passwords not already written
+  cracken --priority-file top100.txt --dedup-priority ?l?l?l?l?l?l
+
+  # all 8 digit passwords except the already cracked ones in cracked.txt
+  cracken --exclude-wordlist cracked.txt ?d?d?d?d?d?d?d?d
+
+  # 6 lowercase passwords from the most to the least probable by a model trained on rockyou.txt
+  cracken train -f rockyou.txt -o model.txt
+  cracken --order-by-model model.txt ?l?l?l?l?l?l
+
+  # record the masks, wordlists hashes and cracken version of the run to run.json
+  cracken --manifest run.json -w firstnames.txt ?w1?d?d?d?d
+
+  # only the words using the lines appended to firstnames.txt since the run of run.json
+  cracken --delta-from run.json --manifest run2.json -w firstnames.txt ?w1?d?d?d?d
+
+  # generate for up to 2 hours, recording the number of words generated until then in run.json
+  cracken --max-time 2h --manifest run.json ?a?a?a?a?a?a?a?a
+
+  # digits from any 1 to 4 of the 6 positions of the mask
+  cracken --positional-increment -m 1 -x 4 ?d?d?d?d?d?d
+
+  # write the words of each mask of masks.txt to its own file - out/mask_000.txt, out/mask_001.txt etc.
+  cracken --output-dir out -i masks.txt
+
+  # every word of masks.txt prefixed with its mask and a tab (e.g. ?d?d<tab>42), to trace hits back to masks
+  cracken --annotate-mask -i masks.txt
+
+  # only the words from abc000 to abc999 of the mask, e.g. to resume an interrupted run
+  cracken --from abc000 --to abc999 ?l?l?l?d?d?d
+
+  # print the number of words and the estimated time to generate them
+  cracken --stats --estimate-time ?a?a?a?a?a?a?a?a
+
   # words with pwd prefix - pwd0000 to pwd9999
   cracken pwd?d?d?d?d
 
@@ -39,6 +167,15 @@ Example Usage:
   # 4 custom charsets - the order determines the id of the charset
   cracken -c 01 -c ab -c de -c ef '?1?2?3?4'
 
+  # custom charset of digits and lowercase chars
+  cracken -c '?d?l' '?1?1?1?1'
+
+  # custom charset from a hashcat charset file
+  cracken --charset-file german.hcchr '?1?1?1?1'
+
+  # 4 consonants (?l without aeiou) followed by a non zero digit. use \{ for a literal brace after a charset
+  cracken '?l{-aeiou}?l{-aeiou}?l{-aeiou}?l{-aeiou}?d{-0}'
+
   # 4 lowercase chars with years 2000-2019 suffix
   cracken -c 01 '?l?l?l?l20?1?d'
 
@@ -48,9 +185,30 @@ Example Usage:
   # starts with firstname from wordlist with lastname from wordlist ending with symbol
   cracken -w firstnames.txt -w lastnames.txt -c '!@#$' '?w1?w2?1'
 
+  # multiple masks generated one after another
+  cracken ?d?d ?l?l ?u?u
+
+  # generate all masks of a hashcat .hcmask file
+  cracken -i rockyou-1-60.hcmask
+
+  # a masks file with ?w1{words.txt} and .hcchr files relative to another directory than its own
+  cracken -i masks.txt --base-dir bundle/
+
   # repeating wordlists multiple times and combining charsets
   cracken -w verbs.txt -w nouns.txt '?w1?w2?w1?w2?w2?d?d?d'
 
+  # passphrases of 3 different words - skips 'correct-correct-horse' etc.
+  cracken -w words.txt --no-repeat-any '?w1-?w1-?w1'
+
+  # passphrases of at least 2 different words - skips 'the-the-the' but keeps 'the-the-cat'
+  cracken -w words.txt --min-distinct-words 2 '?w1-?w1-?w1'
+
+  # pairs of usernames and passwords of the same line - user1:pass1, user2:pass2 etc.
+  cracken -w usernames.txt -w passwords.txt --zip '?w1:?w2'
+
+  # use the 3rd column of a csv file as the wordlist
+  cracken -w users.csv --wordlist-column 3 --wordlist-delim ',' '?w1?d?d'
+
 
   ## Create Smartlists Subcommand Examples:
 
@@ -63,20 +221,103 @@ Example Usage:
   # create smartlist with minimum subword length of 3 and max numbers-only subwords of size 6
   cracken create -f rockyou.txt --min-word-len 3 --numbers-max-size 6 --smartlist smart.txt
 
+  # create smartlist of alphabetic only subwords
+  cracken create -f rockyou.txt --only-alpha --smartlist smart.txt
+
+  # create smartlist without numbers-only and punctuation-only subwords
+  cracken create -f rockyou.txt --reject '^([0-9]+|[[:punct:]]+)$' --smartlist smart.txt
+
+  # update an existing smartlist with words from a new corpus
+  cracken create --base smart.txt -f newcorpus.txt --smartlist updated.txt
+
+  # create smartlist of the 10k most frequent subwords sorted alphabetically
+  cracken create -f rockyou.txt -m 10000 --sort-order alpha --smartlist smart.txt
+
 
   ## Entropy Subcommand Examples:
 
   # estimating entropy of a password
   cracken entropy --smartlist vocab.txt 'helloworld123!'
 
+  # estimating entropy of a password with additional vocab words, without a file
+  cracken entropy --smartlist vocab.txt --word company --word acme 'Acme2024!'
+
+  # estimating entropy of a password, also printing the number of guesses (2^entropy)
+  cracken entropy --smartlist vocab.txt --show-keyspace 'helloworld123!'
+
+  # estimating entropy with english letters frequencies instead of uniform charsets
+  cracken entropy --smartlist vocab.txt --english-freq 'helloworld123!'
+
+  # estimating charset mask entropy only, without a smartlist
+  cracken entropy --mask-only 'Pa55!'
+
+  # the charset mask entropy of each char of a password
+  cracken entropy --mask-only --per-char 'Passw0rd'
+
+  # a 0-100 strength score of a password with its label (weak, ok or strong) alongside the entropy bits
+  cracken entropy --smartlist vocab.txt --score 'helloworld123!'
+
+  # estimating entropy of a multilingual password, counting chars instead of bytes
+  cracken entropy --codepoints --smartlist vocab.txt 'пароль123'
+
   # estimating entropy of a passwords file with a charset mask entropy (default is hybrid)
   cracken entropy --smartlist vocab.txt -t charset -p passwords.txt
 
   # estimating the entropy of a passwords file
   cracken entropy --smartlist vocab.txt -p passwords.txt
+
+  # estimating entropy of passwords from multiple files, prefixing rows with the filename
+  cracken entropy --smartlist vocab.txt -p dump1.txt -p dump2.txt
+
+  # the average entropy of a passwords file and its 10 strongest passwords
+  cracken entropy --smartlist vocab.txt --summary --top-strong 10 -p passwords.txt
+
+
+  ## Wordlist Stats Subcommand Examples:
+
+  # number of words per length, min/max/avg length and charsets coverage of rockyou.txt
+  cracken wordlist-stats -w rockyou.txt
+
+  # same as above as json, including the 5 most common lengths
+  cracken wordlist-stats -w rockyou.txt --top 5 --format json
+
+
+  ## Sample Subcommand Examples:
+
+  # 100000 random lines of rockyou.txt, the same ones on every run with the same seed
+  cracken sample -w rockyou.txt -n 100000 --seed 42
+
+
+  ## Substrings Subcommand Examples:
+
+  # every 4 bytes substring of each word, e.g. pass, assw, sswo, swor and word of password
+  cracken substrings -w words.txt --len 4 --unique
+
+
+  ## Charset Subcommand Examples:
+
+  # the chars of the ?s builtin charset with their byte values (it includes the space)
+  cracken charset ?s
+
+  # the chars of a custom charset definition as expanded by generate -c
+  cracken charset -c '?dabc\x00'
+
+
+  ## Normalize Subcommand Examples:
+
+  # a canonical wordlist of a windows (CRLF) wordlist - trimmed, deduped and sorted by length
+  cracken normalize -w messy.txt -o clean.txt --dedup --sort length
+
+  # normalizes a wordlist in place keeping only its 8 to 16 bytes words
+  cracken normalize -w words.txt -o words.txt --min-len 8 --max-len 16
 "#;
 
-fn parse_args(args: Option<Vec<&str>>) -> ArgMatches<'static> {
+/// parses the command line `args`, adding the defaults of the config loaded by `load_config` to
+/// the generate subcommand. other subcommands do not load the config
+fn parse_args<F>(args: Option<Vec<&str>>, load_config: F) -> BoxResult<ArgMatches<'static>>
+where
+    F: FnOnce() -> BoxResult<Option<GenerateConfig>>,
+{
     let osargs: Vec<String>;
     let mut args = match args {
         Some(itr) => itr,
@@ -87,35 +328,65 @@ fn parse_args(args: Option<Vec<&str>>) -> ArgMatches<'static> {
     };
 
     // workaround for default subcommand
-    if args.len() >= 2 && !vec!["generate", "entropy", "create", "--help"].contains(&args[1]) {
+    if args.len() >= 2
+        && ![
+            "generate",
+            "entropy",
+            "create",
+            "train",
+            "wordlist-stats",
+            "sample",
+            "substrings",
+            "charset",
+            "normalize",
+            "--help",
+        ]
+        .contains(&args[1])
+    {
         args.insert(1, "generate");
     }
 
+    let after_help = format!(
+        "{}\n{}-v{} {}-{} compiler: {}\nmore info at: {}",
+        EXAMPLE_USAGE,
+        built_info::PKG_NAME,
+        built_info::PKG_VERSION,
+        built_info::CFG_OS,
+        built_info::CFG_TARGET_ARCH,
+        built_info::RUSTC_VERSION,
+        built_info::PKG_HOMEPAGE,
+    );
+
+    // config file defaults are added as flags unless given on the command line
+    if args.len() >= 2 && args[1] == "generate" {
+        if let Some(config) = load_config()? {
+            // on invalid args the config is skipped, the final parse reports the error
+            if let Ok(matches) = build_app(&after_help).get_matches_from_safe(&args) {
+                let given = matches.subcommand_matches("generate").unwrap();
+                let config_flags = config.missing_flags(|name| given.occurrences_of(name) > 0);
+                args.splice(2..2, config_flags.iter().map(|flag| flag.as_str()));
+                return Ok(build_app(&after_help).get_matches_from(args));
+            }
+        }
+    }
+    Ok(build_app(&after_help).get_matches_from(args))
+}
+
+fn build_app(after_help: &str) -> App<'static, '_> {
     App::new(format!(
         "Cracken v{} - {}",
         built_info::PKG_VERSION,
         built_info::PKG_DESCRIPTION
     )).setting(AppSettings::DisableHelpSubcommand)
         .setting(AppSettings::ArgRequiredElseHelp)
-    .after_help(
-        format!(
-            "{}\n{}-v{} {}-{} compiler: {}\nmore info at: {}",
-            EXAMPLE_USAGE,
-            built_info::PKG_NAME,
-            built_info::PKG_VERSION,
-            built_info::CFG_OS,
-            built_info::CFG_TARGET_ARCH,
-            built_info::RUSTC_VERSION,
-            built_info::PKG_HOMEPAGE,
-        )
-        .as_str())
+    .after_help(after_help)
         .subcommand(SubCommand::with_name("generate")
-        .about("(default) - Generates newline separated words according to given mask and wordlist files")
+        .about("(default) - Generates newline separated words according to given mask and wordlist files. only words are written to stdout")
         .display_order(0)
     .arg(
         Arg::with_name("mask")
             .long_help(
-                r#"the wordlist mask to generate.
+                r#"the wordlist mask to generate, multiple masks are generated one after another.
 available masks are:
     builtin charsets:
     ?d - digits: "0123456789"
@@ -127,20 +398,35 @@ available masks are:
 
     custom charsets ?1 to ?9:
     ?1 - first custom charset specified by --charset 'mychars'
+    ?1{mychars} - the first custom charset given inline
 
     wordlists ?w1 to ?w9:
     ?w1 - first wordlist specified by --wordlist 'my-wordlist.txt'
+    ?w1{my-wordlist.txt} - the first wordlist given inline
 "#,
             )
             .takes_value(true)
-            .required_unless("masks-file"),
+            .multiple(true)
+            .required_unless_one(&["masks-file", "combine"]),
     )
     .arg(Arg::with_name("masks-file")
             .short("i")
             .long("masks-file")
-            .help("a file containing masks to generate")
+            .help("a file containing masks to generate, one per line, or - to read them from stdin. a line may override the custom charsets: '?1?2 -c abc -c 0123' or use .hcchr charset files. inline wordlists (?w1{words.txt}) and charset files are relative to the masks file directory. inline resources apply to their own line only")
+            .takes_value(true)
+            .required_unless_one(&["mask", "combine"]),
+    )
+    .arg(Arg::with_name("hcmask")
+            .long("hcmask")
+            .help("parse the masks file as a hashcat .hcmask file (auto detected by .hcmask extension)")
+            .takes_value(false)
+            .requires("masks-file"),
+    )
+    .arg(Arg::with_name("base-dir")
+            .long("base-dir")
+            .help("directory the wordlists and charset files referenced by the masks file are relative to, instead of the masks file directory")
             .takes_value(true)
-            .required_unless("mask"),
+            .requires("masks-file"),
     )
     .arg(
         Arg::with_name("min-length")
@@ -162,14 +448,270 @@ available masks are:
         Arg::with_name("stats")
             .short("s")
             .long("stats")
-            .help("prints the number of words this command will generate and exits")
+            .help("prints the number of words this command will generate to stderr and exits")
+            .takes_value(false)
+            .required(false),
+    ).arg(
+        Arg::with_name("stats-file")
+            .long("stats-file")
+            .help("with --stats, writes the number of words to this file instead of stderr, e.g. for scripts")
+            .takes_value(true)
+            .requires("stats")
+            .required(false),
+    ).arg(
+        Arg::with_name("estimate-time")
+            .long("estimate-time")
+            .help("with --stats, also prints the estimated run time measured by a short generation sample of each mask")
+            .takes_value(false)
+            .requires("stats")
+            .required(false),
+    ).arg(
+        Arg::with_name("preview")
+            .long("preview")
+            .help("prints the first N and last N words of each mask with its number of words to stderr and exits, without generating the words between. the last words are computed for charsets and wordlists masks only")
+            .takes_value(true)
+            .conflicts_with_all(&["stats", "priority-file", "number-lines", "manifest", "output-dir", "max-time", "checksum"])
+            .required(false),
+    ).arg(
+        Arg::with_name("step")
+            .long("step")
+            .help("generates only every N-th word of each mask by its index, a uniform spread of the keyspace (e.g. run workers with --offset 0 to N-1 to split it). charsets and wordlists masks only")
+            .takes_value(true)
+            .conflicts_with_all(&["preview", "combine", "order-by-model", "positional-increment", "delta-from"])
+            .required(false),
+    ).arg(
+        Arg::with_name("offset")
+            .long("offset")
+            .help("with --step, the index of the first word to generate, between 0 and N-1. defaults to 0")
+            .takes_value(true)
+            .requires("step")
+            .required(false),
+    ).arg(
+        Arg::with_name("verbose")
+            .short("v")
+            .long("verbose")
+            .help("reports to stderr the duplicate chars removed from custom charsets and the wordlist lines skipped by --max-word-len")
+            .takes_value(false)
+            .required(false),
+    ).arg(
+        Arg::with_name("combine")
+            .long("combine")
+            .help("instead of a mask, generates concatenations of any number of words from the wordlist with a total length between --total-min and --total-max")
+            .takes_value(false)
+            .requires_all(&["wordlist", "total-max"])
+            .conflicts_with_all(&["mask", "masks-file"])
+            .required(false),
+    ).arg(
+        Arg::with_name("order-by-model")
+            .long("order-by-model")
+            .help("generates the words of charsets masks from the most to the least probable according to a model created by the train subcommand, instead of in order. note: all the pending words are kept in memory, growing with the number of generated words")
+            .takes_value(true)
+            .conflicts_with_all(&["combine", "min-length", "max-length"])
+            .required(false),
+    ).arg(
+        Arg::with_name("manifest")
+            .long("manifest")
+            .help("writes a json file describing the run - masks, charsets, wordlists record counts, format and sha256, lengths, the other options changing the words (e.g. --prefix), total words and cracken version - for reproducing it later")
+            .takes_value(true)
+            .required(false),
+    ).arg(
+        Arg::with_name("delta-from")
+            .long("delta-from")
+            .help("generates only the words using the lines appended to the wordlist since the run of this --manifest file, for a single wordlist that only grew")
+            .takes_value(true)
+            .requires("wordlist")
+            .conflicts_with_all(&["combine", "order-by-model", "zip", "unordered-unique", "no-repeat", "no-repeat-any", "dedup-wordlists", "positional-increment", "from", "one-per-length"])
+            .required(false),
+    ).arg(
+        Arg::with_name("max-time")
+            .long("max-time")
+            .help("stops generating after this duration (e.g. 30s, 15m, 2h or 1d) and exits successfully. --manifest then records the number of words generated before stopping")
+            .takes_value(true)
+            .required(false),
+    ).arg(
+        Arg::with_name("fail-on-deadline")
+            .long("fail-on-deadline")
+            .help("exits with an error when --max-time stopped the generation")
+            .takes_value(false)
+            .requires("max-time")
+            .required(false),
+    ).arg(
+        Arg::with_name("positional-increment")
+            .long("positional-increment")
+            .help("generates charsets masks with any subset of their positions, e.g. ?d?d?d also generates ?d and ?d?d from every pair of positions. the words grow exponentially with the mask length - bound the subset sizes with --minlen and --maxlen")
+            .takes_value(false)
+            .conflicts_with_all(&["combine", "order-by-model"])
+            .required(false),
+    ).arg(
+        Arg::with_name("one-per-length")
+            .long("one-per-length")
+            .help("generates only the first word of each length from --minlen to --maxlen, to quickly see what a mask produces at every length. with --stats prints the number of words the mask would generate and the number of samples. charsets masks only")
+            .takes_value(false)
+            .conflicts_with_all(&["combine", "order-by-model", "positional-increment", "from"])
+            .required(false),
+    ).arg(
+        Arg::with_name("min-classes")
+            .long("min-classes")
+            .help("generates only the words with at least K of the 4 char classes lower, upper, digit and symbol (any other byte), e.g. 3 for a '3 of 4 classes' password policy. --stats counts the matching words exactly. charsets masks only")
+            .takes_value(true)
+            .conflicts_with_all(&["combine", "order-by-model", "positional-increment", "from", "one-per-length", "max-per-length"])
+            .required(false),
+    ).arg(
+        Arg::with_name("max-per-length")
+            .long("max-per-length")
+            .help("generates only the first N words of each length from --minlen to --maxlen, a length balanced sample instead of exhausting the short lengths first. --stats counts the capped words. charsets masks only")
+            .takes_value(true)
+            .conflicts_with_all(&["combine", "order-by-model", "positional-increment", "from", "one-per-length"])
+            .required(false),
+    ).arg(
+        Arg::with_name("from")
+            .long("from")
+            .help("generates only the words from this word up to --to (inclusive) in generation order, e.g. to resume or split a run. charsets masks only")
+            .takes_value(true)
+            .requires("to")
+            .conflicts_with_all(&["combine", "order-by-model", "positional-increment"])
+            .required(false),
+    ).arg(
+        Arg::with_name("to")
+            .long("to")
+            .help("the last word to generate with --from")
+            .takes_value(true)
+            .requires("from")
+            .required(false),
+    ).arg(
+        Arg::with_name("total-min")
+            .long("total-min")
+            .help("minimum total length of the concatenated words used with --combine, defaults to 1")
+            .takes_value(true)
+            .requires("combine")
+            .required(false),
+    ).arg(
+        Arg::with_name("total-max")
+            .long("total-max")
+            .help("maximum total length of the concatenated words used with --combine")
+            .takes_value(true)
+            .requires("combine")
+            .required(false),
+    ).arg(
+        Arg::with_name("prefix")
+            .long("prefix")
+            .help("a fixed string added before every generated word")
+            .takes_value(true)
+            .required(false),
+    ).arg(
+        Arg::with_name("suffix")
+            .long("suffix")
+            .help("a fixed string added after every generated word")
+            .takes_value(true)
+            .required(false),
+    ).arg(
+        Arg::with_name("encode")
+            .long("encode")
+            .help("encodes every word (with its prefix and suffix) as hex or base64, e.g. for binary ?b words")
+            .takes_value(true)
+            .possible_values(&["hex", "base64"])
+            .required(false),
+    ).arg(
+        Arg::with_name("case")
+            .long("case")
+            .help("converts the case of every word - upper, lower, capitalize (the first letter) or title (the first letter of every run of letters). other bytes are kept as is")
+            .takes_value(true)
+            .possible_values(&["upper", "lower", "capitalize", "title"])
+            .required(false),
+    ).arg(
+        Arg::with_name("format")
+            .long("format")
+            .help("output format of the words. jsonl writes a json object per word - {\"word\":\"...\",\"index\":N,\"length\":L}, base64 encoding words that are not valid utf8. jsonl is several times slower than plain")
+            .takes_value(true)
+            .possible_values(&["plain", "jsonl"])
+            .required(false),
+    ).arg(
+        Arg::with_name("priority-file")
+            .long("priority-file")
+            .help("file of words written as is before any generated word, e.g. the most likely passwords")
+            .takes_value(true)
+            .required(false),
+    ).arg(
+        Arg::with_name("dedup-priority")
+            .long("dedup-priority")
+            .help("skip generated words already written from --priority-file. note: the priority words are kept in memory")
+            .takes_value(false)
+            .requires("priority-file")
+            .required(false),
+    ).arg(
+        Arg::with_name("exclude-wordlist")
+            .long("exclude-wordlist")
+            .help("skip generated words found in this file, e.g. already cracked passwords. --stats is then an upper bound. note: the excluded words are kept in memory, with an overhead of about 50 bytes per word")
+            .takes_value(true)
+            .required(false),
+    ).arg(
+        Arg::with_name("number-lines")
+            .long("number-lines")
+            .help("prefixes each word with its zero padded index (e.g. 0042:word), padded to the digits count of the last index")
+            .takes_value(false)
+            .required(false),
+    ).arg(
+        Arg::with_name("number-sep")
+            .long("number-sep")
+            .help("separator between the index and the word used with --number-lines")
+            .takes_value(true)
+            .default_value(":")
+            .required(false),
+    ).arg(
+        Arg::with_name("annotate-mask")
+            .long("annotate-mask")
+            .help("prefixes every word with the mask generating it and a tab (e.g. '?d?d\t42'), to trace words back to the masks of a masks file")
             .takes_value(false)
+            .conflicts_with("combine")
+            .required(false),
+    ).arg(
+        Arg::with_name("annotate-sep")
+            .long("annotate-sep")
+            .help("separator between the mask and the word used with --annotate-mask, defaults to a tab")
+            .takes_value(true)
+            .requires("annotate-mask")
+            .required(false),
+    ).arg(
+        Arg::with_name("annotate-suffix")
+            .long("annotate-suffix")
+            .help("with --annotate-mask, writes the mask after the word instead of before it")
+            .takes_value(false)
+            .requires("annotate-mask")
+            .required(false),
+    ).arg(
+        Arg::with_name("no-trailing-separator")
+            .long("no-trailing-separator")
+            .help("omits the newline after the last word written (of each file with --output-dir), for consumers failing on a trailing newline")
+            .takes_value(false)
+            .required(false),
+    ).arg(
+        Arg::with_name("async-write")
+            .long("async-write")
+            .help("writes the output from a separate thread, so generation continues while a slow consumer (e.g. a pipe to a hash cracker) reads the previous chunks")
+            .takes_value(false)
+            .required(false),
+    ).arg(
+        Arg::with_name("checksum")
+            .long("checksum")
+            .help("prints a checksum of the written output to stderr at the end, to verify a wordlist regenerated elsewhere. xxhash (xxHash64) is much faster than sha256. with --output-dir a checksum is printed per file")
+            .takes_value(true)
+            .possible_values(&["sha256", "xxhash"])
             .required(false),
     ).arg(
         Arg::with_name("custom-charset")
             .short("c")
             .long("custom-charset")
-            .help("custom charset (string of chars). up to 9 custom charsets - ?1 to ?9. use ?1 on the mask for the first charset")
+            .help("custom charset (string of chars). up to 9 custom charsets - ?1 to ?9. use ?1 on the mask for the first charset. builtin charsets are expanded (e.g. '?d?l' for digits and lowercase), '??' or '\\?' is a literal '?', '\\\\' is a literal '\\' and '\\xHH' is a hex byte")
+            .takes_value(true)
+            .required(false)
+            .multiple(true)
+            .number_of_values(1)
+            .max_values(9),
+    )
+    .arg(
+        Arg::with_name("charset-file")
+            .long("charset-file")
+            .help("file containing a custom charset (e.g. hashcat .hcchr), numbered along with -c by their order")
             .takes_value(true)
             .required(false)
             .multiple(true)
@@ -180,7 +722,7 @@ available masks are:
         Arg::with_name("wordlist")
             .short("w")
             .long("wordlist")
-            .help("filename containing newline (0xA) separated words. note: currently all wordlists loaded to memory")
+            .help("filename containing newline (0xA) separated words, - to read them from stdin, or a directory of such files loaded as a single wordlist sorted by filename. note: currently all wordlists loaded to memory")
             .takes_value(true)
             .required(false)
             .multiple(true)
@@ -194,6 +736,118 @@ available masks are:
             .help("output file to write the wordlist to, defaults to stdout")
             .takes_value(true)
             .required(false),
+    )
+    .arg(
+        Arg::with_name("anti-mask")
+            .long("anti-mask")
+            .help("generates the complement of a charsets mask - the words of its lengths from the chars of this alphabet (a custom charset, e.g. '?l?u?d?s') not matching the mask on any position")
+            .takes_value(true)
+            .conflicts_with_all(&["combine", "order-by-model", "positional-increment"])
+            .required(false),
+    )
+    .arg(
+        Arg::with_name("tee")
+            .long("tee")
+            .help("also writes the wordlist to stdout while writing it to --output-file. both get the same bytes, a closed stdout (e.g. piped to head) does not stop writing the file")
+            .takes_value(false)
+            .requires("output-file")
+            .required(false),
+    )
+    .arg(
+        Arg::with_name("output-dir")
+            .long("output-dir")
+            .help("writes the words of each mask to its own file in this directory - mask_000.txt, mask_001.txt etc.")
+            .takes_value(true)
+            .conflicts_with_all(&["output-file", "priority-file"])
+            .required(false),
+    )
+    .arg(
+        Arg::with_name("unordered-unique")
+            .long("unordered-unique")
+            .help("for wordlists used on multiple positions, emit only a single ordering of the same words (e.g. ?w1?w1 emits 'ab' but not 'ba')")
+            .takes_value(false)
+            .required(false),
+    )
+    .arg(
+        Arg::with_name("zip")
+            .long("zip")
+            .help("iterates the wordlists of the mask in lockstep by line instead of their product, stopping at the shortest (e.g. ?w1:?w2 pairs line 1 of both, then line 2 etc.). blank lines are kept so the lines stay paired. note: the lines are loaded into memory")
+            .takes_value(false)
+            .requires("wordlist")
+            .conflicts_with_all(&["combine", "order-by-model", "unordered-unique", "no-repeat", "no-repeat-any", "dedup-wordlists"])
+            .required(false),
+    )
+    .arg(
+        Arg::with_name("no-repeat")
+            .long("no-repeat")
+            .help("skip words with the same wordlist word on consecutive wordlist positions (e.g. ?w1?w1 skips 'alphaalpha'). --stats is then an upper bound")
+            .takes_value(false)
+            .required(false),
+    )
+    .arg(
+        Arg::with_name("no-repeat-any")
+            .long("no-repeat-any")
+            .help("skip words with the same wordlist word on any two wordlist positions. --stats is then an upper bound")
+            .takes_value(false)
+            .conflicts_with("no-repeat")
+            .required(false),
+    )
+    .arg(
+        Arg::with_name("min-distinct-words")
+            .long("min-distinct-words")
+            .help("skip words using less than this number of distinct words on their wordlist positions (e.g. 2 with ?w1?w1?w1 skips 'thethethe'). --stats is then an upper bound")
+            .takes_value(true)
+            .requires("wordlist")
+            .conflicts_with_all(&["combine", "order-by-model", "zip", "delta-from"])
+            .required(false),
+    )
+    .arg(
+        Arg::with_name("dedup-wordlists")
+            .long("dedup-wordlists")
+            .help("remove duplicate words from each wordlist, e.g. words repeated across files of a wordlist directory")
+            .takes_value(false)
+            .requires("wordlist")
+            .required(false),
+    )
+    .arg(
+        Arg::with_name("max-word-len")
+            .long("max-word-len")
+            .help("skips wordlist lines longer than this many bytes (e.g. malformed giant lines), reported with --verbose. by default lines longer than the max word size of 511 fail the generation")
+            .takes_value(true)
+            .requires("wordlist")
+            .required(false),
+    )
+    .arg(
+        Arg::with_name("recursive")
+            .long("recursive")
+            .help("for wordlist directories, also load the files of nested directories")
+            .takes_value(false)
+            .requires("wordlist")
+            .required(false),
+    )
+    .arg(
+        Arg::with_name("wordlist-column")
+            .long("wordlist-column")
+            .help("use only the N-th column (1-based) of each wordlist line, lines without this column are skipped")
+            .takes_value(true)
+            .required(false),
+    )
+    .arg(
+        Arg::with_name("wordlist-delim")
+            .long("wordlist-delim")
+            .help("single char column delimiter used with --wordlist-column (use '\\t' for tab), defaults to ','")
+            .takes_value(true)
+            .requires("wordlist-column")
+            .required(false),
+    )
+    .arg(
+        Arg::with_name("wordlist-format")
+            .long("wordlist-format")
+            .help("how the words of the wordlist files are delimited: newlines (lines), NUL bytes (nul) or netstrings like '5:hello,' (netstring). nul and netstring words may contain newlines, written as is to the output unless transformed (e.g. --encode hex). defaults to lines")
+            .takes_value(true)
+            .possible_values(&["lines", "nul", "netstring"])
+            .requires("wordlist")
+            .required(false),
     )).subcommand(SubCommand::with_name("entropy")
         .about(r#"
 Computes the estimated entropy of password or password file.
@@ -212,21 +866,107 @@ There are two types of keyspace size estimations:
             .takes_value(true)
             .multiple(true)
             .number_of_values(1)
-            .required(true),
+            .required_unless_one(&["mask-only", "word"]),
         ).arg(
-        Arg::with_name("password")
-            .help("password to estimate entropy for")
+        Arg::with_name("word")
+            .long("word")
+            .help("ad-hoc vocab word used alongside the smartlists (e.g. a company name), can be specified multiple times")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .required(false),
+        ).arg(
+        Arg::with_name("mask-only")
+            .long("mask-only")
+            .help("estimates only the charset mask entropy, without loading any smartlist")
+            .takes_value(false)
+            .required(false)
+            .conflicts_with_all(&["smartlist", "word", "mask_type"]),
+        ).arg(
+        Arg::with_name("password")
+            .help("password to estimate entropy for")
             .takes_value(true)
             .required_unless("passwords-file")
         ).arg(
         Arg::with_name("passwords-file")
             .short("p")
             .long("passwords-file")
-            .help("newline separated password file to estimate entropy for")
+            .help("newline separated (or NUL separated with -z) password file to estimate entropy for, or - to read the passwords from stdin. can be specified multiple times, rows are then prefixed with the source filename")
             .takes_value(true)
             .required(false)
+            .multiple(true)
+            .number_of_values(1)
             .conflicts_with("password"),
         ).arg(
+        Arg::with_name("null-delimited")
+            .short("z")
+            .long("null-delimited")
+            .help("passwords file is NUL separated instead of newline separated")
+            .takes_value(false)
+            .required(false)
+            .requires("passwords-file"),
+        ).arg(
+        Arg::with_name("codepoints")
+            .long("codepoints")
+            .help("treat each utf8 codepoint as a single char instead of each byte")
+            .takes_value(false)
+            .required(false),
+        ).arg(
+        Arg::with_name("no-charset-words")
+            .long("no-charset-words")
+            .help("do not match the builtin charsets as subwords, only the smartlist words. fails on passwords not fully covered by the smartlists")
+            .takes_value(false)
+            .required(false)
+            .conflicts_with("mask-only"),
+        ).arg(
+        Arg::with_name("exact-match")
+            .long("exact-match")
+            .help("report a password found whole in a smartlist as that single word, skipping the search for a cheaper split. faster on dumps of dictionary words but may overestimate")
+            .takes_value(false)
+            .required(false)
+            .conflicts_with("mask-only"),
+        ).arg(
+        Arg::with_name("verbose")
+            .short("v")
+            .long("verbose")
+            .help("include the source smartlist filename of each subword in masks (e.g. ?w1[names.txt])")
+            .takes_value(false)
+            .required(false)
+            .conflicts_with("mask-only"),
+        ).arg(
+        Arg::with_name("charset-freq")
+            .long("charset-freq")
+            .help("chars frequencies file (as created by the train subcommand) - a builtin charset char then costs -log2(p) by its frequency among its charset instead of log2(charset size)")
+            .takes_value(true)
+            .required(false),
+        ).arg(
+        Arg::with_name("english-freq")
+            .long("english-freq")
+            .help("like --charset-freq with the builtin english letters frequencies")
+            .takes_value(false)
+            .required(false)
+            .conflicts_with("charset-freq"),
+        ).arg(
+        Arg::with_name("show-keyspace")
+            .long("show-keyspace")
+            .help("also prints the number of passwords of the charset mask and the hybrid split (2^entropy)")
+            .takes_value(false)
+            .required(false)
+            .conflicts_with("passwords-file"),
+        ).arg(
+        Arg::with_name("per-char")
+            .long("per-char")
+            .help("also prints a table of each password char with its charset mask symbol and entropy")
+            .takes_value(false)
+            .required(false)
+            .conflicts_with("passwords-file"),
+        ).arg(
+        Arg::with_name("score")
+            .long("score")
+            .help("also prints a 0-100 strength score and label of the entropy: weak below 28 bits (0-32), ok below 60 bits (33-65) and strong above (66-100, 100 from 100 bits). password file rows then get score,label columns after the entropy")
+            .takes_value(false)
+            .required(false),
+        ).arg(
         Arg::with_name("summary")
             .short("s")
             .long("summary")
@@ -235,6 +975,13 @@ There are two types of keyspace size estimations:
             .required(false)
             .conflicts_with("password"),
         ).arg(
+        Arg::with_name("top-strong")
+            .long("top-strong")
+            .help("with --summary, also prints the N passwords with the highest entropy, from the strongest")
+            .takes_value(true)
+            .required(false)
+            .requires("summary"),
+        ).arg(
         Arg::with_name("mask_type")
             .short("t")
             .long("mask-type")
@@ -243,6 +990,27 @@ There are two types of keyspace size estimations:
             .required(false)
             .possible_values(&["hybrid", "charset"])
             .conflicts_with("password"),
+        ).arg(
+        Arg::with_name("smartlist-column")
+            .long("smartlist-column")
+            .help("use only the N-th column (1-based) of each smartlist line, lines without this column are skipped")
+            .takes_value(true)
+            .required(false),
+        ).arg(
+        Arg::with_name("smartlist-delim")
+            .long("smartlist-delim")
+            .help("single char column delimiter used with --smartlist-column (use '\\t' for tab), defaults to ','")
+            .takes_value(true)
+            .requires("smartlist-column")
+            .required(false),
+        ).arg(
+        Arg::with_name("smartlist-format")
+            .long("smartlist-format")
+            .help("how the words of the smartlist files are delimited: newlines (lines), NUL bytes (nul) or netstrings like '5:hello,' (netstring), see --wordlist-format of generate. defaults to lines")
+            .takes_value(true)
+            .possible_values(&["lines", "nul", "netstring"])
+            .requires("smartlist")
+            .required(false),
         )
     ).subcommand(SubCommand::with_name("create")
         .about("Create a new smartlist from input file(s)")
@@ -264,11 +1032,18 @@ There are two types of keyspace size estimations:
             .takes_value(true)
             .required(true)
         )
+        .arg(
+            Arg::with_name("base")
+            .long("base")
+            .help("existing smartlist to update - its words are merged with the newly trained words and re-ranked by frequency on the input files")
+            .takes_value(true)
+            .required(false)
+        )
         .arg(
         Arg::with_name("tokenizer")
             .short("t")
             .long("tokenizer")
-            .help("tokenizer to use, can be specified multiple times.\none of: bpe,unigram,wordpiece")
+            .help("tokenizer to use, can be specified multiple times. tokenizers run in the given order, each once.\none of: bpe,unigram,wordpiece")
             .takes_value(true)
             .possible_values(&["bpe", "unigram", "wordpiece"])
             .required(false)
@@ -280,7 +1055,7 @@ There are two types of keyspace size estimations:
             Arg::with_name("quiet")
             .short("q")
             .long("quiet")
-            .help("disables printing progress bar")
+            .help("disables printing the stages and progress bars")
             .takes_value(false)
             .required(false)
         )
@@ -314,8 +1089,212 @@ There are two types of keyspace size estimations:
             .takes_value(true)
             .required(false)
         )
+        .arg(
+            Arg::with_name("only_alpha")
+            .long("only-alpha")
+            .help("keeps only alphabetic words")
+            .takes_value(false)
+            .required(false)
+            .conflicts_with_all(&["only_alnum", "keep_matching"])
+        )
+        .arg(
+            Arg::with_name("only_alnum")
+            .long("only-alnum")
+            .help("keeps only alphanumeric words")
+            .takes_value(false)
+            .required(false)
+            .conflicts_with("keep_matching")
+        )
+        .arg(
+            Arg::with_name("keep_matching")
+            .long("keep-matching")
+            .help("keeps only words fully matching the given regex")
+            .takes_value(true)
+            .required(false)
+        )
+        .arg(
+            Arg::with_name("reject")
+            .long("reject")
+            .help("removes words matching the given regex anywhere, anchor it to match whole words (e.g. '^[[:punct:]]+$')")
+            .takes_value(true)
+            .required(false)
+        )
+        .arg(
+            Arg::with_name("casefold_dedup")
+            .long("casefold-dedup")
+            .help("treats words differing only by case as duplicates, keeping the most frequent casing")
+            .takes_value(false)
+            .required(false)
+        )
+        .arg(
+            Arg::with_name("sort_order")
+            .long("sort-order")
+            .help("output order of the smartlist words. the most frequent words are always the ones kept up to --vocab-max-size")
+            .takes_value(true)
+            .possible_values(&["frequency", "alpha", "length"])
+            .default_value("frequency")
+            .required(false)
+        )
+    ).subcommand(SubCommand::with_name("train")
+        .about("Train a character probability model from passwords file(s), used by generate --order-by-model")
+        .arg(
+        Arg::with_name("file")
+            .short("f")
+            .long("file")
+            .help("newline separated passwords filename, can be specified multiple times for multiple files")
+            .takes_value(true)
+            .required(true)
+            .multiple(true)
+            .number_of_values(1)
+        )
+        .arg(
+            Arg::with_name("model")
+            .short("o")
+            .long("model")
+            .help("output model filename")
+            .takes_value(true)
+            .required(true)
+        )
+    ).subcommand(SubCommand::with_name("wordlist-stats")
+        .about("Prints the number of words per length, min/max/avg length and charsets coverage of a wordlist")
+        .arg(
+        Arg::with_name("wordlist")
+            .short("w")
+            .long("wordlist")
+            .help("newline separated wordlist filename")
+            .takes_value(true)
+            .required(true)
+        )
+        .arg(
+            Arg::with_name("top")
+            .long("top")
+            .help("also print the N most common word lengths")
+            .takes_value(true)
+            .required(false)
+        )
+        .arg(
+            Arg::with_name("format")
+            .long("format")
+            .help("output format")
+            .takes_value(true)
+            .possible_values(&["text", "json"])
+            .default_value("text")
+            .required(false)
+        )
+    ).subcommand(SubCommand::with_name("sample")
+        .about("Prints random lines of a wordlist in a single pass, keeping only the sampled lines in memory")
+        .arg(
+        Arg::with_name("wordlist")
+            .short("w")
+            .long("wordlist")
+            .help("newline separated wordlist filename")
+            .takes_value(true)
+            .required(true)
+        )
+        .arg(
+            Arg::with_name("lines")
+            .short("n")
+            .long("lines")
+            .help("number of lines to sample, all the lines are printed when the wordlist has fewer")
+            .takes_value(true)
+            .required(true)
+        )
+        .arg(
+            Arg::with_name("seed")
+            .long("seed")
+            .help("random seed for a reproducible sample, defaults to a time based seed")
+            .takes_value(true)
+            .required(false)
+        )
+    ).subcommand(SubCommand::with_name("substrings")
+        .about("Prints every substring of a given length (a sliding window) of each wordlist word, e.g. all the 4-grams of 'password'")
+        .arg(
+        Arg::with_name("wordlist")
+            .short("w")
+            .long("wordlist")
+            .help("newline separated wordlist filename, or - to read it from stdin")
+            .takes_value(true)
+            .required(true)
+        )
+        .arg(
+            Arg::with_name("len")
+            .long("len")
+            .help("length in bytes of the substrings, shorter words are skipped")
+            .takes_value(true)
+            .required(true)
+        )
+        .arg(
+            Arg::with_name("unique")
+            .long("unique")
+            .help("print each substring once, keeping the printed substrings in memory")
+            .takes_value(false)
+            .required(false)
+        )
+    ).subcommand(SubCommand::with_name("charset")
+        .about("Prints the chars of a builtin charset or of a custom charset definition with their byte values")
+        .arg(
+        Arg::with_name("symbol")
+            .help("builtin charset symbol to print, e.g. ?s")
+            .takes_value(true)
+            .required_unless("custom-charset")
+        )
+        .arg(
+            Arg::with_name("custom-charset")
+            .short("c")
+            .long("custom-charset")
+            .help("custom charset definition to print expanded, as given to generate -c (e.g. '?dabc\\x00')")
+            .takes_value(true)
+            .conflicts_with("symbol")
+            .required(false)
+        )
+    ).subcommand(SubCommand::with_name("normalize")
+        .about("Canonicalizes a wordlist - trims whitespace and CRLF line endings off the words, drops blank lines and optionally dedups, filters and sorts them")
+        .arg(
+        Arg::with_name("wordlist")
+            .short("w")
+            .long("wordlist")
+            .help("newline separated wordlist filename, or - to read it from stdin")
+            .takes_value(true)
+            .required(true)
+        )
+        .arg(
+            Arg::with_name("output-file")
+            .short("o")
+            .long("output-file")
+            .help("output file to write the normalized wordlist to, may be the wordlist itself to normalize it in place. defaults to stdout")
+            .takes_value(true)
+            .required(false)
+        )
+        .arg(
+            Arg::with_name("dedup")
+            .long("dedup")
+            .help("keep only the first occurrence of each word")
+            .takes_value(false)
+            .required(false)
+        )
+        .arg(
+            Arg::with_name("sort")
+            .long("sort")
+            .help("sorts the words by length (shortest first, then by bytes) or lexicographically by bytes (lex). defaults to the input order")
+            .takes_value(true)
+            .possible_values(&["length", "lex"])
+            .required(false)
+        )
+        .arg(
+            Arg::with_name("min-len")
+            .long("min-len")
+            .help("skip words shorter than this number of bytes")
+            .takes_value(true)
+            .required(false)
+        )
+        .arg(
+            Arg::with_name("max-len")
+            .long("max-len")
+            .help("skip words longer than this number of bytes")
+            .takes_value(true)
+            .required(false)
+        )
     )
-    .get_matches_from(args)
 }
 
 /// helper for handling cast and optional values at same time, exiting on error
@@ -339,27 +1318,291 @@ macro_rules! optional_value_t_or_exit {
     };
 }
 
+/// returns the number of lines of the wordlist generated by the run of the manifest file
+/// `fname`, verifying the wordlist only grew since by appending lines
+fn delta_from_manifest(
+    fname: &str,
+    wordlists: &[&str],
+    wordlist_options: &WordlistOptions,
+) -> BoxResult<usize> {
+    let recorded = match read_manifest_wordlists(fname) {
+        Ok(recorded) => recorded,
+        Err(e) => bail!("cannot read manifest {}: {}", fname, e),
+    };
+    let recorded = match (&recorded[..], wordlists) {
+        ([recorded], [wordlist]) if !Path::new(wordlist).is_dir() => recorded,
+        _ => {
+            bail!("--delta-from requires a single wordlist file, both in the manifest and the run")
+        }
+    };
+    let format = wordlist_options.wordlist_format().name();
+    if recorded.format != format {
+        bail!(
+            "wordlist {} is read as {} but as {} by the run of manifest {}",
+            wordlists[0],
+            format,
+            recorded.format,
+            fname
+        );
+    }
+    let digest = records_digest(wordlists[0], wordlist_options, recorded.records)?;
+    if digest.as_ref() != Some(&recorded.sha256) {
+        bail!(
+            "wordlist {} changed other than appending lines since manifest {}",
+            wordlists[0],
+            fname
+        );
+    }
+    Ok(recorded.records as usize)
+}
+
+/// adds the resources given inline in the masks to the ones given by flags, by their index.
+/// an index given both ways must be the same resource
+fn merge_inline_resources(
+    given: &mut Vec<String>,
+    inline: BTreeMap<usize, String>,
+    symbol: &str,
+) -> BoxResult<()> {
+    for (idx, value) in inline {
+        match given.get(idx) {
+            Some(existing) if *existing == value => {}
+            Some(existing) => bail!(
+                "{}{} is given both as {:?} and inline as {:?}",
+                symbol,
+                idx + 1,
+                existing,
+                value
+            ),
+            None if idx == given.len() => given.push(value),
+            None => bail!(
+                "{}{}{{{}}} requires all the previous {}N to be given too",
+                symbol,
+                idx + 1,
+                value,
+                symbol
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// returns the custom charsets of both -c and --charset-file by their order on the command line
+fn custom_charsets_from_args(args: &ArgMatches) -> BoxResult<Vec<String>> {
+    let mut charsets: Vec<(usize, String)> = vec![];
+    if let (Some(values), Some(indices)) = (
+        args.values_of("custom-charset"),
+        args.indices_of("custom-charset"),
+    ) {
+        charsets.extend(indices.zip(values.map(|v| v.to_owned())));
+    }
+    if let (Some(values), Some(indices)) = (
+        args.values_of("charset-file"),
+        args.indices_of("charset-file"),
+    ) {
+        for (idx, fname) in indices.zip(values) {
+            match read_charset_file(fname) {
+                Ok(charset) => charsets.push((idx, charset)),
+                Err(e) => bail!("cannot read charset file {}: {}", fname, e),
+            }
+        }
+    }
+
+    if charsets.len() > 9 {
+        bail!(
+            "up to 9 custom charsets are supported, got {}",
+            charsets.len()
+        );
+    }
+    charsets.sort_by_key(|(idx, _)| *idx);
+    Ok(charsets.into_iter().map(|(_, charset)| charset).collect())
+}
+
+/// parses the column selection args of wordlist-like inputs
+fn column_selector_from_args(
+    args: &ArgMatches,
+    column_arg: &str,
+    delim_arg: &str,
+) -> BoxResult<Option<ColumnSelector>> {
+    let column = match optional_value_t_or_exit!(args, column_arg, usize) {
+        Some(column) => column,
+        None => return Ok(None),
+    };
+    if column == 0 {
+        bail!("--{} is 1-based and must be at least 1", column_arg);
+    }
+
+    let delimiter = match args.value_of(delim_arg).unwrap_or(",") {
+        "\\t" => b'\t',
+        delim if delim.len() == 1 => delim.as_bytes()[0],
+        delim => bail!("--{} must be a single char, got: {:?}", delim_arg, delim),
+    };
+    Ok(Some(ColumnSelector::new(column - 1, delimiter)))
+}
+
+/// parses the `WordlistFormat` of the `format_arg` option, lines if not given
+fn wordlist_format_from_args(args: &ArgMatches, format_arg: &str) -> WordlistFormat {
+    match args.value_of(format_arg) {
+        Some("nul") => WordlistFormat::Nul,
+        Some("netstring") => WordlistFormat::Netstring,
+        _ => WordlistFormat::Lines,
+    }
+}
+
 pub fn run(args: Option<Vec<&str>>) -> BoxResult<()> {
+    let config_fname = find_config_file(env::var_os(CONFIG_ENV_VAR), Path::new(""));
+    run_with_config_file(args, config_fname)
+}
+
+/// runs the command line `args` with the defaults of the `config_fname` config file
+fn run_with_config_file(args: Option<Vec<&str>>, config_fname: Option<PathBuf>) -> BoxResult<()> {
     // parse args
-    let arg_matches = parse_args(args);
+    let arg_matches = parse_args(args, || {
+        config_fname.map(GenerateConfig::from_file).transpose()
+    })?;
+    run_subcommand(&arg_matches, &mut stdout())
+}
 
+/// runs the subcommand of `arg_matches`. the results of the subcommands printing them to stdout
+/// are written to `out` instead
+fn run_subcommand(arg_matches: &ArgMatches, out: &mut (dyn Write + Send)) -> BoxResult<()> {
     match arg_matches.subcommand() {
-        ("generate", Some(matches)) => run_wordlist_generator(matches),
+        ("generate", Some(matches)) => run_wordlist_generator(matches, out),
         ("create", Some(matches)) => run_create_smartlist(matches),
-        ("entropy", Some(matches)) => run_entropy_estimator(matches),
+        ("entropy", Some(matches)) => run_entropy_estimator(matches, out),
+        ("train", Some(matches)) => run_train_model(matches),
+        ("wordlist-stats", Some(matches)) => run_wordlist_stats(matches),
+        ("sample", Some(matches)) => run_sample(matches),
+        ("substrings", Some(matches)) => run_substrings(matches, out),
+        ("charset", Some(matches)) => run_charset(matches, out),
+        ("normalize", Some(matches)) => run_normalize(matches),
         (_, None) => bail!("invalid command"),
         _ => unreachable!("oopsie, subcommand is required"),
     }
 }
 
-pub fn run_wordlist_generator(args: &ArgMatches) -> BoxResult<()> {
-    let masks = match args.value_of("mask") {
-        Some(mask) => vec![mask.to_owned()],
+/// fails when stdin (`-`) is given more than once to the input arguments `names`, as it can
+/// be read only once
+fn check_single_stdin_input(args: &ArgMatches, names: &[&str]) -> BoxResult<()> {
+    let stdin_names: Vec<&str> = names
+        .iter()
+        .flat_map(|&name| {
+            args.values_of(name)
+                .into_iter()
+                .flatten()
+                .filter(|value| is_stdin_input(value))
+                .map(move |_| name)
+        })
+        .collect();
+    if stdin_names.len() > 1 {
+        bail!(
+            "stdin (-) can be read only once but is given {} times (--{}), save the input to a file first",
+            stdin_names.len(),
+            stdin_names.join(", --")
+        );
+    }
+    Ok(())
+}
+
+pub fn run_wordlist_generator(args: &ArgMatches, stdout: &mut (dyn Write + Send)) -> BoxResult<()> {
+    // the --async-write threads write to `stdout` and end with the generation
+    thread::scope(|scope| generate_wordlist(args, stdout, scope))
+}
+
+fn generate_wordlist<'scope>(
+    args: &ArgMatches,
+    stdout: &'scope mut (dyn Write + Send),
+    scope: &'scope Scope<'scope, '_>,
+) -> BoxResult<()> {
+    check_single_stdin_input(
+        args,
+        &[
+            "wordlist",
+            "masks-file",
+            "charset-file",
+            "exclude-wordlist",
+            "priority-file",
+            "order-by-model",
+        ],
+    )?;
+    let deadline = match args.value_of("max-time") {
+        Some(max_time) => Some(Instant::now() + parse_duration(max_time)?),
+        None => None,
+    };
+    let combine = if args.is_present("combine") {
+        let total_minlen = optional_value_t_or_exit!(args, "total-min", usize).unwrap_or(1);
+        let total_maxlen = optional_value_t_or_exit!(args, "total-max", usize).unwrap();
+        Some((total_minlen, total_maxlen))
+    } else {
+        None
+    };
+
+    let mut custom_charsets = custom_charsets_from_args(args)?;
+    let mut wordlists: Vec<String> = args
+        .values_of("wordlist")
+        .map(|x| x.map(|w| w.to_owned()).collect())
+        .unwrap_or_default();
+
+    // wordlists and custom charsets given inline in the masks, e.g. ?w1{names.txt}
+    let mut inline_resources = InlineResources::default();
+    let masks = match args.values_of("mask") {
+        Some(masks) => masks
+            .map(|mask| {
+                let (mask, resources) = extract_inline_resources(mask)?;
+                for (idx, wordlist) in resources.wordlists {
+                    insert_inline(&mut inline_resources.wordlists, "?w", idx, wordlist)?;
+                }
+                for (idx, charset) in resources.custom_charsets {
+                    insert_inline(&mut inline_resources.custom_charsets, "?", idx, charset)?;
+                }
+                Ok(MaskEntry::new(mask))
+            })
+            .collect::<BoxResult<_>>()?,
+        // combine mode generates from the wordlist only
+        None if combine.is_some() => vec![],
         None => {
             let masks_fname = args.value_of("masks-file").unwrap();
-            let file = BufReader::new(File::open(masks_fname)?);
-            let masks: Result<Vec<_>, _> = file.lines().collect();
-            masks?
+            let entries = if args.is_present("hcmask") || is_hcmask_file(masks_fname) {
+                read_hcmask_file(masks_fname)?
+            } else {
+                read_masks_file(masks_fname)?
+            };
+            // the files referenced by a masks file are relative to it, keeping bundles portable
+            let base_dir = match args.value_of("base-dir") {
+                Some(dir) => PathBuf::from(dir),
+                None => masks_file_dir(masks_fname),
+            };
+            // the resources given inline on a line are of that line only, added to its own
+            // custom charsets or the command line ones
+            entries
+                .into_iter()
+                .map(|entry| {
+                    let entry = load_charset_files(entry, &base_dir)?;
+                    let (mask, resources) = extract_inline_resources(&entry.mask)?;
+                    let mut entry_charsets = entry.custom_charsets;
+                    if !resources.custom_charsets.is_empty() {
+                        let mut charsets =
+                            entry_charsets.unwrap_or_else(|| custom_charsets.clone());
+                        merge_inline_resources(&mut charsets, resources.custom_charsets, "?")?;
+                        entry_charsets = Some(charsets);
+                    }
+                    let mut entry_wordlists = None;
+                    if !resources.wordlists.is_empty() {
+                        let inline = resources
+                            .wordlists
+                            .into_iter()
+                            .map(|(idx, wordlist)| (idx, resolve_path(&base_dir, &wordlist)))
+                            .collect();
+                        let mut line_wordlists = wordlists.clone();
+                        merge_inline_resources(&mut line_wordlists, inline, "?w")?;
+                        entry_wordlists = Some(line_wordlists);
+                    }
+                    Ok(MaskEntry {
+                        mask,
+                        custom_charsets: entry_charsets,
+                        wordlists: entry_wordlists,
+                    })
+                })
+                .collect::<BoxResult<_>>()?
         }
     };
 
@@ -367,230 +1610,3788 @@ pub fn run_wordlist_generator(args: &ArgMatches) -> BoxResult<()> {
     let maxlen = optional_value_t_or_exit!(args, "max-length", usize);
     let outfile = args.value_of("output-file");
 
-    // create output file
-    let mut out: Box<dyn Write> = match outfile {
-        Some(fname) => match File::create(fname) {
-            Ok(fp) => Box::new(fp),
-            Err(e) => bail!("cannot open file {}: {}", fname, e),
-        },
-        None => Box::new(stdout()),
-    };
-
-    let custom_charsets: Vec<&str> = args
-        .values_of("custom-charset")
-        .map(|x| x.collect())
-        .unwrap_or_else(Vec::new);
-
-    let wordlists: Vec<&str> = args
-        .values_of("wordlist")
-        .map(|x| x.collect())
-        .unwrap_or_else(Vec::new);
+    let output_dir = args.value_of("output-dir");
+    let mut writers = OutputWriters::new(
+        args.value_of("checksum"),
+        args.is_present("no-trailing-separator"),
+    )
+    .async_write(args.is_present("async-write").then_some(scope));
+    let mut out = writers.open(outfile, args.is_present("tee"), output_dir, stdout)?;
 
-    for mask in masks {
-        // create output file
-        let word_generator =
-            get_word_generator(&mask, minlen, maxlen, &custom_charsets, &wordlists)?;
-        if args.is_present("stats") {
-            let combs = word_generator.combinations();
-            println!("{}", combs);
-            return Ok(());
+    merge_inline_resources(&mut custom_charsets, inline_resources.custom_charsets, "?")?;
+    let custom_charsets: Vec<&str> = custom_charsets.iter().map(|c| c.as_str()).collect();
+    if args.is_present("verbose") {
+        for line in charset_duplicates_report(&custom_charsets, &masks) {
+            eprintln!("{}", line);
         }
+    }
 
-        match word_generator.gen(&mut out) {
-            Ok(_) => {}
-            Err(e) => {
-                match e.kind() {
-                    // ignore broken pipe, (e.g. happens when using head)
-                    ErrorKind::BrokenPipe => return Ok(()),
-                    _ => bail!("error occurred writing to out: {}", e),
-                }
+    merge_inline_resources(&mut wordlists, inline_resources.wordlists, "?w")?;
+    let wordlists: Vec<&str> = wordlists.iter().map(|w| w.as_str()).collect();
+    // the wordlists of the run, of every masks file line too
+    let mut all_wordlists = wordlists.clone();
+    for entry_wordlists in masks.iter().filter_map(|entry| entry.wordlists.as_ref()) {
+        for wordlist in entry_wordlists {
+            if !all_wordlists.contains(&wordlist.as_str()) {
+                all_wordlists.push(wordlist);
             }
         }
     }
-    Ok(())
-}
 
-pub fn run_entropy_estimator(args: &ArgMatches) -> BoxResult<()> {
-    let smartlist_files: Vec<&str> = args.values_of("smartlist").map(|x| x.collect()).unwrap();
-    let est = EntropyEstimator::from_files(smartlist_files.as_ref())?;
-    let is_summary_only = args.is_present("summary");
-    let mask_type = args.value_of("mask_type").unwrap_or("hybrid");
-    let mut total_entropy = 0f64;
-    let mut pwd_count = 0usize;
-    let mut stdout = stdout();
+    let max_word_len = optional_value_t_or_exit!(args, "max-word-len", usize);
+    let wordlist_options = WordlistOptions::new()
+        .column(column_selector_from_args(
+            args,
+            "wordlist-column",
+            "wordlist-delim",
+        )?)
+        .recursive(args.is_present("recursive"))
+        .dedup(args.is_present("dedup-wordlists"))
+        .max_word_len(max_word_len)
+        .format(wordlist_format_from_args(args, "wordlist-format"));
+    // pipes, devices and stdin (e.g. a fifo, <(cmd) or -) stream their words once, only a single read
+    // of the wordlist is supported for them. a missing file fails on loading it instead
+    let streamed = all_wordlists.iter().find(|&fname| {
+        let path = Path::new(fname);
+        is_stdin_input(path) || (path.exists() && !path.is_dir() && !is_regular_file(path))
+    });
+    if let Some(fname) = streamed {
+        let reads_again = [
+            ("--manifest", args.is_present("manifest")),
+            ("--delta-from", args.is_present("delta-from")),
+            ("--number-lines", args.is_present("number-lines")),
+            // masks otherwise share the loaded wordlist
+            (
+                "--zip with multiple masks",
+                args.is_present("zip") && combine.is_none() && masks.len() > 1,
+            ),
+        ];
+        if let Some((feature, _)) = reads_again.iter().find(|(_, used)| *used) {
+            bail!(
+                "wordlist {} is not a regular file (e.g. a fifo) and can be read only once, {} requires reading it again",
+                fname,
+                feature
+            );
+        }
+    }
 
-    if let Some(pwd) = args.value_of("password") {
-        let entropy_result = est.estimate_password_entropy(pwd.as_bytes())?;
+    let delta_from = match args.value_of("delta-from") {
+        Some(fname) => Some(delta_from_manifest(
+            fname,
+            &all_wordlists,
+            &wordlist_options,
+        )?),
+        None => None,
+    };
+    let options = GeneratorOptions::new()
+        .delta_from(delta_from)
+        .wordlist_options(wordlist_options.clone())
+        // masks sharing a wordlist (e.g. of a masks file) load it once
+        .cache_wordlists(true)
+        .unordered_unique(args.is_present("unordered-unique"))
+        .zip(args.is_present("zip"))
+        .one_per_length(args.is_present("one-per-length"))
+        .max_per_length(optional_value_t_or_exit!(args, "max-per-length", usize))
+        .min_classes(optional_value_t_or_exit!(args, "min-classes", usize).unwrap_or(0))
+        .anti_mask(
+            args.value_of("anti-mask")
+                .map(|alphabet| alphabet.to_owned()),
+        )
+        .no_repeat(
+            match (
+                args.is_present("no-repeat"),
+                args.is_present("no-repeat-any"),
+            ) {
+                (true, _) => NoRepeat::Adjacent,
+                (_, true) => NoRepeat::Any,
+                _ => NoRepeat::Off,
+            },
+        )
+        .min_distinct_words(
+            optional_value_t_or_exit!(args, "min-distinct-words", usize).unwrap_or(0),
+        )
+        .positional_increment(args.is_present("positional-increment"))
+        .range(match (args.value_of("from"), args.value_of("to")) {
+            (Some(from), Some(to)) => Some((from.to_owned(), to.to_owned())),
+            _ => None,
+        });
+
+    if combine.is_some() && wordlists.len() != 1 {
+        bail!("--combine requires exactly one wordlist");
+    }
+
+    // generators are built lazily one at a time, either a single combine generator or one per mask
+    let generators_count = if combine.is_some() { 1 } else { masks.len() };
+    let model = match args.value_of("order-by-model") {
+        Some(fname) => Some(CharModel::from_file(fname)?),
+        None => None,
+    };
+    let build_generator = |idx: usize| match (combine, &model) {
+        (None, Some(model)) => model_generator(&masks[idx], &custom_charsets, model),
+        (None, None) => mask_generator(
+            &masks[idx],
+            &custom_charsets,
+            minlen,
+            maxlen,
+            &wordlists,
+            &options,
+        ),
+        (Some((total_minlen, total_maxlen)), _) => {
+            let word_generator: Box<dyn WordGenerator> = Box::new(CombineGenerator::new(
+                wordlists[0],
+                total_minlen,
+                total_maxlen,
+                &options,
+            )?);
+            Ok(word_generator)
+        }
+    };
+
+    let is_stats = args.is_present("stats");
+    let preview = optional_value_t_or_exit!(args, "preview", usize);
+    let step = match optional_value_t_or_exit!(args, "step", u64) {
+        Some(0) => bail!("--step must be at least 1"),
+        Some(step) => {
+            let offset = optional_value_t_or_exit!(args, "offset", u64).unwrap_or(0);
+            if offset >= step {
+                bail!("--offset must be less than --step {}, got {}", step, offset);
+            }
+            Some((step, offset))
+        }
+        None => None,
+    };
+    let mut total_combs = 0.to_biguint().unwrap();
+    // the words the masks would generate without --one-per-length, for --stats
+    let one_per_length = args.is_present("one-per-length");
+    let mut full_combs = 0.to_biguint().unwrap();
+    if is_stats && one_per_length {
+        for entry in masks.iter() {
+            let mask_charsets = entry_charsets(entry, &custom_charsets);
+            full_combs += mask_combinations(&entry.mask, minlen, maxlen, &mask_charsets, &[])?;
+        }
+    }
+    // the estimated run time of the masks of --estimate-time
+    let estimate_time = args.is_present("estimate-time");
+    let mut estimated_secs = 0f64;
+
+    let encoding = args.value_of("encode").map(|encoding| match encoding {
+        "hex" => Encoding::Hex,
+        "base64" => Encoding::Base64,
+        x => unreachable!("invalid encoding {}", x),
+    });
+    let jsonl = args.value_of("format") == Some("jsonl");
+    if jsonl && (encoding.is_some() || args.is_present("number-lines")) {
+        bail!("--format jsonl cannot be used with --encode or --number-lines, it includes the word index");
+    }
+    let case = args.value_of("case").map(|case| match case {
+        "upper" => Case::Upper,
+        "lower" => Case::Lower,
+        "capitalize" => Case::Capitalize,
+        "title" => Case::Title,
+        x => unreachable!("invalid case {}", x),
+    });
+    let annotation = args.is_present("annotate-mask").then(|| {
+        Annotation::new(
+            args.value_of("annotate-sep").unwrap_or("\t"),
+            args.is_present("annotate-suffix"),
+        )
+    });
+    let mut output_options = OutputOptions::new()
+        .case(case)
+        .prefix(args.value_of("prefix").unwrap_or("").as_bytes())
+        .suffix(args.value_of("suffix").unwrap_or("").as_bytes())
+        .encoding(encoding)
+        .jsonl(jsonl)
+        .annotation(annotation);
+    let max_lens = match combine {
+        Some((_, total_maxlen)) => vec![("--combine".to_owned(), Some(total_maxlen))],
+        None => masks
+            .iter()
+            .map(|entry| Ok((entry.mask.clone(), entry.mask.parse::<Mask>()?.max_len())))
+            .collect::<BoxResult<Vec<_>>>()?,
+    };
+    for (mask, max_len) in &max_lens {
+        if let Some(max_len) = max_len {
+            if max_len + output_options.added_word_len() >= MAX_WORD_SIZE {
+                bail!(
+                    "mask {} with --prefix and --suffix exceeds the max word size of {}",
+                    mask,
+                    MAX_WORD_SIZE - 1
+                );
+            }
+        }
+    }
+
+    // generators built upfront, then generated in order instead of being built lazily
+    let mut prebuilt = VecDeque::new();
+    if args.is_present("number-lines") && !is_stats {
+        // pad to the digits of the last index, requires counting all words upfront
+        let mut combs = 0.to_biguint().unwrap();
+        for idx in 0..generators_count {
+            let word_generator = build_generator(idx)?;
+            combs += word_generator.combinations();
+            prebuilt.push_back(word_generator);
+        }
+        let zero = 0.to_biguint().unwrap();
+        let last_index = if combs > zero { combs - 1u32 } else { zero };
+        let width = last_index.to_string().len();
+        let separator = args.value_of("number-sep").unwrap();
+        output_options = output_options.number_lines(Some(NumberLines::new(width, separator)));
+    }
+    // the output was closed (e.g. piped to head), the remaining masks are only counted
+    let mut pipe_closed = false;
+    let mut exclude = match (args.value_of("exclude-wordlist"), is_stats) {
+        (Some(fname), false) => read_words_set(fname)?,
+        _ => HashSet::new(),
+    };
+    if let (Some(fname), false) = (args.value_of("priority-file"), is_stats) {
+        let dedup = args.is_present("dedup-priority");
+        match write_priority_words(fname, &mut out, dedup) {
+            Ok(priority_words) => exclude.extend(priority_words),
+            // ignore broken pipe, (e.g. happens when using head)
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => pipe_closed = true,
+            Err(e) => bail!("error occurred writing priority file {}: {}", fname, e),
+        }
+    }
+    output_options = output_options.exclude(exclude);
+
+    let mut out = WordsWriter::new(out, output_options.clone()).deadline(deadline);
+    // words generated before the --max-time deadline by the --output-dir files
+    let mut mask_files_words = 0;
+    let mut deadline_reached = false;
+
+    if let Some(dir) = output_dir {
+        if let Err(e) = fs::create_dir_all(dir) {
+            bail!("cannot create output dir {}: {}", dir, e);
+        }
+    }
+    let mut output_files = vec![];
+
+    for idx in 0..generators_count {
+        let word_generator = match prebuilt.pop_front() {
+            Some(word_generator) => word_generator,
+            None => build_generator(idx)?,
+        };
+        if let Some(n) = preview {
+            print_preview(word_generator.as_ref(), n, &output_options, &mut stderr())?;
+            continue;
+        }
+        let mask_combs = match step {
+            Some((step, offset)) => {
+                let combs = word_generator.combinations();
+                if combs > BigUint::from(offset) && word_generator.nth_word(&0u32.into()).is_none()
+                {
+                    bail!(
+                        "--step cannot index the words of mask {}, it supports charsets and wordlists masks only",
+                        word_generator.mask()
+                    );
+                }
+                stepped_combinations(&combs, step, offset)
+            }
+            None => word_generator.combinations(),
+        };
+        if is_stats {
+            // masks generate at different rates (e.g. a wordlist mask vs a charsets mask)
+            if estimate_time {
+                let sample_duration = (SAMPLE_DURATION / generators_count.max(1) as u32)
+                    .max(MIN_MASK_SAMPLE_DURATION);
+                let words_per_sec = sample_words_per_sec(word_generator.as_ref(), sample_duration)?;
+                estimated_secs += biguint_to_f64(&mask_combs) / words_per_sec as f64;
+            }
+            total_combs += mask_combs;
+            continue;
+        }
+        total_combs += mask_combs;
+        // the remaining masks are only counted for the manifest
+        if deadline_reached || pipe_closed {
+            continue;
+        }
+
+        // with --output-dir each mask is written to its own file
+        let mut mask_out: WordsWriter;
+        let out = match output_dir {
+            Some(dir) => {
+                let (fname, fp) = writers.open_mask_file(dir, idx)?;
+                mask_out = WordsWriter::new(fp, output_options.clone()).deadline(deadline);
+                output_files.push(fname);
+                &mut mask_out
+            }
+            None => &mut out,
+        };
+
+        match write_mask_words(word_generator.as_ref(), step, out)? {
+            MaskWrite::Done => {}
+            MaskWrite::PipeClosed => pipe_closed = true,
+            MaskWrite::DeadlineReached {
+                pipe_closed: closed,
+            } => {
+                deadline_reached = true;
+                pipe_closed = closed;
+            }
+        }
+        if output_dir.is_some() {
+            mask_files_words += out.words_written();
+        }
+    }
+    // words generated before the --max-time deadline, of all masks
+    let words_written = mask_files_words + out.words_written();
+    if let Some(max_word_len) = max_word_len.filter(|_| args.is_present("verbose")) {
+        for line in skipped_words_report(&options, max_word_len) {
+            eprintln!("{}", line);
+        }
+    }
+    if preview.is_some() {
+        return Ok(());
+    }
+    if deadline_reached {
+        eprintln!(
+            "stopped at the --max-time deadline after {} words",
+            words_written
+        );
+    }
+
+    if !is_stats && !pipe_closed {
+        match out.flush() {
+            // ignore broken pipe, (e.g. happens when using head)
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => pipe_closed = true,
+            flushed => flushed?,
+        }
+    }
+    // the checksums of a closed output are of partial output
+    if !is_stats && !pipe_closed {
+        for line in writers.checksum_lines() {
+            eprintln!("{}", line);
+        }
+    }
+
+    // stdout is reserved for generated words only, all other output goes to stderr
+    if is_stats && one_per_length {
+        eprintln!(
+            "would generate {}, showing {} samples",
+            full_combs, total_combs
+        );
+    } else if is_stats && !args.is_present("stats-file") {
+        eprintln!("{}", total_combs);
+    }
+    if let Some(fname) = args.value_of("stats-file") {
+        // the number of words a full run writes, not of the --one-per-length samples
+        let stats_combs = if one_per_length {
+            &full_combs
+        } else {
+            &total_combs
+        };
+        if let Err(e) = fs::write(fname, format!("{}\n", stats_combs)) {
+            bail!("cannot write stats file {}: {}", fname, e);
+        }
+    }
+    if is_stats && estimate_time {
+        eprintln!("{}", format_time_estimate(&total_combs, estimated_secs));
+    }
+
+    if let Some(fname) = args.value_of("manifest") {
+        let manifest = Manifest {
+            masks: masks.iter().map(|entry| entry.mask.clone()).collect(),
+            custom_charsets: custom_charsets.iter().map(|&c| c.to_owned()).collect(),
+            wordlists: all_wordlists.iter().map(|&w| w.to_owned()).collect(),
+            wordlist_options,
+            minlen,
+            maxlen,
+            combine,
+            combinations: total_combs,
+            options: manifest_options(args),
+            output_files,
+            stopped_after: (deadline_reached || pipe_closed).then_some(words_written),
+        };
+        if let Err(e) = manifest.to_file(fname) {
+            bail!("error writing manifest {}: {}", fname, e);
+        }
+    }
+    if deadline_reached && args.is_present("fail-on-deadline") {
+        bail!("the --max-time deadline was reached before generating all words");
+    }
+    Ok(())
+}
+
+/// the writers of the generated words - the output file, stdout or both with --tee, or a file
+/// per mask with --output-dir. each is checksummed with --checksum, and its last newline is
+/// trimmed with --no-trailing-separator
+struct OutputWriters<'scope, 'env> {
+    checksum_kind: Option<String>,
+    no_trailing_separator: bool,
+    /// the scope of the --async-write threads writing the outputs
+    async_write: Option<&'scope Scope<'scope, 'env>>,
+    /// the checksums of the opened outputs by their name
+    checksums: Vec<(String, Rc<RefCell<Checksum>>)>,
+}
+
+impl<'scope, 'env> OutputWriters<'scope, 'env> {
+    fn new(checksum_kind: Option<&str>, no_trailing_separator: bool) -> Self {
+        OutputWriters {
+            checksum_kind: checksum_kind.map(|kind| kind.to_owned()),
+            no_trailing_separator,
+            async_write: None,
+            checksums: vec![],
+        }
+    }
+
+    /// writes every output from a thread of `scope`
+    fn async_write(mut self, scope: Option<&'scope Scope<'scope, 'env>>) -> Self {
+        self.async_write = scope;
+        self
+    }
+
+    /// the output `fp` as is, or written from a separate thread with --async-write
+    fn raw(&self, fp: Box<dyn Write + Send + 'scope>) -> Box<dyn Write + 'scope> {
+        match self.async_write {
+            Some(scope) => Box::new(ThreadedWriter::new(scope, fp, ASYNC_WRITE_CHUNKS)),
+            None => fp,
+        }
+    }
+
+    /// wraps the output `fp` named `name` with its checksum and trailing newline trimming
+    fn wrap(&mut self, fp: Box<dyn Write + Send + 'scope>, name: &str) -> Box<dyn Write + 'scope> {
+        let fp = self.raw(fp);
+        // the checksums hash the final bytes written, after all the transforms
+        let checksum = match self.checksum_kind.as_deref() {
+            Some("sha256") => Some(Checksum::sha256()),
+            Some("xxhash") => Some(Checksum::xxhash64()),
+            _ => None,
+        };
+        let fp: Box<dyn Write + 'scope> = match checksum {
+            Some(checksum) => {
+                let checksum = Rc::new(RefCell::new(checksum));
+                self.checksums.push((name.to_owned(), checksum.clone()));
+                Box::new(ChecksumWriter::new(fp, checksum))
+            }
+            None => fp,
+        };
+        // the newline of the last word is held back before the checksum, it hashes the
+        // written bytes
+        if self.no_trailing_separator {
+            Box::new(NoTrailingNewlineWriter::new(fp))
+        } else {
+            fp
+        }
+    }
+
+    /// opens the output of the run - `outfile` (and `stdout` too with `tee`) or `stdout`.
+    /// with an `output_dir` nothing is written to the returned `stdout`
+    fn open(
+        &mut self,
+        outfile: Option<&str>,
+        tee: bool,
+        output_dir: Option<&str>,
+        stdout: &'scope mut (dyn Write + Send),
+    ) -> BoxResult<Box<dyn Write + 'scope>> {
+        let out: Box<dyn Write + 'scope> = match outfile {
+            Some(fname) => match File::create(fname) {
+                Ok(fp) if tee => self.wrap(Box::new(TeeWriter::new(fp, stdout)), fname),
+                Ok(fp) => self.wrap(Box::new(fp), fname),
+                Err(e) => bail!("cannot open file {}: {}", fname, e),
+            },
+            None if output_dir.is_some() => self.raw(Box::new(stdout)),
+            None => self.wrap(Box::new(stdout), "-"),
+        };
+        Ok(out)
+    }
+
+    /// creates the --output-dir file of the mask of index `idx` in `dir`, returning its name
+    fn open_mask_file(
+        &mut self,
+        dir: &str,
+        idx: usize,
+    ) -> BoxResult<(String, Box<dyn Write + 'scope>)> {
+        let fname = Path::new(dir).join(format!("mask_{:03}.txt", idx));
+        let fp = match File::create(&fname) {
+            Ok(fp) => fp,
+            Err(e) => bail!("cannot open file {}: {}", fname.display(), e),
+        };
+        let fname = fname.to_string_lossy().into_owned();
+        let fp = self.wrap(Box::new(fp), &fname);
+        Ok((fname, fp))
+    }
+
+    /// returns the checksum of every output, formatted like sha256sum
+    fn checksum_lines(&self) -> Vec<String> {
+        self.checksums
+            .iter()
+            .map(|(name, checksum)| {
+                let checksum = checksum.borrow();
+                format!("{}: {}  {}", checksum.name(), checksum.hex_digest(), name)
+            })
+            .collect()
+    }
+}
+
+/// how writing the words of a mask ended
+#[derive(Debug, PartialEq)]
+enum MaskWrite {
+    /// all the words were written
+    Done,
+    /// the output was closed (e.g. piped to head), the remaining words are not written
+    PipeClosed,
+    /// the deadline of the output was reached, the words written before it are flushed
+    /// unless the output was closed too
+    DeadlineReached { pipe_closed: bool },
+}
+
+/// writes the words of `word_generator` to `out` - or only every `step` word from `offset`
+/// with a `step` - and flushes them, so write errors are of this mask
+fn write_mask_words(
+    word_generator: &dyn WordGenerator,
+    step: Option<(u64, u64)>,
+    out: &mut WordsWriter,
+) -> BoxResult<MaskWrite> {
+    // written with the words by --annotate-mask
+    out.set_mask(word_generator.mask());
+    let generated = match step {
+        Some((step, offset)) => gen_stepped(word_generator, step, offset, out),
+        None => word_generator.gen(out),
+    };
+    match generated.and_then(|_| out.flush()) {
+        Ok(_) => Ok(MaskWrite::Done),
+        // ignore broken pipe, (e.g. happens when using head)
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(MaskWrite::PipeClosed),
+        // the words written so far are kept, flushing words buffered by the output
+        Err(e) if e.kind() == ErrorKind::TimedOut && out.has_deadline() => match out.flush() {
+            Ok(_) => Ok(MaskWrite::DeadlineReached { pipe_closed: false }),
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => {
+                Ok(MaskWrite::DeadlineReached { pipe_closed: true })
+            }
+            Err(e) => Err(e.into()),
+        },
+        Err(e) => bail!("error occurred writing to out: {}", e),
+    }
+}
+
+/// the options changing the generated words, recorded by --manifest to reproduce a run. the
+/// masks, custom charsets, wordlists and lengths are recorded on their own
+const MANIFEST_OPTIONS: &[&str] = &[
+    "hcmask",
+    "base-dir",
+    "order-by-model",
+    "delta-from",
+    "positional-increment",
+    "one-per-length",
+    "min-classes",
+    "max-per-length",
+    "from",
+    "to",
+    "step",
+    "offset",
+    "prefix",
+    "suffix",
+    "encode",
+    "case",
+    "format",
+    "priority-file",
+    "dedup-priority",
+    "exclude-wordlist",
+    "number-lines",
+    "number-sep",
+    "annotate-mask",
+    "annotate-sep",
+    "annotate-suffix",
+    "no-trailing-separator",
+    "anti-mask",
+    "unordered-unique",
+    "zip",
+    "no-repeat",
+    "no-repeat-any",
+    "min-distinct-words",
+    "dedup-wordlists",
+    "max-word-len",
+    "recursive",
+    "wordlist-column",
+    "wordlist-delim",
+    "wordlist-format",
+];
+
+/// returns the `MANIFEST_OPTIONS` given to the run by their long name, flags as `true`
+fn manifest_options(args: &ArgMatches) -> Map<String, Value> {
+    MANIFEST_OPTIONS
+        .iter()
+        // --number-sep has a default value, used only with --number-lines
+        .filter(|&&name| name != "number-sep" || args.is_present("number-lines"))
+        .filter(|&name| args.is_present(name))
+        .map(|&name| {
+            let value = match args.value_of(name) {
+                Some(value) => Value::from(value),
+                None => Value::Bool(true),
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+/// parses a duration of a number and a unit - s, m, h or d (e.g. `30s`, `2h`)
+fn parse_duration(s: &str) -> BoxResult<Duration> {
+    let unit_secs = match s.chars().last() {
+        Some('s') => 1,
+        Some('m') => 60,
+        Some('h') => 60 * 60,
+        Some('d') => 24 * 60 * 60,
+        _ => bail!(
+            "invalid duration {}, expected a number and a unit of s, m, h or d",
+            s
+        ),
+    };
+    match s[..s.len() - 1]
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit_secs))
+    {
+        Some(secs) => Ok(Duration::from_secs(secs)),
+        None => bail!(
+            "invalid duration {}, expected a number and a unit of s, m, h or d",
+            s
+        ),
+    }
+}
+
+/// builds the generator of a single mask entry. per mask custom charsets and wordlists override
+/// the global ones
+fn mask_generator<'a>(
+    entry: &'a MaskEntry,
+    custom_charsets: &[&'a str],
+    minlen: Option<usize>,
+    maxlen: Option<usize>,
+    wordlists: &[&'a str],
+    options: &GeneratorOptions,
+) -> BoxResult<Box<dyn WordGenerator + 'a>> {
+    get_word_generator_with_options(
+        &entry.mask,
+        minlen,
+        maxlen,
+        &entry_charsets(entry, custom_charsets),
+        &entry_wordlists(entry, wordlists),
+        options,
+    )
+}
+
+/// builds the generator of a charsets mask ordered by `model`
+fn model_generator<'a>(
+    entry: &'a MaskEntry,
+    custom_charsets: &[&'a str],
+    model: &CharModel,
+) -> BoxResult<Box<dyn WordGenerator + 'a>> {
+    let mask_charsets = entry_charsets(entry, custom_charsets);
+    let mask: Mask = entry.mask.parse()?;
+    mask.validate(mask_charsets.len(), 0)?;
+    Ok(Box::new(ModelGenerator::new(
+        mask.into_ops(),
+        &mask_charsets,
+        model,
+    )?))
+}
+
+/// returns a message for each wordlist loaded with `options` with words skipped by
+/// --max-word-len
+fn skipped_words_report(options: &GeneratorOptions, max_word_len: usize) -> Vec<String> {
+    options
+        .loaded_wordlists()
+        .into_iter()
+        .filter(|(_, wordlist)| wordlist.skipped_words() > 0)
+        .map(|(fname, wordlist)| {
+            format!(
+                "skipped {} lines of wordlist {} longer than {} bytes",
+                wordlist.skipped_words(),
+                fname,
+                max_word_len
+            )
+        })
+        .collect()
+}
+
+/// returns a message for each custom charset with duplicate chars, global ones and the ones of
+/// masks file entries
+fn charset_duplicates_report(custom_charsets: &[&str], masks: &[MaskEntry]) -> Vec<String> {
+    let report = |charsets: &[&str], mask: Option<&str>| {
+        charsets
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, charset)| {
+                let duplicates = custom_charset_duplicates(charset.as_bytes());
+                if duplicates == 0 {
+                    return None;
+                }
+                let of_mask = mask.map_or(String::new(), |mask| format!(" of mask {}", mask));
+                Some(format!(
+                    "charset ?{}{} had {} duplicate characters removed",
+                    idx + 1,
+                    of_mask,
+                    duplicates
+                ))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut lines = report(custom_charsets, None);
+    for entry in masks {
+        if let Some(charsets) = &entry.custom_charsets {
+            let charsets: Vec<&str> = charsets.iter().map(|c| c.as_str()).collect();
+            lines.extend(report(&charsets, Some(&entry.mask)));
+        }
+    }
+    lines
+}
+
+/// returns the custom charsets of a masks file entry, or the command line ones if it has none
+fn entry_charsets<'a>(entry: &'a MaskEntry, custom_charsets: &[&'a str]) -> Vec<&'a str> {
+    match &entry.custom_charsets {
+        Some(charsets) => charsets.iter().map(|c| c.as_str()).collect(),
+        None => custom_charsets.to_vec(),
+    }
+}
+
+/// returns the wordlists of a masks file entry, or the command line ones if it has none
+fn entry_wordlists<'a>(entry: &'a MaskEntry, wordlists: &[&'a str]) -> Vec<&'a str> {
+    match &entry.wordlists {
+        Some(entry_wordlists) => entry_wordlists.iter().map(|w| w.as_str()).collect(),
+        None => wordlists.to_vec(),
+    }
+}
+
+/// reads the lines of a file into a set
+fn read_words_set(fname: &str) -> BoxResult<HashSet<Vec<u8>>> {
+    let file = match open_input(fname) {
+        Ok(file) => file,
+        Err(e) => bail!("cannot open file {}: {}", fname, e),
+    };
+    Ok(RawFileReader::new(file).collect::<Result<_, _>>()?)
+}
+
+/// writes the lines of a priority file as is, returning them if `dedup` is set
+fn write_priority_words(
+    fname: &str,
+    out: &mut dyn Write,
+    dedup: bool,
+) -> Result<HashSet<Vec<u8>>, std::io::Error> {
+    let mut priority_words = HashSet::new();
+    let mut writer = BufWriter::new(out);
+    for word in RawFileReader::new(open_input(fname)?) {
+        let word = word?;
+        writer.write_all(&word)?;
+        writer.write_all(b"\n")?;
+        if dedup {
+            priority_words.insert(word);
+        }
+    }
+    writer.flush()?;
+    Ok(priority_words)
+}
+
+/// duration of the generation samples used by --estimate-time, split between the masks
+const SAMPLE_DURATION: Duration = Duration::from_millis(300);
+/// the shortest generation sample of a mask, so a sample of many masks is still measurable
+const MIN_MASK_SAMPLE_DURATION: Duration = Duration::from_millis(5);
+
+/// measures the generation throughput of `word_generator` by generating for `duration`
+fn sample_words_per_sec(word_generator: &dyn WordGenerator, duration: Duration) -> BoxResult<u64> {
+    let start = Instant::now();
+    let mut sample = SampleWriter::new(start + duration);
+    {
+        let mut out = WordsWriter::new(Box::new(&mut sample), OutputOptions::new());
+        match word_generator.gen(&mut out).and_then(|_| out.flush()) {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::TimedOut => {}
+            Err(e) => bail!("error occurred sampling generation speed: {}", e),
+        }
+    }
+    let elapsed = start.elapsed().as_secs_f64().max(f64::EPSILON);
+    Ok(((sample.words() as f64 / elapsed) as u64).max(1))
+}
+
+/// returns the number of words of indices `offset`, `offset + step`, `offset + 2 * step` etc.
+/// out of `combs` words
+fn stepped_combinations(combs: &BigUint, step: u64, offset: u64) -> BigUint {
+    if combs <= &BigUint::from(offset) {
+        return BigUint::from(0u32);
+    }
+    (combs - offset - 1u32) / step + 1u32
+}
+
+/// generates the words of indices `offset`, `offset + step`, `offset + 2 * step` etc. of a
+/// generator computing words by index
+fn gen_stepped(
+    word_generator: &dyn WordGenerator,
+    step: u64,
+    offset: u64,
+    out: &mut WordsWriter,
+) -> Result<(), std::io::Error> {
+    let combs = word_generator.combinations();
+    let mut index = BigUint::from(offset);
+    while index < combs {
+        let mut word = word_generator
+            .nth_word(&index)
+            .expect("generator must compute words by index");
+        let word_len = word.len();
+        word.push(b'\n');
+        out.write_words(&word, word_len)?;
+        index += step;
+    }
+    Ok(())
+}
+
+/// writes the first and last `n` words of `word_generator` with its number of words to `err`,
+/// e.g. stderr. the words are written with `output_options` except line numbers, and the last
+/// words are written only if the generator computes words by index
+fn print_preview(
+    word_generator: &dyn WordGenerator,
+    n: usize,
+    output_options: &OutputOptions,
+    err: &mut dyn Write,
+) -> BoxResult<()> {
+    let combs = word_generator.combinations();
+    let mut head = HeadWriter::new(n);
+    let generated = {
+        let mut out = WordsWriter::new(Box::new(&mut head), OutputOptions::new());
+        word_generator.gen(&mut out).and_then(|_| out.flush())
+    };
+    match generated {
+        Ok(_) => {}
+        Err(_) if head.is_full() => {}
+        Err(e) => bail!("error occurred generating the preview: {}", e),
+    }
+    let first = head.into_lines();
+
+    // the last words not already printed as first words
+    let tail_start = BigUint::from(first.len()).max(combs.clone().max(BigUint::from(n)) - n);
+    let mut last = Some(vec![]);
+    let mut index = tail_start.clone();
+    while index < combs {
+        last = last.and_then(|mut words| {
+            words.push(word_generator.nth_word(&index)?);
+            Some(words)
+        });
+        index += 1u32;
+    }
+
+    let mask = match word_generator.mask() {
+        "" => "words".to_owned(),
+        mask => format!("mask {}", mask),
+    };
+    let total = match word_generator.is_exact_count() {
+        true => combs.to_string(),
+        false => format!("up to {}", combs),
+    };
+    match &last {
+        Some(last) => writeln!(
+            err,
+            "{} - first {} / last {} of {}:",
+            mask,
+            first.len(),
+            last.len(),
+            total
+        )?,
+        None => writeln!(
+            err,
+            "{} - first {} of {} (the last words cannot be computed):",
+            mask,
+            first.len(),
+            total
+        )?,
+    }
+    // the preview indices are not the words indices
+    let output_options = output_options.clone().number_lines(None);
+    let write_words = |err: &mut dyn Write, words: &[Vec<u8>]| -> Result<(), Error> {
+        let mut out = WordsWriter::new(Box::new(err), output_options.clone());
+        out.set_mask(word_generator.mask());
+        for word in words {
+            let mut line = Vec::with_capacity(word.len() + 1);
+            line.extend_from_slice(word);
+            line.push(b'\n');
+            out.write_words(&line, word.len())?;
+        }
+        out.flush()
+    };
+    write_words(err, &first)?;
+    if let Some(last) = last {
+        if tail_start > BigUint::from(first.len()) {
+            writeln!(err, "...")?;
+        }
+        write_words(err, &last)?;
+    }
+    Ok(())
+}
+
+/// formats the estimated time to generate `combs` words, e.g. "~2.50 hours at ~1000 words/sec"
+fn format_time_estimate(combs: &BigUint, secs: f64) -> String {
+    let words_per_sec = match secs > 0f64 {
+        true => biguint_to_f64(combs) / secs,
+        false => 0f64,
+    };
+    format!(
+        "~{:.2} hours at ~{:.0} words/sec",
+        secs / 3600f64,
+        words_per_sec
+    )
+}
+
+/// converts a large number to a float, infinity if too large
+fn biguint_to_f64(num: &BigUint) -> f64 {
+    num.to_string().parse::<f64>().unwrap_or(f64::INFINITY)
+}
+
+pub fn run_entropy_estimator(args: &ArgMatches, out: &mut dyn Write) -> BoxResult<()> {
+    check_single_stdin_input(args, &["passwords-file", "smartlist", "charset-freq"])?;
+    let char_model = if args.is_present("english-freq") {
+        Some(CharModel::english())
+    } else if let Some(fname) = args.value_of("charset-freq") {
+        match CharModel::from_file(fname) {
+            Ok(model) => Some(model),
+            Err(e) => bail!("cannot read charset frequencies file {}: {}", fname, e),
+        }
+    } else {
+        None
+    };
+    let codepoints = args.is_present("codepoints");
+
+    // no vocab is needed for mask only entropy
+    let est = if args.is_present("mask-only") {
+        None
+    } else {
+        let smartlist_files: Vec<&str> = args
+            .values_of("smartlist")
+            .map(|x| x.collect())
+            .unwrap_or_default();
+        let inline_words: Vec<&str> = args
+            .values_of("word")
+            .map(|x| x.collect())
+            .unwrap_or_default();
+        let wordlist_options = WordlistOptions::new()
+            .column(column_selector_from_args(
+                args,
+                "smartlist-column",
+                "smartlist-delim",
+            )?)
+            .format(wordlist_format_from_args(args, "smartlist-format"));
+        Some(
+            EntropyEstimator::from_files_with_options(smartlist_files.as_ref(), &wordlist_options)?
+                .inline_words(&inline_words)
+                .verbose(args.is_present("verbose"))
+                .charset_words(!args.is_present("no-charset-words"))
+                .codepoints(codepoints)
+                .char_model(char_model.clone())
+                .exact_match(args.is_present("exact-match")),
+        )
+    };
+    let is_summary_only = args.is_present("summary");
+    let mask_entropy = |pwd: &[u8]| match (&char_model, codepoints) {
+        (Some(model), _) => password_mask_entropy_with_model(pwd, model, codepoints),
+        (None, true) => password_mask_entropy_codepoints(pwd),
+        (None, false) => password_mask_entropy(pwd),
+    };
+    let mask_type = args.value_of("mask_type").unwrap_or("hybrid");
+    // the strongest passwords rows, earlier passwords first on equal entropy
+    let mut top_strong = optional_value_t_or_exit!(args, "top-strong", usize)
+        .map(TopN::<(OrderedFloat<f64>, Reverse<usize>, String)>::new);
+    let mut total_entropy = 0f64;
+    let mut pwd_count = 0usize;
+
+    let show_keyspace = args.is_present("show-keyspace");
+    let is_score = args.is_present("score");
+    let mut total_score = 0u64;
+    if let Some(pwd) = args.value_of("password") {
+        let per_char = args.is_present("per-char").then(|| {
+            password_mask_entropy_per_char(pwd.as_bytes(), char_model.as_ref(), codepoints)
+        });
+        let est = match &est {
+            Some(est) => est,
+            None => {
+                let (mask_entropy, charset_mask) = mask_entropy(pwd.as_bytes());
+                writeln!(
+                    out,
+                    "charset-mask: {}\ncharset-mask-entropy: {:.2}",
+                    charset_mask, mask_entropy
+                )?;
+                if is_score {
+                    let (score, label) = strength_score(mask_entropy);
+                    writeln!(out, "charset-mask-score: {} ({})", score, label)?;
+                }
+                if show_keyspace {
+                    writeln!(
+                        out,
+                        "charset-mask-keyspace: {}",
+                        charset_mask_keyspace(&charset_mask)
+                    )?;
+                }
+                if let Some(per_char) = &per_char {
+                    write_per_char_entropy(out, per_char)?;
+                    writeln!(out)?;
+                }
+                return Ok(());
+            }
+        };
+        let entropy_result = est.estimate_password_entropy(pwd.as_bytes())?;
+        let (hybrid_keyspace, charset_keyspace) = if show_keyspace {
+            (
+                format!(
+                    "\nhybrid-keyspace: {}",
+                    est.subword_keyspace(pwd.as_bytes())?
+                ),
+                format!(
+                    "\ncharset-mask-keyspace: {}",
+                    charset_mask_keyspace(&entropy_result.charset_mask)
+                ),
+            )
+        } else {
+            (String::new(), String::new())
+        };
+        let score_line = |name: &str, entropy: f64| {
+            if is_score {
+                let (score, label) = strength_score(entropy);
+                format!("\n{}-score: {} ({})", name, score, label)
+            } else {
+                String::new()
+            }
+        };
         let text = format!(
             "hybrid-min-split: {:?}
 hybrid-mask: {}
-hybrid-min-entropy: {:.2}
+hybrid-min-entropy: {:.2}{}{}
 --
 charset-mask: {}
-charset-mask-entropy: {:.2}
-            ",
+charset-mask-entropy: {:.2}{}{}",
             entropy_result.subword_entropy_min_split,
             entropy_result.min_subword_mask,
             entropy_result.subword_entropy,
+            score_line("hybrid", entropy_result.subword_entropy),
+            hybrid_keyspace,
             entropy_result.charset_mask,
             entropy_result.mask_entropy,
+            score_line("charset-mask", entropy_result.mask_entropy),
+            charset_keyspace,
+        );
+        let written = write!(out, "{}", text).and_then(|_| match &per_char {
+            Some(per_char) => {
+                writeln!(out)?;
+                write_per_char_entropy(out, per_char)
+            }
+            None => Ok(()),
+        });
+        if let Err(e) = written.and_then(|_| write!(out, "\n            ")) {
+            match e.kind() {
+                // ignore broken pipe, (e.g. happens when using head)
+                ErrorKind::BrokenPipe => return Ok(()),
+                _ => bail!("error occurred writing to out: {}", e),
+            }
+        }
+    } else if let Some(pwd_files) = args.values_of("passwords-file") {
+        let pwd_files: Vec<&str> = pwd_files.collect();
+        let is_multi_files = pwd_files.len() > 1;
+        let delimiter = if args.is_present("null-delimited") {
+            b'\0'
+        } else {
+            b'\n'
+        };
+
+        for pwd_file in pwd_files {
+            let file = open_input(pwd_file)?;
+            let reader = RawFileReader::with_delimiter(file, delimiter);
+            let mut file_entropy = 0f64;
+            let mut file_pwd_count = 0usize;
+
+            for pwd in reader.into_iter() {
+                let pwd = pwd?;
+                let (pwd_entropy, pwd_mask) = match &est {
+                    Some(est) => {
+                        let entropy_result = est.estimate_password_entropy(&pwd)?;
+                        match mask_type {
+                            "hybrid" => (
+                                entropy_result.subword_entropy,
+                                entropy_result.min_subword_mask,
+                            ),
+                            "charset" => (entropy_result.mask_entropy, entropy_result.charset_mask),
+                            _ => unreachable!("invalid entropy type"),
+                        }
+                    }
+                    None => mask_entropy(&pwd),
+                };
+                let (pwd_score, pwd_label) = strength_score(pwd_entropy);
+                let row = || {
+                    let source = if is_multi_files {
+                        format!("{},", pwd_file)
+                    } else {
+                        String::new()
+                    };
+                    let score = if is_score {
+                        format!("{},{},", pwd_score, pwd_label)
+                    } else {
+                        String::new()
+                    };
+                    format!(
+                        "{}{:.2},{}{},{}",
+                        source,
+                        pwd_entropy,
+                        score,
+                        pwd_mask,
+                        // keep one output line per password even if it contains newlines
+                        String::from_utf8_lossy(&pwd).replace('\n', "\\n")
+                    )
+                };
+                if !is_summary_only {
+                    if let Err(e) = writeln!(out, "{}", row()) {
+                        match e.kind() {
+                            // ignore broken pipe, (e.g. happens when using head)
+                            ErrorKind::BrokenPipe => return Ok(()),
+                            _ => bail!("error occurred writing to out: {}", e),
+                        }
+                    }
+                } else {
+                    file_entropy += pwd_entropy;
+                    total_score += pwd_score as u64;
+                    if let Some(top_strong) = &mut top_strong {
+                        let key = (
+                            OrderedFloat(pwd_entropy),
+                            Reverse(pwd_count + file_pwd_count),
+                        );
+                        // rows are formatted only for the passwords kept
+                        if top_strong.would_keep(|smallest| key > (smallest.0, smallest.1)) {
+                            top_strong.push((key.0, key.1, row()));
+                        }
+                    }
+                }
+                file_pwd_count += 1;
+            }
+
+            if is_summary_only && is_multi_files {
+                writeln!(
+                    out,
+                    "{} avg entropy: {}",
+                    pwd_file,
+                    file_entropy / file_pwd_count as f64
+                )?;
+            }
+            total_entropy += file_entropy;
+            pwd_count += file_pwd_count;
+        }
+
+        if is_summary_only {
+            writeln!(out, "avg entropy: {}", total_entropy / pwd_count as f64)?;
+            if is_score {
+                writeln!(
+                    out,
+                    "avg score: {:.2}",
+                    total_score as f64 / pwd_count as f64
+                )?;
+            }
+            if let Some(top_strong) = top_strong {
+                let rows = top_strong.into_sorted_vec();
+                writeln!(out, "top {} strongest passwords:", rows.len())?;
+                for (_, _, row) in rows {
+                    writeln!(out, "{}", row)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// writes the chars of a password with their entropy and mask symbol, returned by
+/// `password_mask_entropy_per_char`, below a `--` separator line
+fn write_per_char_entropy(
+    out: &mut dyn Write,
+    per_char: &[(String, f64, char)],
+) -> Result<(), Error> {
+    write!(out, "--")?;
+    for (ch, entropy, symbol) in per_char {
+        write!(out, "\n{}: {:.2} (?{})", ch, entropy, symbol)?;
+    }
+    Ok(())
+}
+
+pub fn run_train_model(args: &ArgMatches) -> BoxResult<()> {
+    let outfile = args.value_of("model").unwrap();
+    check_single_stdin_input(args, &["file"])?;
+    let infiles: Vec<&str> = args.values_of("file").map(|x| x.collect()).unwrap();
+
+    let mut model = CharModel::new();
+    for fname in infiles {
+        match open_input(fname) {
+            Ok(fp) => model.train(fp)?,
+            Err(e) => bail!("cannot open file {}: {}", fname, e),
+        }
+    }
+    model.to_file(outfile)
+}
+
+pub fn run_wordlist_stats(args: &ArgMatches) -> BoxResult<()> {
+    let fname = args.value_of("wordlist").unwrap();
+    let top = optional_value_t_or_exit!(args, "top", usize);
+    let wordlist = match Wordlist::from_file(fname) {
+        Ok(wordlist) => wordlist,
+        Err(e) => bail!("cannot read wordlist {}: {}", fname, e),
+    };
+    let stats = wordlist.stats();
+
+    let text = match args.value_of("format").unwrap() {
+        "json" => format_wordlist_stats_json(&stats, top)?,
+        _ => format_wordlist_stats(&stats, top),
+    };
+    if let Err(e) = write!(stdout(), "{}", text) {
+        match e.kind() {
+            // ignore broken pipe, (e.g. happens when using head)
+            ErrorKind::BrokenPipe => return Ok(()),
+            _ => bail!("error occurred writing to out: {}", e),
+        }
+    }
+    Ok(())
+}
+
+pub fn run_sample(args: &ArgMatches) -> BoxResult<()> {
+    let fname = args.value_of("wordlist").unwrap();
+    let lines = optional_value_t_or_exit!(args, "lines", usize).unwrap();
+    let seed = match optional_value_t_or_exit!(args, "seed", u64) {
+        Some(seed) => seed,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+    };
+    let sample = match open_input(fname).and_then(|fp| reservoir_sample(fp, lines, seed)) {
+        Ok(sample) => sample,
+        Err(e) => bail!("cannot read wordlist {}: {}", fname, e),
+    };
+
+    let mut out = BufWriter::new(stdout());
+    let written = sample
+        .iter()
+        .try_for_each(|line| out.write_all(line).and_then(|_| out.write_all(b"\n")))
+        .and_then(|_| out.flush());
+    if let Err(e) = written {
+        match e.kind() {
+            // ignore broken pipe, (e.g. happens when using head)
+            ErrorKind::BrokenPipe => return Ok(()),
+            _ => bail!("error occurred writing to out: {}", e),
+        }
+    }
+    Ok(())
+}
+
+pub fn run_substrings(args: &ArgMatches, out: &mut dyn Write) -> BoxResult<()> {
+    let fname = args.value_of("wordlist").unwrap();
+    let len = optional_value_t_or_exit!(args, "len", usize).unwrap();
+    if len == 0 {
+        bail!("substrings length must be at least 1");
+    }
+    let fp = match open_input(fname) {
+        Ok(fp) => fp,
+        Err(e) => bail!("cannot read wordlist {}: {}", fname, e),
+    };
+
+    let mut out = BufWriter::new(out);
+    let written =
+        write_substrings(fp, len, args.is_present("unique"), &mut out).and_then(|_| out.flush());
+    if let Err(e) = written {
+        match e.kind() {
+            // ignore broken pipe, (e.g. happens when using head)
+            ErrorKind::BrokenPipe => return Ok(()),
+            _ => bail!("error occurred generating substrings of {}: {}", fname, e),
+        }
+    }
+    Ok(())
+}
+
+pub fn run_normalize(args: &ArgMatches) -> BoxResult<()> {
+    let fname = args.value_of("wordlist").unwrap();
+    let options = NormalizeOptions::new()
+        .dedup(args.is_present("dedup"))
+        .sort(match args.value_of("sort") {
+            Some("length") => SortOrder::Length,
+            Some("lex") => SortOrder::Lex,
+            _ => SortOrder::Input,
+        })
+        .min_len(optional_value_t_or_exit!(args, "min-len", usize))
+        .max_len(optional_value_t_or_exit!(args, "max-len", usize));
+    let fp = match open_input(fname) {
+        Ok(fp) => fp,
+        Err(e) => bail!("cannot read wordlist {}: {}", fname, e),
+    };
+    // all the words are read before the output is created, so it may be the wordlist itself
+    let words = normalize_words(fp, &options)?;
+
+    let outfile = args.value_of("output-file");
+    // a wordlist normalized in place is replaced only once all its words are written to a temp
+    // file next to it, so a failed write (e.g. a full disk) keeps the wordlist intact
+    let tmpfile = outfile
+        .filter(
+            |outfile| match (fs::canonicalize(fname), fs::canonicalize(outfile)) {
+                (Ok(input), Ok(output)) => input == output,
+                _ => false,
+            },
+        )
+        .map(|outfile| {
+            let path = Path::new(outfile);
+            let name = path.file_name().unwrap().to_string_lossy();
+            path.with_file_name(format!(".{}.cracken-{}.tmp", name, std::process::id()))
+        });
+    let mut out: Box<dyn Write> = match outfile {
+        Some(outfile) => {
+            let path = tmpfile.as_deref().unwrap_or(Path::new(outfile));
+            match File::create(path) {
+                Ok(fp) => Box::new(BufWriter::new(fp)),
+                Err(e) => bail!("cannot open file {}: {}", path.display(), e),
+            }
+        }
+        None => Box::new(BufWriter::new(stdout())),
+    };
+    let written = words
+        .iter()
+        .try_for_each(|word| {
+            out.write_all(word)?;
+            out.write_all(b"\n")
+        })
+        .and_then(|_| out.flush());
+    drop(out);
+    let written = match (&tmpfile, outfile) {
+        (Some(tmpfile), Some(outfile)) => written
+            .and_then(|_| fs::set_permissions(tmpfile, fs::metadata(outfile)?.permissions()))
+            .and_then(|_| fs::rename(tmpfile, outfile))
+            .inspect_err(|_| {
+                let _ = fs::remove_file(tmpfile);
+            }),
+        _ => written,
+    };
+    if let Err(e) = written {
+        match e.kind() {
+            // ignore broken pipe, (e.g. happens when using head)
+            ErrorKind::BrokenPipe => return Ok(()),
+            _ => bail!("error occurred writing the normalized wordlist: {}", e),
+        }
+    }
+    Ok(())
+}
+
+pub fn run_charset(args: &ArgMatches, out: &mut dyn Write) -> BoxResult<()> {
+    let (name, charset) = match (args.value_of("symbol"), args.value_of("custom-charset")) {
+        (Some(symbol), _) => {
+            let symbol_chr = match symbol.strip_prefix('?').unwrap_or(symbol).as_bytes() {
+                [chr] if find_symbol(*chr as char).is_some() => *chr as char,
+                _ => bail!("unknown builtin charset symbol: {:?}", symbol),
+            };
+            (format!("?{}", symbol_chr), Charset::from_symbol(symbol_chr))
+        }
+        (None, Some(spec)) => {
+            let chars = expand_custom_charset(spec.as_bytes());
+            if chars.is_empty() {
+                bail!("custom charset cannot be empty");
+            }
+            (format!("-c {}", spec), Charset::from_chars(&chars))
+        }
+        _ => unreachable!("a charset symbol or a custom charset is required"),
+    };
+
+    if let Err(e) = write!(out, "{}", format_charset(&name, &charset)) {
+        match e.kind() {
+            // ignore broken pipe, (e.g. happens when using head)
+            ErrorKind::BrokenPipe => return Ok(()),
+            _ => bail!("error occurred writing to out: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// formats the chars of a charset - a summary line, the chars in generation order and a line
+/// per char with its hex and decimal byte values
+fn format_charset(name: &str, charset: &Charset) -> String {
+    let chars = charset.chars_sorted();
+    let (min, max) = (chars[0], chars[chars.len() - 1]);
+    let mut text = format!(
+        "{}: {} chars, min 0x{:02x} '{}', max 0x{:02x} '{}'\n",
+        name,
+        chars.len(),
+        min,
+        escape_byte(min),
+        max,
+        escape_byte(max)
+    );
+    text.extend(chars.iter().map(|&ch| escape_byte(ch)));
+    text.push('\n');
+    for ch in chars {
+        text.push_str(&format!("0x{:02x} {:>3} '{}'\n", ch, ch, escape_byte(ch)));
+    }
+    text
+}
+
+fn format_wordlist_stats(stats: &WordlistStats, top: Option<usize>) -> String {
+    let mut text = format!(
+        "words: {}\nmin-len: {}\nmax-len: {}\navg-len: {:.2}\n--\nlen,count\n",
+        stats.words, stats.min_len, stats.max_len, stats.avg_len
+    );
+    for (len, count) in stats.len_counts.iter() {
+        text += &format!("{},{}\n", len, count);
+    }
+    if let Some(top) = top {
+        text += "--\ntop-lens:\n";
+        for (len, count) in stats.top_lens(top) {
+            text += &format!("{},{}\n", len, count);
+        }
+    }
+    text += "--\ncharset,words\n";
+    for (symbol, count) in stats.charset_words.iter() {
+        text += &format!("?{},{}\n", symbol, count);
+    }
+    text
+}
+
+fn format_wordlist_stats_json(stats: &WordlistStats, top: Option<usize>) -> BoxResult<String> {
+    let lens = |len_counts: &[(usize, usize)]| {
+        len_counts
+            .iter()
+            .map(|(len, count)| json!({"len": len, "count": count}))
+            .collect::<Vec<_>>()
+    };
+    let charsets: serde_json::Map<_, _> = stats
+        .charset_words
+        .iter()
+        .map(|(symbol, count)| (format!("?{}", symbol), json!(count)))
+        .collect();
+    let mut value = json!({
+        "words": stats.words,
+        "min_len": stats.min_len,
+        "max_len": stats.max_len,
+        "avg_len": stats.avg_len,
+        "len_counts": lens(&stats.len_counts),
+        "charset_words": charsets,
+    });
+    if let Some(top) = top {
+        value["top_lens"] = json!(lens(&stats.top_lens(top)));
+    }
+    Ok(serde_json::to_string_pretty(&value)? + "\n")
+}
+
+pub fn run_create_smartlist(args: &ArgMatches) -> BoxResult<()> {
+    let outfile = args.value_of("smartlist").unwrap();
+    let infiles: Vec<&str> = args.values_of("file").map(|x| x.collect()).unwrap();
+    if infiles.iter().any(is_stdin_input) {
+        bail!("create cannot read its input files from stdin as they are read twice, save the input to a file first");
+    }
+    let vocab_max_size =
+        optional_value_t_or_exit!(args, "vocab_max_size", u32).unwrap_or(DEFAULT_VOCAB_SIZE);
+    let min_frequency = optional_value_t_or_exit!(args, "min_frequency", u32).unwrap_or(0);
+    let print_progress = !args.is_present("quiet");
+    let numbers_max_size = optional_value_t_or_exit!(args, "numbers_max_size", u32);
+    let min_word_len = optional_value_t_or_exit!(args, "min_word_len", u32).unwrap_or(1);
+
+    let tokenizers = args
+        .values_of("tokenizer")
+        .map(|x| x.collect())
+        .unwrap_or_else(|| vec!["unigram"])
+        .into_iter()
+        .map(|x| match x {
+            "bpe" => SmartlistTokenizer::BPE,
+            "unigram" => SmartlistTokenizer::Unigram,
+            "wordpiece" => SmartlistTokenizer::WordPiece,
+            _ => unreachable!("invalid tokenizer {}", x),
+        });
+
+    let sort_order = match args.value_of("sort_order").unwrap() {
+        "frequency" => SmartlistSortOrder::Frequency,
+        "alpha" => SmartlistSortOrder::Alpha,
+        "length" => SmartlistSortOrder::Length,
+        x => unreachable!("invalid sort order {}", x),
+    };
+
+    let keep_matching = if args.is_present("only_alpha") {
+        Some("[[:alpha:]]+")
+    } else if args.is_present("only_alnum") {
+        Some("[[:alnum:]]+")
+    } else {
+        args.value_of("keep_matching")
+    };
+    let keep_matching = match keep_matching {
+        Some(re) => match Regex::new(re) {
+            Ok(re) => Some(re),
+            Err(e) => bail!(format!("invalid --keep-matching regex: {}", e)),
+        },
+        None => None,
+    };
+    let reject = match args.value_of("reject").map(Regex::new) {
+        Some(Ok(re)) => Some(re),
+        Some(Err(e)) => bail!(format!("invalid --reject regex: {}", e)),
+        None => None,
+    };
+
+    let base_vocab = match args.value_of("base") {
+        Some(fname) => read_vocab_file(fname)?,
+        None => vec![],
+    };
+
+    let mut writer = BufWriter::new(File::create(outfile)?);
+    let vocab = SmartlistBuilder::new()
+        .infiles(infiles)
+        .base_vocab(base_vocab)
+        .min_frequency(min_frequency)
+        .vocab_max_size(vocab_max_size)
+        .tokenizers(tokenizers.into_iter())
+        .print_progress(print_progress)
+        .numbers_max_size(numbers_max_size)
+        .min_word_len(min_word_len)
+        .keep_matching(keep_matching)
+        .reject(reject)
+        .casefold_dedup(args.is_present("casefold_dedup"))
+        .sort_order(sort_order)
+        .build()?;
+
+    // write to file
+    for word in vocab.iter() {
+        writer.write_all(word.as_bytes())?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{ErrorKind, Write};
+    use std::time::{Duration, Instant};
+    use std::{env, fs};
+
+    use num_bigint::ToBigUint;
+
+    use crate::charsets::Charset;
+    use crate::config::GenerateConfig;
+    use crate::generators::{
+        get_word_generator, get_word_generator_with_options, GeneratorOptions,
+    };
+    use crate::masks_file::MaskEntry;
+    use crate::output::{Annotation, Case, Encoding, NumberLines, OutputOptions, WordsWriter};
+    use crate::wordlists::{Wordlist, WordlistOptions};
+    use crate::{built_info, runner, test_util, BoxResult};
+
+    use super::{
+        charset_duplicates_report, format_charset, format_wordlist_stats,
+        format_wordlist_stats_json, parse_args, parse_duration, print_preview, run_subcommand,
+        skipped_words_report, write_mask_words, MaskWrite, OutputWriters,
+    };
+
+    /// runs cracken with `args` without a config file, so the tests do not depend on the
+    /// environment or the working directory
+    fn run(args: Option<Vec<&str>>) -> BoxResult<()> {
+        runner::run_with_config_file(args, None)
+    }
+
+    /// runs the cracken subcommand of `args` and returns what it prints to stdout
+    fn run_stdout(args: Vec<&str>) -> String {
+        let arg_matches = parse_args(Some(args), || Ok(None)).unwrap();
+        let mut out = vec![];
+        run_subcommand(&arg_matches, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    /// runs cracken with `args` writing its output into a temp file and returns the output
+    fn run_to_bytes(name: &str, args: Vec<&str>) -> Vec<u8> {
+        let fname = env::temp_dir().join(format!("cracken-test-{}.txt", name));
+        let fname = fname.to_str().unwrap();
+        let mut run_args = vec!["cracken", "-o", fname];
+        run_args.extend(args);
+        run(Some(run_args)).unwrap();
+
+        let output = fs::read(fname).unwrap();
+        fs::remove_file(fname).unwrap();
+        output
+    }
+
+    fn run_to_string(name: &str, args: Vec<&str>) -> String {
+        String::from_utf8(run_to_bytes(name, args)).unwrap()
+    }
+
+    #[test]
+    fn test_run_generate_smoke() {
+        for args in [vec!["cracken", "generate", "?d"], vec!["cracken", "?d"]] {
+            assert!(run(Some(args)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_run_entropy_smoke() {
+        let vocab_fname = test_util::wordlist_fname("vocab.txt");
+        let args = Some(vec![
+            "cracken",
+            "entropy",
+            "--smartlist",
+            vocab_fname.to_str().unwrap(),
+            "helloworld123!",
+        ]);
+        assert!(run(args).is_ok());
+    }
+
+    #[test]
+    fn test_run_dev_null() {
+        let args = Some(vec!["cracken", "-o", "/dev/null", "?d"]);
+        assert!(run(args).is_ok());
+    }
+
+    #[test]
+    fn test_run_custom_charset() {
+        let args = Some(vec!["cracken", "-c=abcdef0123456789", "?1"]);
+        assert!(run(args).is_ok());
+    }
+
+    #[test]
+    fn test_run_stats() {
+        let args = Some(vec!["cracken", "-s", "?d?s?u?l?a?b"]);
+        assert!(run(args).is_ok());
+    }
+
+    #[test]
+    fn test_run_stats_file() {
+        let stats_fname = env::temp_dir().join("cracken-test-stats-file-count.txt");
+        let masks_fname = test_util::wordlist_fname("masks.hcmask");
+        let stats_file = stats_fname.to_str().unwrap();
+        for (args, expected) in [
+            (vec!["-s", "?d?d"], "100\n"),
+            (vec!["-s", "-m", "1", "?d?d"], "110\n"),
+            // summed over all the masks of a masks file
+            (vec!["-s", "-i", masks_fname.to_str().unwrap()], "104\n"),
+            // the full count rather than the number of samples
+            (vec!["-s", "--one-per-length", "-m", "1", "?d?d"], "110\n"),
+        ] {
+            let args = [vec!["--stats-file", stats_file], args].concat();
+            assert_eq!(run_to_string("stats-file", args), "");
+            assert_eq!(fs::read_to_string(&stats_fname).unwrap(), expected);
+        }
+        fs::remove_file(&stats_fname).unwrap();
+
+        let args = Some(vec![
+            "cracken",
+            "--stats-file",
+            "/tmp/this/dir/not/exisT",
+            "-s",
+            "?d",
+        ]);
+        assert!(run(args).is_err());
+    }
+
+    /// every mask of a masks file is counted with its own custom charsets and lengths, the
+    /// same words as generated
+    #[test]
+    fn test_run_stats_masks_file() {
+        let stats_fname = env::temp_dir().join("cracken-test-stats-masks-file-count.txt");
+        let masks_fname = test_util::wordlist_fname("masks-charsets.txt");
+        let gen_args = vec!["-c", "z", "-m", "1", "-i", masks_fname.to_str().unwrap()];
+        let args = [
+            vec!["--stats-file", stats_fname.to_str().unwrap(), "-s"],
+            gen_args.clone(),
+        ]
+        .concat();
+        assert_eq!(run_to_string("stats-masks-file", args), "");
+        // z and z0-z9 of ?1?d, a, b and aX to bY of ?1?2 with the charsets of its line
+        assert_eq!(fs::read_to_string(&stats_fname).unwrap(), "17\n");
+        fs::remove_file(&stats_fname).unwrap();
+
+        let output = run_to_string("stats-masks-file-gen", gen_args);
+        assert_eq!(output.lines().count(), 17);
+    }
+
+    #[test]
+    fn test_format_charset() {
+        assert_eq!(
+            format_charset("-c ab?d", &Charset::from_custom("ab?d"))
+                .lines()
+                .take(3)
+                .collect::<Vec<_>>(),
+            vec![
+                "-c ab?d: 12 chars, min 0x30 '0', max 0x62 'b'",
+                "0123456789ab",
+                "0x30  48 '0'"
+            ]
+        );
+        let text = format_charset("?s", &Charset::from_symbol('s'));
+        assert!(text.starts_with("?s: 33 chars, min 0x20 ' ', max 0x7e '~'\n !\"#"));
+        assert_eq!(text.lines().count(), 2 + 33);
+        let text = format_charset("?b", &Charset::from_symbol('b'));
+        assert!(text.contains("\n0x00   0 '\\x00'\n"));
+        assert!(text.ends_with("\n0xff 255 '\\xff'\n"));
+    }
+
+    #[test]
+    fn test_run_normalize() {
+        // normalizes a wordlist in place
+        let fname = env::temp_dir().join("cracken-test-normalize.txt");
+        fs::write(&fname, "gamma\r\n  beta\n\nalpha\r\nbeta \n\r\n").unwrap();
+        let fname_str = fname.to_str().unwrap();
+        let args = vec![
+            "cracken",
+            "normalize",
+            "-w",
+            fname_str,
+            "-o",
+            fname_str,
+            "--dedup",
+            "--sort",
+            "lex",
+        ];
+        assert!(run(Some(args)).is_ok());
+        assert_eq!(fs::read_to_string(&fname).unwrap(), "alpha\nbeta\ngamma\n");
+
+        let args = vec![
+            "cracken",
+            "normalize",
+            "-w",
+            fname_str,
+            "-o",
+            fname_str,
+            "--sort",
+            "length",
+            "--max-len",
+            "4",
+        ];
+        assert!(run(Some(args)).is_ok());
+        assert_eq!(fs::read_to_string(&fname).unwrap(), "beta\n");
+        fs::remove_file(&fname).unwrap();
+
+        // the wordlist is replaced by a temp file in its directory, keeping its permissions
+        let dirname = env::temp_dir().join("cracken-test-normalize-dir");
+        fs::create_dir_all(&dirname).unwrap();
+        let fname = dirname.join("words.txt");
+        fs::write(&fname, "b\na\n").unwrap();
+        let mut permissions = fs::metadata(&fname).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&fname, permissions.clone()).unwrap();
+        let fname_str = fname.to_str().unwrap();
+        let args = vec![
+            "cracken",
+            "normalize",
+            "-w",
+            fname_str,
+            "-o",
+            fname_str,
+            "--sort",
+            "lex",
+        ];
+        assert!(run(Some(args)).is_ok());
+        assert_eq!(fs::read_to_string(&fname).unwrap(), "a\nb\n");
+        assert_eq!(fs::metadata(&fname).unwrap().permissions(), permissions);
+        assert_eq!(fs::read_dir(&dirname).unwrap().count(), 1);
+        fs::remove_dir_all(&dirname).unwrap();
+
+        let args = vec!["cracken", "normalize", "-w", "no-such-wordlist.txt"];
+        assert!(run(Some(args)).is_err());
+    }
+
+    #[test]
+    fn test_run_substrings() {
+        let fname = test_util::wordlist_fname("wordlist-3words.txt");
+        let output = run_stdout(vec![
+            "cracken",
+            "substrings",
+            "-w",
+            fname.to_str().unwrap(),
+            "--len",
+            "4",
+        ]);
+        assert_eq!(output, "alph\nlpha\nbeta\ngamm\namma\n");
+        let output = run_stdout(vec![
+            "cracken",
+            "substrings",
+            "-w",
+            fname.to_str().unwrap(),
+            "--len",
+            "2",
+            "--unique",
+        ]);
+        assert_eq!(output, "al\nlp\nph\nha\nbe\net\nta\nga\nam\nmm\nma\n");
+        // no word is long enough
+        let output = run_stdout(vec![
+            "cracken",
+            "substrings",
+            "-w",
+            fname.to_str().unwrap(),
+            "--len",
+            "9",
+            "--unique",
+        ]);
+        assert_eq!(output, "");
+
+        for args in [
+            vec![
+                "cracken",
+                "substrings",
+                "-w",
+                fname.to_str().unwrap(),
+                "--len",
+                "0",
+            ],
+            vec![
+                "cracken",
+                "substrings",
+                "-w",
+                "no-such-wordlist.txt",
+                "--len",
+                "4",
+            ],
+        ] {
+            assert!(run(Some(args)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_run_charset() {
+        let output = run_stdout(vec!["cracken", "charset", "?s"]);
+        assert!(output.starts_with(
+            "?s: 33 chars, min 0x20 ' ', max 0x7e '~'\n !\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~\n0x20  32 ' '\n"
+        ));
+        assert!(output.ends_with("0x7e 126 '~'\n"));
+        assert_eq!(output.lines().count(), 2 + 33);
+
+        // the symbol may be given without the ?
+        assert_eq!(
+            run_stdout(vec!["cracken", "charset", "d"]),
+            run_stdout(vec!["cracken", "charset", "?d"])
+        );
+
+        let output = run_stdout(vec!["cracken", "charset", "-c", "?dab\\x00"]);
+        assert!(output.starts_with(
+            "-c ?dab\\x00: 13 chars, min 0x00 '\\x00', max 0x62 'b'\n\\x000123456789ab\n\
+            0x00   0 '\\x00'\n0x30  48 '0'\n"
+        ));
+        assert!(output.ends_with("0x61  97 'a'\n0x62  98 'b'\n"));
+
+        for args in [
+            vec!["cracken", "charset", "?x"],
+            vec!["cracken", "charset", "?dd"],
+            vec!["cracken", "charset", "-c", ""],
+        ] {
+            assert!(run(Some(args)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_run_perm_denied() {
+        let args = Some(vec!["cracken", "-o", "/tmp/this/dir/not/exisT", "?d"]);
+        assert!(run(args).is_err());
+    }
+
+    #[test]
+    fn test_run_bad_args() {
+        let args = Some(vec!["cracken", "-m", "2", "?d"]);
+        assert!(run(args).is_err());
+    }
+
+    #[test]
+    fn test_run_bad_args2() {
+        let args = Some(vec!["cracken", "?x"]);
+        assert!(run(args).is_err());
+    }
+
+    #[test]
+    fn test_run_bad_args3() {
+        let args = Some(vec!["cracken", "-x", "5", "?d"]);
+        assert!(run(args).is_err());
+    }
+
+    #[test]
+    fn test_run_wordlist_column() {
+        let fname = test_util::wordlist_fname("wordlist-columns.csv");
+        let output = run_to_string(
+            "wordlist-column",
+            vec![
+                "-w",
+                fname.to_str().unwrap(),
+                "--wordlist-column",
+                "3",
+                "--wordlist-delim",
+                ",",
+                "?w1?d",
+            ],
+        );
+        // the row without a third column is skipped, the header is kept as a word
+        let expected: String = ["secret", "hunter2", "letmein", "password"]
+            .iter()
+            .flat_map(|word| (0..10).map(move |d| format!("{}{}\n", word, d)))
+            .collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_run_wordlist_dir() {
+        let dirname = test_util::wordlist_fname("wordlist-dir");
+        let dirname = dirname.to_str().unwrap();
+        let output = run_to_string("wordlist-dir", vec!["-w", dirname, "?w1"]);
+        assert_eq!(output, "one\ntwo\ntwo\nthree\n");
+
+        let output = run_to_string(
+            "wordlist-dir-recursive",
+            vec!["-w", dirname, "--recursive", "--dedup-wordlists", "?w1"],
+        );
+        assert_eq!(output, "one\ntwo\nfour\nthree\n");
+    }
+
+    #[test]
+    fn test_run_encode() {
+        let output = run_to_string("encode-hex", vec!["--encode", "hex", "?b"]);
+        let expected: Vec<_> = (0..=255u8).map(|ch| format!("{:02x}\n", ch)).collect();
+        assert_eq!(output, expected.concat());
+
+        let output = run_to_string(
+            "encode-base64",
+            vec!["--encode", "base64", "--prefix", "a", "?d?d", "b"],
+        );
+        assert!(output.starts_with("YTAw\nYTAx\n"));
+        assert!(output.ends_with("YTk5\nYWI=\n"));
+    }
+
+    #[test]
+    fn test_run_transforms_newline_bytes() {
+        // words of any length containing a newline byte are transformed whole
+        let output = run_to_string(
+            "newline-bytes-hex",
+            vec!["--encode", "hex", "-m1", "-x2", "?b?b"],
+        );
+        assert_eq!(output.lines().count(), 256 + 256 * 256);
+        assert!(output.contains("\n09\n0a\n0b\n"));
+        assert!(output.contains("\n0a09\n0a0a\n0a0b\n"));
+
+        let output = run_to_bytes(
+            "newline-bytes-prefix",
+            vec!["--prefix", "X", "-m1", "-x2", "?b?b"],
+        );
+        assert_eq!(output.len(), 256 * 3 + 256 * 256 * 4);
+
+        let output = run_to_string(
+            "newline-bytes-jsonl",
+            vec!["--format", "jsonl", "-c", "\\x0aa", "-m1", "-x2", "?1?1"],
+        );
+        let words: Vec<_> = output
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["word"].clone())
+            .collect();
+        assert_eq!(words, ["\n", "a", "\n\n", "\na", "a\n", "aa"]);
+
+        let fname = test_util::wordlist_fname("wordlist-nul.txt");
+        let output = run_to_string(
+            "newline-bytes-wordlist",
+            vec![
+                "-w",
+                fname.to_str().unwrap(),
+                "--wordlist-format",
+                "nul",
+                "--encode",
+                "hex",
+                "--prefix",
+                "X:",
+                "?w1",
+            ],
+        );
+        assert_eq!(output.lines().count(), 4);
+        assert!(output.contains("\n583a62650a7461\n"));
+    }
+
+    #[test]
+    fn test_run_priority_file() {
+        let fname = test_util::wordlist_fname("wordlist-3words.txt");
+        let fname = fname.to_str().unwrap();
+        let output = run_to_string("priority-file", vec!["--priority-file", fname, "a?d"]);
+        assert_eq!(
+            output,
+            "alpha\nbeta\ngamma\na0\na1\na2\na3\na4\na5\na6\na7\na8\na9\n"
+        );
+
+        let output = run_to_string(
+            "dedup-priority",
+            vec![
+                "--priority-file",
+                fname,
+                "--dedup-priority",
+                "beta",
+                "?l?l?l?l",
+            ],
+        );
+        let words: Vec<_> = output.lines().collect();
+        assert_eq!(words[..4], ["alpha", "beta", "gamma", "aaaa"]);
+        // beta is generated by the mask but written only once
+        assert_eq!(words.len(), 3 + 26usize.pow(4) - 1);
+        assert_eq!(words.iter().filter(|&&w| w == "beta").count(), 1);
+    }
+
+    #[test]
+    fn test_run_exclude_wordlist() {
+        let fname = test_util::wordlist_fname("wordlist1.txt");
+        let fname = fname.to_str().unwrap();
+        let output = run_to_string(
+            "exclude-wordlist",
+            vec!["--exclude-wordlist", fname, "-c", "123", "?1?1?1?1?1?1"],
+        );
+        let words: Vec<_> = output.lines().collect();
+        // 111111 and 123123 are excluded
+        assert_eq!(words.len(), 3usize.pow(6) - 2);
+        assert!(!words.contains(&"111111"));
+        assert!(!words.contains(&"123123"));
+        assert!(words.contains(&"111112"));
+    }
+
+    #[test]
+    fn test_run_train_order_by_model() {
+        let passwords_fname = test_util::wordlist_fname("wordlist1.txt");
+        let model_fname = env::temp_dir().join("cracken-test-train-model.txt");
+        let model_fname = model_fname.to_str().unwrap();
+        let args = vec![
+            "cracken",
+            "train",
+            "-f",
+            passwords_fname.to_str().unwrap(),
+            "-o",
+            model_fname,
+        ];
+        run(Some(args)).unwrap();
+
+        let output = run_to_string(
+            "order-by-model",
+            vec!["--order-by-model", model_fname, "?d?d"],
+        );
+        fs::remove_file(model_fname).unwrap();
+        let words: Vec<_> = output.lines().collect();
+        // 1 is the most frequent digit of wordlist1.txt, then 2 and 3 equally
+        assert_eq!(words[..4], ["11", "12", "13", "21"]);
+        assert_eq!(words.len(), 100);
+    }
+
+    #[test]
+    fn test_run_delta_from() {
+        let manifest_fname = env::temp_dir().join("cracken-test-delta-manifest.json");
+        let manifest_fname = manifest_fname.to_str().unwrap();
+        let wordlist = env::temp_dir().join("cracken-test-delta-from-wordlist.txt");
+        let wordlist = wordlist.to_str().unwrap();
+        fs::write(wordlist, "alpha\nbeta\n").unwrap();
+        let args = vec!["--manifest", manifest_fname, "-w", wordlist, "?w1?w1"];
+        assert_eq!(run_to_string("delta-before", args).lines().count(), 4);
+
+        fs::write(wordlist, "alpha\nbeta\ngamma\n").unwrap();
+        let args = vec!["--delta-from", manifest_fname, "-w", wordlist, "?w1?w1"];
+        let output = run_to_string("delta-from", args);
+        assert_eq!(
+            output,
+            "gammabeta\ngammaalpha\ngammagamma\nbetagamma\nalphagamma\n"
+        );
+
+        // the last line of the manifest run may have no newline
+        fs::write(wordlist, "alpha\nbeta").unwrap();
+        let args = vec!["--manifest", manifest_fname, "-w", wordlist, "?w1"];
+        assert_eq!(run_to_string("delta-before", args), "beta\nalpha\n");
+        fs::write(wordlist, "alpha\nbeta\ngamma\n").unwrap();
+        let args = vec!["--delta-from", manifest_fname, "-w", wordlist, "?w1"];
+        assert_eq!(run_to_string("delta-from", args), "gamma\n");
+
+        // a changed line cannot be diffed
+        fs::write(wordlist, "alpha\nbetta\ngamma\n").unwrap();
+        let args = vec![
+            "cracken",
+            "--delta-from",
+            manifest_fname,
+            "-w",
+            wordlist,
+            "?w1",
+        ];
+        assert!(run(Some(args)).is_err());
+        fs::remove_file(wordlist).unwrap();
+        fs::remove_file(manifest_fname).unwrap();
+    }
+
+    #[test]
+    fn test_run_manifest() {
+        let manifest_fname = env::temp_dir().join("cracken-test-manifest.json");
+        let manifest_fname = manifest_fname.to_str().unwrap();
+        let wordlist = test_util::wordlist_fname("wordlist-3words.txt");
+        let output = run_to_string(
+            "manifest",
+            vec![
+                "--manifest",
+                manifest_fname,
+                "-w",
+                wordlist.to_str().unwrap(),
+                "-c",
+                "xy",
+                "--prefix",
+                "<",
+                "--encode",
+                "hex",
+                "--no-repeat",
+                "?w1?1",
+            ],
+        );
+        assert_eq!(output.lines().count(), 6);
+        assert!(output.starts_with("3c6265746178\n"));
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(manifest_fname).unwrap()).unwrap();
+        fs::remove_file(manifest_fname).unwrap();
+        assert_eq!(manifest["masks"], serde_json::json!(["?w1?1"]));
+        assert_eq!(manifest["custom_charsets"], serde_json::json!(["xy"]));
+        assert_eq!(manifest["combinations"], "6");
+        assert_eq!(manifest["wordlists"][0]["records"], 3);
+        assert_eq!(manifest["version"], built_info::PKG_VERSION);
+        // the output options, but not the options of where to write it
+        assert_eq!(
+            manifest["options"],
+            serde_json::json!({"prefix": "<", "encode": "hex", "no-repeat": true})
+        );
+    }
+
+    #[test]
+    fn test_run_positional_increment() {
+        let output = run_to_string(
+            "positional-increment",
+            vec!["--positional-increment", "-x", "2", "?d?d?d"],
+        );
+        // 3 single positions and 3 pairs of positions
+        assert_eq!(output.lines().count(), 3 * 10 + 3 * 100);
+        assert!(output.starts_with("0\n1\n"));
+        assert!(output.ends_with("98\n99\n"));
+    }
+
+    #[test]
+    fn test_parse_args_config() {
+        let config = GenerateConfig::from_file(test_util::wordlist_fname("cracken.toml")).unwrap();
+        let load_config = || Ok(Some(config.clone()));
+
+        let args = parse_args(Some(vec!["cracken", "?1?2?d"]), load_config).unwrap();
+        let args = args.subcommand_matches("generate").unwrap();
+        let charsets: Vec<_> = args.values_of("custom-charset").unwrap().collect();
+        assert_eq!(charsets, vec!["abc", "?d"]);
+        assert_eq!(args.value_of("wordlist"), Some("wordlist1.txt"));
+        assert_eq!(args.value_of("min-length"), Some("2"));
+        assert_eq!(args.value_of("max-length"), Some("3"));
+
+        // command line flags take precedence
+        let args = parse_args(
+            Some(vec!["cracken", "-c", "xyz", "--maxlen=4", "?1?d?d?d"]),
+            load_config,
+        )
+        .unwrap();
+        let args = args.subcommand_matches("generate").unwrap();
+        let charsets: Vec<_> = args.values_of("custom-charset").unwrap().collect();
+        assert_eq!(charsets, vec!["xyz"]);
+        assert_eq!(args.value_of("min-length"), Some("2"));
+        assert_eq!(args.value_of("max-length"), Some("4"));
+
+        // flags are matched as whole args, not by the prefix of values
+        let args = parse_args(Some(vec!["cracken", "-x4", "--", "-m?d?d?d"]), load_config).unwrap();
+        let args = args.subcommand_matches("generate").unwrap();
+        assert_eq!(
+            args.values_of("mask").unwrap().collect::<Vec<_>>(),
+            vec!["-m?d?d?d"]
+        );
+        assert_eq!(args.value_of("min-length"), Some("2"));
+        assert_eq!(args.value_of("max-length"), Some("4"));
+
+        // other subcommands do not load the config
+        let args = parse_args(
+            Some(vec!["cracken", "entropy", "--mask-only", "pass"]),
+            || panic!("the config is loaded"),
+        )
+        .unwrap();
+        assert!(args.subcommand_matches("entropy").is_some());
+    }
+
+    #[test]
+    fn test_run_invalid_config_file() {
+        let config_fname = Some(test_util::wordlist_fname("cracken-invalid.toml"));
+        let args = vec!["cracken", "entropy", "--mask-only", "pass"];
+        assert!(runner::run_with_config_file(Some(args), config_fname.clone()).is_ok());
+        let args = vec!["cracken", "-o", "/dev/null", "?d"];
+        assert!(runner::run_with_config_file(Some(args), config_fname).is_err());
+    }
+
+    #[test]
+    fn test_run_output_dir() {
+        let dirname = env::temp_dir().join("cracken-test-output-dir");
+        let manifest_fname = dirname.join("run.json");
+        let args = vec![
+            "cracken",
+            "--output-dir",
+            dirname.to_str().unwrap(),
+            "--manifest",
+            manifest_fname.to_str().unwrap(),
+            "--suffix",
+            "!",
+            "?d",
+            "a?d",
+        ];
+        run(Some(args)).unwrap();
+
+        let first = fs::read_to_string(dirname.join("mask_000.txt")).unwrap();
+        assert_eq!(first, "0!\n1!\n2!\n3!\n4!\n5!\n6!\n7!\n8!\n9!\n");
+        let second = fs::read_to_string(dirname.join("mask_001.txt")).unwrap();
+        assert!(second.starts_with("a0!\na1!\n"));
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&manifest_fname).unwrap()).unwrap();
+        assert_eq!(manifest["masks"], serde_json::json!(["?d", "a?d"]));
+        assert!(manifest["output_files"][1]
+            .as_str()
+            .unwrap()
+            .ends_with("mask_001.txt"));
+        fs::remove_dir_all(dirname).unwrap();
+    }
+
+    #[test]
+    fn test_run_wordlist_stats() {
+        let fname = test_util::wordlist_fname("wordlist1.txt");
+        for format in ["text", "json"] {
+            let args = vec![
+                "cracken",
+                "wordlist-stats",
+                "-w",
+                fname.to_str().unwrap(),
+                "--top",
+                "2",
+                "--format",
+                format,
+            ];
+            assert!(run(Some(args)).is_ok());
+        }
+        let args = vec!["cracken", "wordlist-stats", "-w", "no-such-wordlist.txt"];
+        assert!(run(Some(args)).is_err());
+    }
+
+    #[test]
+    fn test_run_sample() {
+        let fname = test_util::wordlist_fname("wordlist1.txt");
+        let args = vec![
+            "cracken",
+            "sample",
+            "-w",
+            fname.to_str().unwrap(),
+            "-n",
+            "3",
+            "--seed",
+            "42",
+        ];
+        assert!(run(Some(args)).is_ok());
+        let args = vec!["cracken", "sample", "-w", "no-such-wordlist.txt", "-n", "3"];
+        assert!(run(Some(args)).is_err());
+    }
+
+    #[test]
+    fn test_format_wordlist_stats() {
+        let fname = test_util::wordlist_fname("wordlist1.txt");
+        let stats = Wordlist::from_file(fname).unwrap().stats();
+        assert_eq!(
+            format_wordlist_stats(&stats, Some(2)),
+            "words: 10\nmin-len: 5\nmax-len: 9\navg-len: 6.70\n--\nlen,count\n\
+             5,1\n6,5\n7,1\n8,2\n9,1\n--\ntop-lens:\n6,5\n8,2\n--\ncharset,words\n\
+             ?l,3\n?u,0\n?d,8\n?s,0\n"
+        );
+
+        let value: serde_json::Value =
+            serde_json::from_str(&format_wordlist_stats_json(&stats, None).unwrap()).unwrap();
+        assert_eq!(value["words"], 10);
+        assert_eq!(value["avg_len"], 6.7);
+        assert_eq!(
+            value["len_counts"][1],
+            serde_json::json!({"len": 6, "count": 5})
+        );
+        assert_eq!(value["charset_words"]["?d"], 8);
+        assert!(value["top_lens"].is_null());
+    }
+
+    #[test]
+    fn test_run_wordlist_column_invalid() {
+        let fname = test_util::wordlist_fname("wordlist-columns.csv");
+        for (column, delim) in [("0", ","), ("1", ",,")] {
+            let args = Some(vec![
+                "cracken",
+                "-w",
+                fname.to_str().unwrap(),
+                "--wordlist-column",
+                column,
+                "--wordlist-delim",
+                delim,
+                "?w1",
+            ]);
+            assert!(run(args).is_err());
+        }
+    }
+
+    #[test]
+    fn test_run_multiple_masks() {
+        let output = run_to_string("multiple-masks", vec!["-c", "ab", "?d", "?1?1"]);
+        assert_eq!(output, "0\n1\n2\n3\n4\n5\n6\n7\n8\n9\naa\nab\nba\nbb\n");
+    }
+
+    #[test]
+    fn test_run_multiple_masks_stats() {
+        let stats_fname = env::temp_dir().join("cracken-test-multiple-masks-count.txt");
+        let stats_file = stats_fname.to_str().unwrap();
+        let args = vec!["--stats-file", stats_file, "-s", "?d", "?1?1", "-c", "ab"];
+        assert_eq!(run_to_string("multiple-masks-stats", args), "");
+        assert_eq!(fs::read_to_string(&stats_fname).unwrap(), "14\n");
+        fs::remove_file(&stats_fname).unwrap();
+    }
+
+    #[test]
+    fn test_run_masks_file_custom_charsets() {
+        let masks_fname = test_util::wordlist_fname("masks-charsets.txt");
+        let output = run_to_string(
+            "masks-file-charsets",
+            vec!["-c", "z", "-i", masks_fname.to_str().unwrap()],
+        );
+        let expected = "z0\nz1\nz2\nz3\nz4\nz5\nz6\nz7\nz8\nz9\naX\naY\nbX\nbY\n";
+        assert_eq!(output, expected);
+    }
+
+    /// the files referenced by a masks file are found relative to it from any working directory
+    #[test]
+    fn test_run_masks_file_relative_paths() {
+        let masks_fname = test_util::wordlist_fname("masks-relative.txt");
+        let output = run_to_bytes(
+            "masks-file-relative",
+            vec!["-i", masks_fname.to_str().unwrap()],
+        );
+        let lines: Vec<_> = output.split(|&ch| ch == b'\n').collect();
+        assert_eq!(lines.len(), 30 + 14 + 1);
+        assert!(lines.contains(&&b"alpha9"[..]));
+        assert_eq!(lines[30], b"0");
+
+        // --base-dir overrides the masks file directory
+        let masks_fname = env::temp_dir().join("cracken-test-base-dir.txt");
+        fs::write(&masks_fname, "?w1{wordlist-3words.txt}\n").unwrap();
+        let base_dir = test_util::wordlist_fname("");
+        let output = run_to_string(
+            "masks-file-base-dir",
+            vec![
+                "-i",
+                masks_fname.to_str().unwrap(),
+                "--base-dir",
+                base_dir.to_str().unwrap(),
+            ],
+        );
+        assert_eq!(output, "beta\nalpha\ngamma\n");
+        let args = Some(vec!["cracken", "-i", masks_fname.to_str().unwrap()]);
+        assert!(run(args).is_err());
+        fs::remove_file(&masks_fname).unwrap();
+
+        // a relative masks file path from another working directory
+        let cwd = env::current_dir().unwrap();
+        env::set_current_dir(test_util::wordlist_fname("wordlist-dir")).unwrap();
+        let output = run_to_bytes("masks-file-cwd", vec!["-i", "../masks-relative.txt"]);
+        env::set_current_dir(cwd).unwrap();
+        assert_eq!(output.split(|&ch| ch == b'\n').count(), 30 + 14 + 1);
+    }
+
+    #[test]
+    fn test_run_masks_file_inline_per_line() {
+        // the resources given inline on a line are of that line only
+        let masks_fname = env::temp_dir().join("cracken-test-masks-inline-per-line.txt");
+        let masks = "?2{ab}\n?2{cd}\n?1\n?w1{wordlist-3words.txt}\n?w1{wordlist2.txt}?1 -c -\n";
+        fs::write(&masks_fname, masks).unwrap();
+        let base_dir = test_util::wordlist_fname("");
+        let output = run_to_string(
+            "masks-file-inline-per-line",
+            vec![
+                "-i",
+                masks_fname.to_str().unwrap(),
+                "--base-dir",
+                base_dir.to_str().unwrap(),
+                "-c",
+                "xy",
+            ],
+        );
+        fs::remove_file(&masks_fname).unwrap();
+        let words: Vec<_> = output.lines().collect();
+        assert_eq!(
+            words[..9],
+            ["a", "b", "c", "d", "x", "y", "beta", "alpha", "gamma"]
+        );
+        assert_eq!(words.len(), 9 + 12);
+        assert!(words[9..].iter().all(|word| word.ends_with('-')));
+    }
+
+    #[test]
+    fn test_run_masks_file_crlf() {
+        let masks_fname = test_util::wordlist_fname("masks-crlf.txt");
+        let output = run_to_string("masks-file-crlf", vec!["-i", masks_fname.to_str().unwrap()]);
+        let words: Vec<_> = output.lines().collect();
+        assert_eq!(words.len(), 20 + 100 + 26 + 26);
+        assert_eq!(words[0], "0a");
+        assert_eq!(words[words.len() - 1], "Z ");
+    }
+
+    #[test]
+    fn test_charset_duplicates_report() {
+        let masks = vec![
+            MaskEntry::new("?1?2".to_owned()),
+            MaskEntry {
+                mask: "?1".to_owned(),
+                custom_charsets: Some(vec!["xyx".to_owned()]),
+                wordlists: None,
+            },
+        ];
+        assert_eq!(
+            charset_duplicates_report(&["abc", "?dab1a"], &masks),
+            vec![
+                "charset ?2 had 2 duplicate characters removed",
+                "charset ?1 of mask ?1 had 1 duplicate characters removed",
+            ]
+        );
+        assert!(charset_duplicates_report(&["abc"], &masks[..1]).is_empty());
+
+        let output = run_to_string("verbose", vec!["-v", "-c", "aab", "?1"]);
+        assert_eq!(output, "a\nb\n");
+    }
+
+    #[test]
+    fn test_run_no_repeat() {
+        let fname = test_util::wordlist_fname("wordlist-3words.txt");
+        let output = run_to_string(
+            "no-repeat",
+            vec!["-w", fname.to_str().unwrap(), "--no-repeat", "?w1?w1"],
+        );
+        assert_eq!(output.lines().count(), 6);
+        assert!(!output.contains("betabeta"));
+    }
+
+    #[test]
+    fn test_run_wordlist_format() {
+        for (fname, format) in [
+            ("wordlist-nul.txt", "nul"),
+            ("wordlist-netstring.txt", "netstring"),
+        ] {
+            let fname = test_util::wordlist_fname(fname);
+            let output = run_to_bytes(
+                "wordlist-format",
+                vec![
+                    "-w",
+                    fname.to_str().unwrap(),
+                    "--wordlist-format",
+                    format,
+                    "?w1-?d",
+                ],
+            );
+            let output = String::from_utf8(output).unwrap();
+            assert_eq!(output.matches("be\nta-").count(), 10);
+            assert!(output.starts_with("alpha-0\nalpha-1\n"));
+            assert!(output.ends_with("pass word\r\n-9\n"));
+
+            // transformed words with newlines are kept whole
+            let args = vec![
+                "-w",
+                fname.to_str().unwrap(),
+                "--wordlist-format",
+                format,
+                "--encode",
+                "hex",
+                "?w1-?d",
+            ];
+            let output = run_to_string("wordlist-format-hex", args);
+            assert_eq!(output.lines().count(), 40);
+            assert!(output.contains("\n62650a74612d30\n"));
+        }
+    }
+
+    #[test]
+    fn test_run_wordlist_format_delta_from() {
+        let manifest_fname = env::temp_dir().join("cracken-test-delta-nul-manifest.json");
+        let manifest_fname = manifest_fname.to_str().unwrap();
+        let wordlist = env::temp_dir().join("cracken-test-delta-nul-wordlist.txt");
+        let wordlist = wordlist.to_str().unwrap();
+        let format_args = ["-w", wordlist, "--wordlist-format", "nul"];
+
+        fs::write(wordlist, "a\nb\0c").unwrap();
+        let args = [&format_args[..], &["--manifest", manifest_fname, "?w1"]].concat();
+        assert_eq!(run_to_string("delta-nul-before", args), "c\na\nb\n");
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(manifest_fname).unwrap()).unwrap();
+        assert_eq!(manifest["wordlists"][0]["records"], 2);
+        assert_eq!(manifest["wordlists"][0]["format"], "nul");
+
+        fs::write(wordlist, "a\nb\0c\0d\ne\0").unwrap();
+        let args = [&format_args[..], &["--delta-from", manifest_fname, "?w1"]].concat();
+        assert_eq!(run_to_string("delta-nul", args), "d\ne\n");
+
+        // the wordlist must be read as by the manifest run
+        let args = vec![
+            "cracken",
+            "--delta-from",
+            manifest_fname,
+            "-w",
+            wordlist,
+            "?w1",
+        ];
+        let err = run(Some(args)).unwrap_err();
+        assert!(
+            err.to_string().contains("read as lines but as nul"),
+            "{}",
+            err
+        );
+        fs::remove_file(wordlist).unwrap();
+        fs::remove_file(manifest_fname).unwrap();
+    }
+
+    #[test]
+    fn test_run_min_distinct_words() {
+        let fname = test_util::wordlist_fname("wordlist-3words.txt");
+        let output = run_to_string(
+            "min-distinct-words",
+            vec![
+                "-w",
+                fname.to_str().unwrap(),
+                "--min-distinct-words",
+                "2",
+                "?w1-?w1-?w1",
+            ],
+        );
+        assert_eq!(output.lines().count(), 27 - 3);
+        assert!(!output.contains("beta-beta-beta"));
+        assert!(output.contains("beta-beta-alpha"));
+    }
+
+    #[test]
+    fn test_run_step() {
+        let output = run_to_string("step", vec!["--step", "10", "--offset", "3", "?d?d"]);
+        let expected: Vec<_> = (0..10).map(|i| format!("{}3", i)).collect();
+        assert_eq!(output.lines().collect::<Vec<_>>(), expected);
+
+        // the offsets of a step split the keyspace without overlaps
+        let mut words = vec![];
+        for offset in ["0", "1", "2"] {
+            let output = run_to_string("step", vec!["--step", "3", "--offset", offset, "?d?d"]);
+            words.extend(output.lines().map(String::from));
+        }
+        words.sort();
+        let expected: Vec<_> = (0..100).map(|i| format!("{:02}", i)).collect();
+        assert_eq!(words, expected);
+
+        let fname = test_util::wordlist_fname("wordlist-3words.txt");
+        let output = run_to_string(
+            "step",
+            vec!["-w", fname.to_str().unwrap(), "--step", "2", "?w1?d"],
+        );
+        assert_eq!(output.lines().count(), 15);
+
+        let stats_fname = env::temp_dir().join("cracken-test-step-count.txt");
+        let stats_file = stats_fname.to_str().unwrap();
+        for (args, expected) in [
+            (vec!["--step", "3", "?d?d"], "34\n"),
+            (vec!["--step", "3", "--offset", "2", "?d?d"], "33\n"),
+            (vec!["--step", "200", "--offset", "150", "?d?d"], "0\n"),
+        ] {
+            let args = [vec!["-s", "--stats-file", stats_file], args].concat();
+            assert_eq!(run_to_string("step", args), "");
+            assert_eq!(fs::read_to_string(&stats_fname).unwrap(), expected);
+        }
+        fs::remove_file(&stats_fname).unwrap();
+
+        for args in [
+            vec!["cracken", "--step", "0", "?d"],
+            vec!["cracken", "--step", "2", "--offset", "2", "?d"],
+        ] {
+            assert!(run(Some(args)).is_err());
+        }
+        // words skipped by --no-repeat-any cannot be indexed
+        let args = Some(vec![
+            "cracken",
+            "-w",
+            fname.to_str().unwrap(),
+            "--no-repeat-any",
+            "--step",
+            "2",
+            "?w1?w1",
+        ]);
+        assert!(run(args).is_err());
+    }
+
+    /// the consumer closing the pipe mid generation ends the run successfully, for every mask
+    #[cfg(unix)]
+    #[test]
+    fn test_run_broken_pipe() {
+        let fifo = env::temp_dir().join("cracken-test-broken-pipe.fifo");
+        let _ = fs::remove_file(&fifo);
+        let status = std::process::Command::new("mkfifo")
+            .arg(&fifo)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        for async_write in [false, true] {
+            // reads the first words and closes the pipe
+            let reader_fifo = fifo.clone();
+            let reader = std::thread::spawn(move || {
+                let mut fp = fs::File::open(reader_fifo).unwrap();
+                let mut buf = [0u8; 16];
+                std::io::Read::read_exact(&mut fp, &mut buf).unwrap();
+                buf
+            });
+            let mut args = vec!["cracken", "-o", fifo.to_str().unwrap()];
+            if async_write {
+                args.push("--async-write");
+            }
+            args.extend(["--prefix", "x", "?d?d?d?d?d?d", "?l?l?l?l"]);
+            assert!(run(Some(args)).is_ok());
+            assert_eq!(&reader.join().unwrap(), b"x000000\nx000001\n");
+        }
+        fs::remove_file(&fifo).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_fifo_wordlist() {
+        let fifo = env::temp_dir().join("cracken-test-wordlist.fifo");
+        let _ = fs::remove_file(&fifo);
+        let status = std::process::Command::new("mkfifo")
+            .arg(&fifo)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let fifo_fname = fifo.to_str().unwrap();
+
+        // the words are streamed once, also with the mmap feature
+        let writer_fifo = fifo.clone();
+        let writer = std::thread::spawn(move || fs::write(writer_fifo, "alpha\nbeta\n").unwrap());
+        let output = run_to_string("fifo-wordlist", vec!["-w", fifo_fname, "?w1?d"]);
+        writer.join().unwrap();
+        assert_eq!(output.lines().count(), 20);
+        assert!(output.starts_with("beta0\n"));
+
+        // masks share the words read once
+        let writer_fifo = fifo.clone();
+        let writer = std::thread::spawn(move || fs::write(writer_fifo, "alpha\nbeta\n").unwrap());
+        let output = run_to_string(
+            "fifo-wordlist-masks",
+            vec!["-w", fifo_fname, "?w1", "?w1?d"],
+        );
+        writer.join().unwrap();
+        assert_eq!(output.lines().count(), 22);
+        assert!(output.starts_with("beta\nalpha\nbeta0\n"));
+
+        // reading it again is an error, before opening it
+        for extra_args in [vec!["--zip", "?w1", "?w1?d"], vec!["--number-lines", "?w1"]] {
+            let mut args = vec!["cracken", "-w", fifo_fname];
+            args.extend(extra_args);
+            assert!(run(Some(args)).is_err());
+        }
+        fs::remove_file(&fifo).unwrap();
+    }
+
+    #[test]
+    fn test_run_missing_wordlist_manifest() {
+        let manifest = env::temp_dir().join("cracken-test-missing-wordlist.json");
+        let args = vec![
+            "cracken",
+            "--manifest",
+            manifest.to_str().unwrap(),
+            "-w",
+            "/nonexistent/cracken-nosuch.txt",
+            "?w1",
+        ];
+        let err = run(Some(args)).unwrap_err().to_string();
+        assert!(!err.contains("not a regular file"), "{}", err);
+        assert!(err.contains("No such file"), "{}", err);
+        assert!(!manifest.exists());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(15 * 60));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(2 * 3600));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86400));
+        assert_eq!(parse_duration("0s").unwrap(), Duration::from_secs(0));
+        for invalid in ["", "s", "30", "1.5h", "-1m", "2w", "99999999999999999999d"] {
+            assert!(parse_duration(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_run_max_time() {
+        let dirname = env::temp_dir().join("cracken-test-max-time");
+        fs::create_dir_all(&dirname).unwrap();
+        let manifest = dirname.join("run.json");
+        let outfile = dirname.join("out.txt");
+        let run_args = |extra: &[&str]| {
+            let mut args = vec![
+                "cracken",
+                "-o",
+                outfile.to_str().unwrap(),
+                "--manifest",
+                manifest.to_str().unwrap(),
+                "--max-time",
+                "0s",
+            ];
+            args.extend_from_slice(extra);
+            args.push("?d?d");
+            run(Some(args))
+        };
+
+        assert!(run_args(&[]).is_ok());
+        assert_eq!(fs::read_to_string(&outfile).unwrap(), "");
+        let value: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&manifest).unwrap()).unwrap();
+        assert_eq!(value["stopped_after"], 0);
+        assert_eq!(value["combinations"], "100");
+
+        assert!(run_args(&["--fail-on-deadline"]).is_err());
+        fs::remove_dir_all(dirname).unwrap();
+
+        let output = run_to_string("max-time", vec!["--max-time", "1h", "?d?d"]);
+        assert_eq!(output.lines().count(), 100);
+    }
+
+    #[test]
+    fn test_run_case() {
+        let fname = test_util::wordlist_fname("wordlist-3words.txt");
+        let fname = fname.to_str().unwrap();
+        let expected = [
+            ("upper", "BETA-BETA\n"),
+            ("lower", "beta-beta\n"),
+            ("capitalize", "Beta-beta\n"),
+            ("title", "Beta-Beta\n"),
+        ];
+        for (case, first) in expected {
+            let output = run_to_string(case, vec!["-w", fname, "--case", case, "?w1-?w1"]);
+            assert_eq!(output.lines().count(), 9);
+            assert!(output.starts_with(first), "{}: {}", case, output);
+        }
+    }
+
+    #[test]
+    fn test_run_zip() {
+        let wordlist1 = test_util::wordlist_fname("wordlist-3words.txt");
+        let wordlist2 = test_util::wordlist_fname("wordlist1.txt");
+        let output = run_to_string(
+            "zip",
+            vec![
+                "-w",
+                wordlist1.to_str().unwrap(),
+                "-w",
+                wordlist2.to_str().unwrap(),
+                "--zip",
+                "?w1 ?w2",
+            ],
+        );
+        assert_eq!(output, "alpha 123456\nbeta password\ngamma 123456789\n");
+    }
+
+    #[test]
+    fn test_run_annotate_mask() {
+        let masks_fname = test_util::wordlist_fname("masks-two.txt");
+        let output = run_to_string(
+            "annotate-mask",
+            vec!["--annotate-mask", "-i", masks_fname.to_str().unwrap()],
+        );
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 126);
+        assert_eq!(lines[0], "?d?d\t00");
+        assert_eq!(lines[99], "?d?d\t99");
+        assert_eq!(lines[100], "?l\ta");
+        assert_eq!(lines[125], "?l\tz");
+
+        let output = run_to_string(
+            "annotate-mask",
+            vec![
+                "--annotate-mask",
+                "--annotate-sep",
+                ",",
+                "--annotate-suffix",
+                "?d",
+                "x?d",
+            ],
+        );
+        assert!(output.starts_with("0,?d\n"));
+        assert!(output.ends_with("\nx9,x?d\n"));
+    }
+
+    #[test]
+    fn test_run_min_classes() {
+        let output = run_to_string(
+            "min-classes",
+            vec!["--min-classes", "2", "?1?d", "-c", "aB"],
+        );
+        assert_eq!(output.lines().count(), 20);
+        let output = run_to_string(
+            "min-classes",
+            vec!["--min-classes", "3", "?1?1?d", "-c", "a!"],
+        );
+        assert_eq!(output, "!a0\n!a1\n!a2\n!a3\n!a4\n!a5\n!a6\n!a7\n!a8\n!a9\na!0\na!1\na!2\na!3\na!4\na!5\na!6\na!7\na!8\na!9\n");
+
+        let stats_fname = env::temp_dir().join("cracken-test-min-classes-count.txt");
+        let args = vec![
+            "-s",
+            "--stats-file",
+            stats_fname.to_str().unwrap(),
+            "--min-classes",
+            "3",
+            "?a?a?a",
+        ];
+        assert_eq!(run_to_string("min-classes", args), "");
+        // 94^3 minus the words of 1 or 2 classes, out of lower 26, upper 26, digit 10, symbol 32
+        let (l, u, d, s) = (26u64, 26, 10, 32);
+        let two_classes = |a: u64, b: u64| (a + b).pow(3) - a.pow(3) - b.pow(3);
+        let expected = 94u64.pow(3)
+            - [l, u, d, s].iter().map(|c| c.pow(3)).sum::<u64>()
+            - [(l, u), (l, d), (l, s), (u, d), (u, s), (d, s)]
+                .iter()
+                .map(|&(a, b)| two_classes(a, b))
+                .sum::<u64>();
+        assert_eq!(
+            fs::read_to_string(&stats_fname).unwrap(),
+            format!("{}\n", expected)
+        );
+        fs::remove_file(&stats_fname).unwrap();
+
+        let args = Some(vec!["cracken", "--min-classes", "5", "?d"]);
+        assert!(run(args).is_err());
+    }
+
+    #[test]
+    fn test_run_no_trailing_separator() {
+        let output = run_to_string(
+            "no-trailing-separator",
+            vec!["--no-trailing-separator", "?d"],
+        );
+        assert_eq!(output, "0\n1\n2\n3\n4\n5\n6\n7\n8\n9");
+
+        // only the last word of the whole run, with the output transforms
+        let args = vec!["--no-trailing-separator", "--suffix", "!", "?d", "x?l"];
+        let output = run_to_string("no-trailing-separator", args);
+        assert!(output.contains("9!\nxa!\n"));
+        assert!(output.ends_with("\nxz!"));
+    }
+
+    #[test]
+    fn test_run_max_per_length() {
+        let output = run_to_string(
+            "max-per-length",
+            vec!["-m", "1", "--max-per-length", "3", "?d?d?d"],
+        );
+        assert_eq!(output, "0\n1\n2\n00\n01\n02\n000\n001\n002\n");
+
+        let stats_fname = env::temp_dir().join("cracken-test-max-per-length-count.txt");
+        let args = vec![
+            "-s",
+            "--stats-file",
+            stats_fname.to_str().unwrap(),
+            "-m",
+            "1",
+            "--max-per-length",
+            "50",
+            "?d?d?d",
+        ];
+        assert_eq!(run_to_string("max-per-length", args), "");
+        assert_eq!(fs::read_to_string(&stats_fname).unwrap(), "110\n");
+        fs::remove_file(&stats_fname).unwrap();
+
+        let args = Some(vec!["cracken", "--max-per-length", "0", "?d"]);
+        assert!(run(args).is_err());
+    }
+
+    #[test]
+    fn test_run_one_per_length() {
+        let output = run_to_string(
+            "one-per-length",
+            vec!["--one-per-length", "-m", "1", "?u?l?d"],
+        );
+        assert_eq!(output, "A\nAa\nAa0\n");
+        let args = vec![
+            "cracken",
+            "--one-per-length",
+            "--stats",
+            "-m",
+            "1",
+            "?u?l?d",
+        ];
+        assert!(run(Some(args)).is_ok());
+    }
+
+    #[test]
+    fn test_run_inline_resources() {
+        let wordlist = test_util::wordlist_fname("wordlist-3words.txt");
+        let wordlist = wordlist.to_str().unwrap();
+        let mask = format!("?w1{{{}}}?1{{ab}}", wordlist);
+        let output = run_to_string("inline-resources", vec![&mask]);
+        assert_eq!(output, "betaa\nbetab\nalphaa\nalphab\ngammaa\ngammab\n");
+
+        let expected = run_to_string("flag-resources", vec!["-w", wordlist, "-c", "ab", "?w1?1"]);
+        assert_eq!(output, expected);
+        // the same resources may be given both ways
+        let output = run_to_string("both-resources", vec!["-w", wordlist, &mask]);
+        assert_eq!(output, expected);
+
+        for mask in ["?w2{other.txt}", "?1{xy}"] {
+            let args = vec!["cracken", "-w", wordlist, "-c", "ab", mask];
+            assert!(run(Some(args)).is_err(), "{}", mask);
+        }
+    }
+
+    #[test]
+    fn test_run_checksum() {
+        let expected = run_to_bytes("no-checksum", vec!["--case", "upper", "?l?d"]);
+        for checksum in ["sha256", "xxhash"] {
+            let output = run_to_bytes(
+                "checksum",
+                vec!["--checksum", checksum, "--case", "upper", "?l?d"],
+            );
+            assert_eq!(output, expected);
+        }
+        let args = vec!["cracken", "--checksum", "sha256", "--stats", "?d"];
+        assert!(run(Some(args)).is_ok());
+    }
+
+    #[test]
+    fn test_run_max_word_len() {
+        let fname = test_util::wordlist_fname("wordlist-long-line.txt");
+        let fname = fname.to_str().unwrap();
+        // lines over the max word size fail by default
+        let args = vec!["cracken", "-o", "/dev/null", "-w", fname, "?w1"];
+        assert!(run(Some(args)).is_err());
+
+        let output = run_to_string(
+            "max-word-len",
+            vec!["-v", "--max-word-len", "511", "-w", fname, "?w1"],
+        );
+        assert_eq!(output, "beta\nalpha\ngamma\n");
+
+        // the verbose report of the skipped lines of each loaded wordlist
+        let options = GeneratorOptions::new()
+            .wordlist_options(WordlistOptions::new().max_word_len(Some(6)))
+            .cache_wordlists(true);
+        let wordlist1 = test_util::wordlist_fname("wordlist1.txt");
+        let wordlist1 = wordlist1.to_str().unwrap();
+        get_word_generator_with_options("?w1?w2", None, None, &[], &[fname, wordlist1], &options)
+            .unwrap();
+        assert_eq!(
+            skipped_words_report(&options, 6),
+            vec![
+                format!("skipped 2 lines of wordlist {} longer than 6 bytes", fname),
+                format!(
+                    "skipped 4 lines of wordlist {} longer than 6 bytes",
+                    wordlist1
+                ),
+            ]
+        );
+        let output = run_to_string(
+            "max-word-len",
+            vec!["--max-word-len", "4", "-w", fname, "?w1?d"],
+        );
+        assert_eq!(output.lines().count(), 10);
+        assert!(output.starts_with("beta0\n"));
+    }
+
+    #[test]
+    fn test_run_anti_mask() {
+        let output = run_to_string(
+            "anti-mask",
+            vec!["--anti-mask", "?d", "-c", "01234567", "?1?1"],
+        );
+        assert_eq!(output, "88\n89\n98\n99\n");
+        let args = vec!["cracken", "--anti-mask", "?l?u?d?s", "--stats", "?l?l?d"];
+        assert!(run(Some(args)).is_ok());
+        let args = vec!["cracken", "--anti-mask", "?d", "?d"];
+        assert!(run(Some(args)).is_err());
+    }
+
+    /// writes `data` to the output opened by `writers` to `stdout`, returning the checksums
+    fn write_output<'scope>(
+        mut writers: OutputWriters<'scope, '_>,
+        stdout: &'scope mut Vec<u8>,
+        outfile: Option<&str>,
+        tee: bool,
+        output_dir: Option<&str>,
+        data: &[u8],
+    ) -> Vec<String> {
+        let mut out = writers.open(outfile, tee, output_dir, stdout).unwrap();
+        out.write_all(data).unwrap();
+        drop(out);
+        writers.checksum_lines()
+    }
+
+    #[test]
+    fn test_output_writers() {
+        let data_sha256 = "7e18f737311b2dc3b2f269dd78396b0351f14fb66efa879f768cb23181883c78";
+        let expected_checksum = vec![format!("sha256: {}  -", data_sha256)];
+        let writers = OutputWriters::new(Some("sha256"), true);
+        let mut stdout = vec![];
+        let checksum_lines = write_output(writers, &mut stdout, None, false, None, b"a\nb\n");
+        // the last newline is trimmed before the checksum
+        assert_eq!(stdout, b"a\nb");
+        assert_eq!(checksum_lines, expected_checksum);
+
+        // the async output is checksummed and trimmed before it is handed to its thread
+        let mut stdout = vec![];
+        let checksum_lines = std::thread::scope(|scope| {
+            let writers = OutputWriters::new(Some("sha256"), true).async_write(Some(scope));
+            write_output(writers, &mut stdout, None, false, None, b"a\nb\n")
+        });
+        assert_eq!(stdout, b"a\nb");
+        assert_eq!(checksum_lines, expected_checksum);
+
+        // both the file and stdout are written with tee
+        let dir = env::temp_dir().join("cracken-test-output-writers");
+        fs::create_dir_all(&dir).unwrap();
+        let fname = dir.join("out.txt");
+        let fname = fname.to_str().unwrap();
+        let writers = OutputWriters::new(None, false);
+        let mut stdout = vec![];
+        let checksum_lines = write_output(writers, &mut stdout, Some(fname), true, None, b"a\nb\n");
+        assert_eq!(stdout, b"a\nb\n");
+        assert_eq!(fs::read(fname).unwrap(), b"a\nb\n");
+        assert!(checksum_lines.is_empty());
+
+        // with an output dir stdout is returned as is, each mask file is checksummed
+        let dirname = dir.to_str().unwrap();
+        let writers = OutputWriters::new(Some("sha256"), true);
+        let mut stdout = vec![];
+        let checksum_lines = write_output(writers, &mut stdout, None, false, Some(dirname), b"x\n");
+        assert_eq!(stdout, b"x\n");
+        assert!(checksum_lines.is_empty());
+
+        let mut writers = OutputWriters::new(Some("sha256"), true);
+        let (mask_fname, mut fp) = writers.open_mask_file(dirname, 3).unwrap();
+        fp.write_all(b"a\nb\n").unwrap();
+        drop(fp);
+        assert!(mask_fname.ends_with("mask_003.txt"));
+        assert_eq!(fs::read(&mask_fname).unwrap(), b"a\nb");
+        assert_eq!(
+            writers.checksum_lines(),
+            vec![format!("sha256: {}  {}", data_sha256, mask_fname)]
         );
-        if let Err(e) = write!(&mut stdout, "{}", text) {
-            match e.kind() {
-                // ignore broken pipe, (e.g. happens when using head)
-                ErrorKind::BrokenPipe => return Ok(()),
-                _ => bail!("error occurred writing to out: {}", e),
-            }
+        assert!(writers.open_mask_file("/nonexistent/cracken", 0).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// a writer failing every write with an error of `kind`
+    struct FailingWriter(ErrorKind);
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(self.0.into())
         }
-    } else if let Some(pwd_file) = args.value_of("passwords-file") {
-        let file = File::open(pwd_file)?;
-        let reader = RawFileReader::new(file);
-        for pwd in reader.into_iter() {
-            let pwd = pwd?;
-            let entropy_result = est.estimate_password_entropy(&pwd)?;
-            let (pwd_entropy, pwd_mask) = match mask_type {
-                "hybrid" => (
-                    entropy_result.subword_entropy,
-                    entropy_result.min_subword_mask,
-                ),
-                "charset" => (entropy_result.mask_entropy, entropy_result.charset_mask),
-                _ => unreachable!("invalid entropy type"),
-            };
-            if !is_summary_only {
-                if let Err(e) = writeln!(
-                    &mut stdout,
-                    "{:.2},{},{}",
-                    pwd_entropy,
-                    pwd_mask,
-                    String::from_utf8_lossy(&pwd)
-                ) {
-                    match e.kind() {
-                        // ignore broken pipe, (e.g. happens when using head)
-                        ErrorKind::BrokenPipe => return Ok(()),
-                        _ => bail!("error occurred writing to out: {}", e),
-                    }
-                }
-            } else {
-                total_entropy += pwd_entropy;
-            }
-            pwd_count += 1;
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_mask_words() {
+        let word_gen = get_word_generator("?d", None, None, &[], &[]).unwrap();
+        let mut data = vec![];
+        {
+            let mut out = WordsWriter::new(Box::new(&mut data), OutputOptions::new());
+            let written = write_mask_words(word_gen.as_ref(), Some((3, 1)), &mut out).unwrap();
+            assert_eq!(written, MaskWrite::Done);
+            assert_eq!(out.words_written(), 3);
         }
+        assert_eq!(data, b"1\n4\n7\n");
 
-        if is_summary_only {
-            writeln!(
-                &mut stdout,
-                "avg entropy: {}",
-                total_entropy / pwd_count as f64
-            )?;
+        let mut out = WordsWriter::new(
+            Box::new(FailingWriter(ErrorKind::BrokenPipe)),
+            OutputOptions::new(),
+        );
+        let written = write_mask_words(word_gen.as_ref(), None, &mut out).unwrap();
+        assert_eq!(written, MaskWrite::PipeClosed);
+
+        let mut out =
+            WordsWriter::new(Box::new(vec![]), OutputOptions::new()).deadline(Some(Instant::now()));
+        let written = write_mask_words(word_gen.as_ref(), None, &mut out).unwrap();
+        assert_eq!(written, MaskWrite::DeadlineReached { pipe_closed: false });
+
+        // a timeout of the output itself is an error without a deadline
+        for kind in [ErrorKind::TimedOut, ErrorKind::PermissionDenied] {
+            let mut out = WordsWriter::new(Box::new(FailingWriter(kind)), OutputOptions::new());
+            assert!(write_mask_words(word_gen.as_ref(), None, &mut out).is_err());
         }
     }
-    Ok(())
-}
 
-pub fn run_create_smartlist(args: &ArgMatches) -> BoxResult<()> {
-    let outfile = args.value_of("smartlist").unwrap();
-    let infiles = args.values_of("file").map(|x| x.collect()).unwrap();
-    let vocab_max_size =
-        optional_value_t_or_exit!(args, "vocab_max_size", u32).unwrap_or(DEFAULT_VOCAB_SIZE);
-    let min_frequency = optional_value_t_or_exit!(args, "min_frequency", u32).unwrap_or(0);
-    let print_progress = !args.is_present("quiet");
-    let numbers_max_size = optional_value_t_or_exit!(args, "numbers_max_size", u32);
-    let min_word_len = optional_value_t_or_exit!(args, "min_word_len", u32).unwrap_or(1);
+    #[test]
+    fn test_run_preview() {
+        // nothing is written to the output, the preview goes to stderr
+        let output = run_to_string("preview", vec!["--preview", "3", "?d?d?d?d?d?d?d?d?d?d"]);
+        assert_eq!(output, "");
+        let wordlist1 = test_util::wordlist_fname("wordlist1.txt");
+        let wordlist2 = test_util::wordlist_fname("wordlist2.txt");
+        for args in [
+            vec!["--preview", "5", "?d", "?l?l"],
+            vec!["--preview", "0", "--minlen", "1", "?d?d"],
+            vec!["--preview", "2", "-w", wordlist1.to_str().unwrap(), "?w1?d"],
+            vec![
+                "--preview",
+                "2",
+                "--zip",
+                "-w",
+                wordlist1.to_str().unwrap(),
+                "-w",
+                wordlist2.to_str().unwrap(),
+                "?w1?w2",
+            ],
+        ] {
+            assert_eq!(run_to_string("preview", args), "");
+        }
 
-    let tokenizers = args
-        .values_of("tokenizer")
-        .map(|x| x.collect())
-        .unwrap_or_else(|| vec!["unigram"])
-        .into_iter()
-        .map(|x| match x {
-            "bpe" => SmartlistTokenizer::BPE,
-            "unigram" => SmartlistTokenizer::Unigram,
-            "wordpiece" => SmartlistTokenizer::WordPiece,
-            _ => unreachable!("invalid tokenizer {}", x),
-        });
+        let preview = |mask: &str, n: usize, options: OutputOptions| {
+            let word_gen = get_word_generator(mask, None, None, &[], &[]).unwrap();
+            let mut err = vec![];
+            print_preview(word_gen.as_ref(), n, &options, &mut err).unwrap();
+            String::from_utf8(err).unwrap()
+        };
+        assert_eq!(
+            preview("?d?d", 2, OutputOptions::new()),
+            "mask ?d?d - first 2 / last 2 of 100:\n00\n01\n...\n98\n99\n"
+        );
+        assert_eq!(
+            preview("?d", 5, OutputOptions::new()),
+            "mask ?d - first 5 / last 5 of 10:\n0\n1\n2\n3\n4\n5\n6\n7\n8\n9\n"
+        );
+        // the words are written with the output options, without line numbers
+        let exclude = vec![b"01".to_vec()].into_iter().collect();
+        let options = OutputOptions::new()
+            .prefix(b"<")
+            .suffix(b">")
+            .exclude(exclude)
+            .encoding(Some(Encoding::Hex))
+            .number_lines(Some(NumberLines::new(2, ":")));
+        assert_eq!(
+            preview("?d?d", 2, options),
+            "mask ?d?d - first 2 / last 2 of 100:\n3c30303e\n...\n3c39383e\n3c39393e\n"
+        );
+        let options = OutputOptions::new()
+            .case(Some(Case::Upper))
+            .annotation(Some(Annotation::new("\t", false)));
+        assert_eq!(
+            preview("?l", 1, options),
+            "mask ?l - first 1 / last 1 of 26:\n?l\tA\n...\n?l\tZ\n"
+        );
+    }
 
-    let mut writer = BufWriter::new(File::create(outfile)?);
-    let vocab = SmartlistBuilder::new()
-        .infiles(infiles)
-        .min_frequency(min_frequency)
-        .vocab_max_size(vocab_max_size)
-        .tokenizers(tokenizers.into_iter())
-        .print_progress(print_progress)
-        .numbers_max_size(numbers_max_size)
-        .min_word_len(min_word_len)
-        .build()?;
+    #[test]
+    fn test_run_stdin_given_twice() {
+        let cases = [
+            vec!["cracken", "-w", "-", "-w", "-", "?w1?w2"],
+            vec!["cracken", "-i", "-", "-w", "-"],
+            vec!["cracken", "entropy", "--mask-only", "-p", "-", "-p", "-"],
+            vec![
+                "cracken",
+                "train",
+                "-f",
+                "-",
+                "-f",
+                "-",
+                "-o",
+                "/tmp/cracken-test-stdin.model",
+            ],
+        ];
+        for args in cases {
+            let err = run(Some(args.clone())).unwrap_err();
+            assert!(
+                err.to_string().contains("can be read only once"),
+                "{:?}: {}",
+                args,
+                err
+            );
+        }
+    }
 
-    // write to file
-    for word in vocab.iter() {
-        writer.write_all(word.as_bytes())?;
-        writer.write_all(b"\n")?;
+    #[test]
+    fn test_run_tee() {
+        let fname = env::temp_dir().join("cracken-test-tee.txt");
+        let args = vec![
+            "cracken",
+            "-o",
+            fname.to_str().unwrap(),
+            "--tee",
+            "--checksum",
+            "xxhash",
+            "?d",
+        ];
+        // the words are written both to the file and to stdout
+        let expected = "0\n1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        assert_eq!(run_stdout(args.clone()), expected);
+        assert_eq!(fs::read_to_string(&fname).unwrap(), expected);
+
+        let args = [args, vec!["--async-write"]].concat();
+        assert_eq!(run_stdout(args), expected);
+        assert_eq!(fs::read_to_string(&fname).unwrap(), expected);
+        fs::remove_file(&fname).unwrap();
     }
-    Ok(())
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::{runner, test_util};
+    #[test]
+    fn test_run_async_write() {
+        let expected = run_to_bytes("sync-write", vec!["?d?d?d?d?d"]);
+        let output = run_to_bytes("async-write", vec!["--async-write", "?d?d?d?d?d"]);
+        assert_eq!(output.len(), 600_000);
+        assert_eq!(output, expected);
+
+        let output = run_stdout(vec!["cracken", "--async-write", "?d?d?d?d?d"]);
+        assert_eq!(output.as_bytes(), expected);
+
+        // each mask file is written from its own thread
+        let dir = env::temp_dir().join("cracken-test-async-write-dir");
+        let args = vec![
+            "cracken",
+            "--async-write",
+            "--output-dir",
+            dir.to_str().unwrap(),
+            "?d?d?d?d",
+            "?l?l?l",
+        ];
+        assert_eq!(run_stdout(args), "");
+        assert_eq!(fs::read(dir.join("mask_000.txt")).unwrap().len(), 50_000);
+        assert_eq!(fs::read(dir.join("mask_001.txt")).unwrap().len(), 70_304);
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
     #[test]
-    fn test_run_generate_smoke() {
-        for args in vec![vec!["cracken", "generate", "?d"], vec!["cracken", "?d"]] {
-            assert!(runner::run(Some(args)).is_ok());
+    fn test_run_format_jsonl() {
+        let output = run_to_string("jsonl", vec!["--format", "jsonl", "?d?d"]);
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 100);
+        assert_eq!(lines[0], r#"{"word":"00","index":0,"length":2}"#);
+        assert_eq!(lines[99], r#"{"word":"99","index":99,"length":2}"#);
+
+        // binary words containing a newline byte are kept whole
+        let output = run_to_string(
+            "jsonl-bytes",
+            vec!["--format", "jsonl", "-c", "\\x0a\\xff", "?1"],
+        );
+        assert_eq!(
+            output,
+            "{\"word\":\"\\n\",\"index\":0,\"length\":1}\n\
+             {\"word\":\"/w==\",\"encoding\":\"base64\",\"index\":1,\"length\":1}\n"
+        );
+
+        for args in [
+            vec!["cracken", "--format", "jsonl", "--number-lines", "?d"],
+            vec!["cracken", "--format", "jsonl", "--encode", "hex", "?d"],
+        ] {
+            assert!(run(Some(args)).is_err());
         }
     }
 
     #[test]
-    fn test_run_entropy_smoke() {
+    fn test_run_from_to() {
+        let output = run_to_string("from-to", vec!["--from", "ab8", "--to", "ac1", "?l?l?d"]);
+        assert_eq!(output, "ab8\nab9\nac0\nac1\n");
+
+        for args in [
+            vec!["cracken", "--from", "ac1", "--to", "ab8", "?l?l?d"],
+            vec!["cracken", "--from", "a1", "--to", "ab8", "?l?l?d"],
+            vec![
+                "cracken", "--from", "ab8", "--to", "ac1", "-m", "1", "?l?l?d",
+            ],
+        ] {
+            assert!(run(Some(args)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_run_hcmask_file() {
+        let masks_fname = test_util::wordlist_fname("masks.hcmask");
+        let stats_fname = env::temp_dir().join("cracken-test-hcmask-file-count.txt");
+        let args = vec![
+            "--stats-file",
+            stats_fname.to_str().unwrap(),
+            "-i",
+            masks_fname.to_str().unwrap(),
+            "-s",
+        ];
+        assert_eq!(run_to_string("hcmask-file", args), "");
+        assert_eq!(fs::read_to_string(&stats_fname).unwrap(), "104\n");
+        fs::remove_file(&stats_fname).unwrap();
+
+        let output = run_to_string("hcmask-file-gen", vec!["-i", masks_fname.to_str().unwrap()]);
+        assert_eq!(output.lines().count(), 100 + 4);
+        assert!(output.ends_with("\na,\naX\nb,\nbX\n"));
+    }
+
+    #[test]
+    fn test_run_custom_charset_builtin_refs() {
+        let output = run_to_string("charset-builtin-refs", vec!["-c", "?dx", "?1"]);
+        assert_eq!(output, "0\n1\n2\n3\n4\n5\n6\n7\n8\n9\nx\n");
+    }
+
+    #[test]
+    fn test_run_charset_file() {
+        let charset_fname = test_util::wordlist_fname("charset.hcchr");
+        let output = run_to_bytes(
+            "charset-file",
+            vec![
+                "--charset-file",
+                charset_fname.to_str().unwrap(),
+                "-c",
+                "z",
+                "?2?1",
+            ],
+        );
+        assert_eq!(output.len(), 14 * 3);
+        assert!(output.starts_with(b"z0\nz1\n"));
+        assert!(output.ends_with(b"z\\\nza\nzb\nz\xe4\n"));
+
+        let args = Some(vec!["cracken", "--charset-file", "/not/exists.hcchr", "?1"]);
+        assert!(run(args).is_err());
+    }
+
+    #[test]
+    fn test_run_custom_charset_literal_question_mark() {
+        let output = run_to_string("charset-literal-qmark", vec!["-c", "\\?d", "?1"]);
+        assert_eq!(output, "?\nd\n");
+        let output = run_to_string("charset-trailing-qmark", vec!["-c", "a?", "?1"]);
+        assert_eq!(output, "?\na\n");
+    }
+
+    #[test]
+    fn test_run_create_sort_order() {
+        let fname = test_util::wordlist_fname("wordlist1.txt");
+        let outfile = env::temp_dir().join("cracken-test-create-sort-order.txt");
+        let args = vec![
+            "cracken",
+            "create",
+            "-q",
+            "-f",
+            fname.to_str().unwrap(),
+            "-o",
+            outfile.to_str().unwrap(),
+            "-m",
+            "10",
+            "--sort-order",
+            "length",
+        ];
+        run(Some(args)).unwrap();
+        let words = fs::read_to_string(&outfile).unwrap();
+        fs::remove_file(&outfile).unwrap();
+        let words: Vec<_> = words.lines().collect();
+        assert_eq!(words.len(), 10);
+        assert!(words.windows(2).all(|w| w[0].len() <= w[1].len()));
+    }
+
+    #[test]
+    fn test_run_entropy_null_delimited() {
+        let vocab_fname = test_util::wordlist_fname("vocab.txt");
+        let pwd_fname = test_util::wordlist_fname("passwords-null.txt");
+        let args = Some(vec![
+            "cracken",
+            "entropy",
+            "--smartlist",
+            vocab_fname.to_str().unwrap(),
+            "-z",
+            "-p",
+            pwd_fname.to_str().unwrap(),
+        ]);
+        assert!(run(args).is_ok());
+    }
+
+    #[test]
+    fn test_run_entropy_mask_only() {
+        let args = Some(vec!["cracken", "entropy", "--mask-only", "Pa55!"]);
+        assert!(run(args).is_ok());
+
+        let pwd_fname = test_util::wordlist_fname("passwords-null.txt");
+        let args = Some(vec![
+            "cracken",
+            "entropy",
+            "--mask-only",
+            "-z",
+            "-p",
+            pwd_fname.to_str().unwrap(),
+        ]);
+        assert!(run(args).is_ok());
+    }
+
+    #[test]
+    fn test_run_entropy_multiple_passwords_files() {
+        let vocab_fname = test_util::wordlist_fname("vocab.txt");
+        let pwd_fname1 = test_util::wordlist_fname("wordlist1.txt");
+        let pwd_fname2 = test_util::wordlist_fname("wordlist2.txt");
+        for summary in [false, true] {
+            let mut args = vec![
+                "cracken",
+                "entropy",
+                "--smartlist",
+                vocab_fname.to_str().unwrap(),
+                "-p",
+                pwd_fname1.to_str().unwrap(),
+                "-p",
+                pwd_fname2.to_str().unwrap(),
+            ];
+            if summary {
+                args.push("--summary");
+            }
+            assert!(run(Some(args)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_run_entropy_top_strong() {
+        let pwd_fname = test_util::wordlist_fname("passwords-mixed.txt");
+        let args = vec![
+            "cracken",
+            "entropy",
+            "--mask-only",
+            "--summary",
+            "--top-strong",
+            "2",
+            "-p",
+            pwd_fname.to_str().unwrap(),
+        ];
+        assert_eq!(
+            run_stdout(args),
+            "avg entropy: 33.8716192578223\ntop 2 strongest passwords:\n\
+            53.17,?l?d?s?u?l?s?d?l?u?l?s?d,x7#Kp!9zQw@2\n\
+            47.87,?u?l?d?l?l?d?l?l?l?s?d,Tr0ub4dor&3\n"
+        );
+
+        // the earlier password is kept on equal entropy
+        let pwd_fname = env::temp_dir().join("cracken-test-top-strong-ties.txt");
+        fs::write(&pwd_fname, "ab\nxyz\nzyx\ncd\n").unwrap();
+        let output = run_stdout(vec![
+            "cracken",
+            "entropy",
+            "--mask-only",
+            "--summary",
+            "--top-strong",
+            "1",
+            "-p",
+            pwd_fname.to_str().unwrap(),
+        ]);
+        fs::remove_file(&pwd_fname).unwrap();
+        assert!(output.ends_with("top 1 strongest passwords:\n14.10,?l?l?l,xyz\n"));
+    }
+
+    #[test]
+    fn test_run_entropy_verbose() {
         let vocab_fname = test_util::wordlist_fname("vocab.txt");
         let args = Some(vec![
             "cracken",
             "entropy",
+            "-v",
             "--smartlist",
             vocab_fname.to_str().unwrap(),
             "helloworld123!",
         ]);
-        assert!(runner::run(args).is_ok());
+        assert!(run(args).is_ok());
     }
 
     #[test]
-    fn test_run_dev_null() {
-        let args = Some(vec!["cracken", "-o", "/dev/null", "?d"]);
-        assert!(runner::run(args).is_ok());
+    fn test_run_entropy_inline_words() {
+        let vocab_fname = test_util::wordlist_fname("vocab.txt");
+        for args in [
+            vec![
+                "cracken",
+                "entropy",
+                "--smartlist",
+                vocab_fname.to_str().unwrap(),
+                "--word",
+                "company",
+                "--word",
+                "acme",
+                "Acme2024!",
+            ],
+            vec!["cracken", "entropy", "--word", "acme", "acme2024!"],
+        ] {
+            assert!(run(Some(args)).is_ok());
+        }
     }
 
     #[test]
-    fn test_run_custom_charset() {
-        let args = Some(vec!["cracken", "-c=abcdef0123456789", "?1"]);
-        assert!(runner::run(args).is_ok());
+    fn test_run_entropy_score() {
+        let vocab_fname = test_util::wordlist_fname("vocab.txt");
+        let pwds_fname = test_util::wordlist_fname("passwords-mixed.txt");
+        let output = run_stdout(vec![
+            "cracken",
+            "entropy",
+            "--smartlist",
+            vocab_fname.to_str().unwrap(),
+            "--score",
+            "helloworld123!",
+        ]);
+        assert!(output.contains("hybrid-min-entropy: 31.62\nhybrid-score: 36 (ok)\n"));
+        assert!(output.contains("charset-mask-entropy: 61.97\ncharset-mask-score: 67 (strong)\n"));
+
+        let output = run_stdout(vec![
+            "cracken",
+            "entropy",
+            "--mask-only",
+            "--score",
+            "Pa55!",
+        ]);
+        assert_eq!(
+            output,
+            "charset-mask: ?u?l?d?d?s\ncharset-mask-entropy: 21.04\ncharset-mask-score: 24 (weak)\n"
+        );
+
+        // the score and label columns follow the entropy
+        let output = run_stdout(vec![
+            "cracken",
+            "entropy",
+            "--mask-only",
+            "--score",
+            "-p",
+            pwds_fname.to_str().unwrap(),
+        ]);
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0], "19.93,23,weak,?d?d?d?d?d?d,123456");
+        assert_eq!(
+            lines[4],
+            "53.17,58,ok,?l?d?s?u?l?s?d?l?u?l?s?d,x7#Kp!9zQw@2"
+        );
+
+        let output = run_stdout(vec![
+            "cracken",
+            "entropy",
+            "--mask-only",
+            "--score",
+            "-s",
+            "-p",
+            pwds_fname.to_str().unwrap(),
+        ]);
+        assert_eq!(output, "avg entropy: 33.8716192578223\navg score: 38.00\n");
     }
 
     #[test]
-    fn test_run_stats() {
-        let args = Some(vec!["cracken", "-s", "?d?s?u?l?a?b"]);
-        assert!(runner::run(args).is_ok());
+    fn test_run_entropy_show_keyspace() {
+        let vocab_fname = test_util::wordlist_fname("vocab.txt");
+        for args in [
+            vec![
+                "cracken",
+                "entropy",
+                "--smartlist",
+                vocab_fname.to_str().unwrap(),
+                "--show-keyspace",
+                "helloworld123!",
+            ],
+            vec![
+                "cracken",
+                "entropy",
+                "--mask-only",
+                "--show-keyspace",
+                "Pa55!",
+            ],
+        ] {
+            assert!(run(Some(args)).is_ok());
+        }
     }
 
     #[test]
-    fn test_run_perm_denied() {
-        let args = Some(vec!["cracken", "-o", "/tmp/this/dir/not/exisT", "?d"]);
-        assert!(runner::run(args).is_err());
+    fn test_run_entropy_per_char() {
+        let per_char = "--\nP: 4.70 (?u)\na: 4.70 (?l)\ns: 4.70 (?l)\ns: 4.70 (?l)\nw: 4.70 (?l)\n\
+            0: 3.32 (?d)\nr: 4.70 (?l)\nd: 4.70 (?l)";
+        let vocab_fname = test_util::wordlist_fname("vocab.txt");
+        let output = run_stdout(vec![
+            "cracken",
+            "entropy",
+            "--smartlist",
+            vocab_fname.to_str().unwrap(),
+            "--per-char",
+            "Passw0rd",
+        ]);
+        assert!(output.starts_with("hybrid-min-split: [\"Passw0rd\"]\n"));
+        assert!(output.contains(&format!("charset-mask-entropy: 36.23\n{}\n", per_char)));
+
+        let output = run_stdout(vec![
+            "cracken",
+            "entropy",
+            "--mask-only",
+            "--per-char",
+            "Passw0rd",
+        ]);
+        assert_eq!(
+            output,
+            format!(
+                "charset-mask: ?u?l?l?l?l?d?l?l\ncharset-mask-entropy: 36.23\n{}\n",
+                per_char
+            )
+        );
+
+        let output = run_stdout(vec![
+            "cracken",
+            "entropy",
+            "--mask-only",
+            "--english-freq",
+            "--codepoints",
+            "--per-char",
+            "pässw0rd",
+        ]);
+        assert_eq!(
+            output,
+            "charset-mask: ?l?b?l?l?l?d?l?l\ncharset-mask-entropy: 39.00\n--\n\
+            p: 5.70 (?l)\nä: 8.00 (?b)\ns: 3.98 (?l)\ns: 3.98 (?l)\nw: 5.40 (?l)\n\
+            0: 3.32 (?d)\nr: 4.06 (?l)\nd: 4.56 (?l)\n"
+        );
     }
 
     #[test]
-    fn test_run_bad_args() {
-        let args = Some(vec!["cracken", "-m", "2", "?d"]);
-        assert!(runner::run(args).is_err());
+    fn test_run_entropy_charset_freq() {
+        let model_fname = env::temp_dir().join("cracken-test-entropy-freq.txt");
+        let model_fname = model_fname.to_str().unwrap();
+        let vocab_fname = test_util::wordlist_fname("vocab.txt");
+        let vocab_fname = vocab_fname.to_str().unwrap();
+        run(Some(vec![
+            "cracken",
+            "train",
+            "-f",
+            vocab_fname,
+            "-o",
+            model_fname,
+        ]))
+        .unwrap();
+
+        for args in [
+            vec![
+                "cracken",
+                "entropy",
+                "--mask-only",
+                "--english-freq",
+                "Pa55!",
+            ],
+            vec![
+                "cracken",
+                "entropy",
+                "-f",
+                vocab_fname,
+                "--english-freq",
+                "Pa55!",
+            ],
+            vec![
+                "cracken",
+                "entropy",
+                "-f",
+                vocab_fname,
+                "--charset-freq",
+                model_fname,
+                "Pa55!",
+            ],
+        ] {
+            assert!(run(Some(args)).is_ok());
+        }
+        fs::remove_file(model_fname).unwrap();
+        let args = vec![
+            "cracken",
+            "entropy",
+            "--mask-only",
+            "--charset-freq",
+            "no-such-freq.txt",
+            "x",
+        ];
+        assert!(run(Some(args)).is_err());
     }
 
     #[test]
-    fn test_run_bad_args2() {
-        let args = Some(vec!["cracken", "?x"]);
-        assert!(runner::run(args).is_err());
+    fn test_run_entropy_no_charset_words() {
+        let vocab_fname = test_util::wordlist_fname("wordlist-3words.txt");
+        let mut args = vec![
+            "cracken",
+            "entropy",
+            "--no-charset-words",
+            "--smartlist",
+            vocab_fname.to_str().unwrap(),
+        ];
+        let mut ok_args = args.clone();
+        ok_args.push("alphabeta");
+        assert!(run(Some(ok_args)).is_ok());
+        args.push("alpha1");
+        assert!(run(Some(args)).is_err());
     }
 
     #[test]
-    fn test_run_bad_args3() {
-        let args = Some(vec!["cracken", "-x", "5", "?d"]);
-        assert!(runner::run(args).is_err());
+    fn test_run_entropy_codepoints() {
+        let vocab_fname = test_util::wordlist_fname("vocab.txt");
+        let args = Some(vec![
+            "cracken",
+            "entropy",
+            "--codepoints",
+            "--smartlist",
+            vocab_fname.to_str().unwrap(),
+            "пароль123",
+        ]);
+        assert!(run(args).is_ok());
+        let args = Some(vec![
+            "cracken",
+            "entropy",
+            "--codepoints",
+            "--mask-only",
+            "😃",
+        ]);
+        assert!(run(args).is_ok());
+    }
+
+    #[test]
+    fn test_run_stats_estimate_time() {
+        let args = Some(vec![
+            "cracken",
+            "--stats",
+            "--estimate-time",
+            "?d?d?d",
+            "?l?l",
+        ]);
+        assert!(run(args).is_ok());
+    }
+
+    #[test]
+    fn test_format_time_estimate() {
+        let combs = 7_200_000.to_biguint().unwrap();
+        assert_eq!(
+            runner::format_time_estimate(&combs, 7200f64),
+            "~2.00 hours at ~1000 words/sec"
+        );
+        let combs = 0.to_biguint().unwrap();
+        assert_eq!(
+            runner::format_time_estimate(&combs, 0f64),
+            "~0.00 hours at ~0 words/sec"
+        );
+    }
+
+    #[test]
+    fn test_run_number_lines() {
+        let output = run_to_string("number-lines", vec!["--number-lines", "?d?d"]);
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 100);
+        assert_eq!(lines[0], "00:00");
+        assert_eq!(lines[99], "99:99");
+
+        // the index is global across masks
+        let output = run_to_string(
+            "number-lines-sep",
+            vec!["--number-lines", "--number-sep", "\t", "?d", "a?d"],
+        );
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 20);
+        assert_eq!(lines[0], "00\t0");
+        assert_eq!(lines[19], "19\ta9");
+    }
+
+    #[test]
+    fn test_run_prefix_suffix() {
+        let output = run_to_string(
+            "prefix-suffix",
+            vec!["--prefix", "pre-", "--suffix", "!", "?d"],
+        );
+        assert_eq!(output.lines().next(), Some("pre-0!"));
+        assert_eq!(output.lines().count(), 10);
+
+        let long_suffix = "x".repeat(500);
+        let args = vec![
+            "cracken",
+            "--suffix",
+            long_suffix.as_str(),
+            "?d?d?d?d?d?d?d?d?d?d?d?d",
+        ];
+        assert!(run(Some(args)).is_err());
+    }
+
+    #[test]
+    fn test_run_combine() {
+        let wordlist = test_util::wordlist_fname("wordlist-3words.txt");
+        let args = vec![
+            "--combine",
+            "-w",
+            wordlist.to_str().unwrap(),
+            "--total-min",
+            "9",
+            "--total-max",
+            "9",
+        ];
+        let output = run_to_string("combine", args);
+        assert_eq!(output, "betaalpha\nbetagamma\nalphabeta\ngammabeta\n");
+
+        let args = vec![
+            "cracken",
+            "--combine",
+            "-w",
+            wordlist.to_str().unwrap(),
+            "-w",
+            wordlist.to_str().unwrap(),
+            "--total-max",
+            "9",
+        ];
+        assert!(run(Some(args)).is_err());
     }
 }
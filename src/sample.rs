@@ -0,0 +1,108 @@
+use std::io::{Error, Read};
+
+use crate::helpers::RawFileReader;
+
+/// a small seedable pseudo random generator (splitmix64), good enough for sampling
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// returns a number in `0..n`
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+}
+
+/// returns `n` random lines of `reader` in a single pass, keeping only the sampled lines in
+/// memory (reservoir sampling). blank lines are skipped like in wordlists, and all the lines
+/// are returned when there are fewer than `n`
+pub fn reservoir_sample<R: Read>(reader: R, n: usize, seed: u64) -> Result<Vec<Vec<u8>>, Error> {
+    let mut rng = Rng::new(seed);
+    let mut sample = Vec::with_capacity(n.min(1 << 20));
+    let mut seen = 0u64;
+    for line in RawFileReader::new(reader) {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        seen += 1;
+        if sample.len() < n {
+            sample.push(line);
+        } else {
+            let idx = rng.below(seen) as usize;
+            if idx < n {
+                sample[idx] = line;
+            }
+        }
+    }
+    Ok(sample)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use crate::test_util::wordlist_fname;
+
+    use super::{reservoir_sample, Rng};
+
+    #[test]
+    fn test_rng_below() {
+        let mut rng = Rng::new(42);
+        let mut counts = [0; 4];
+        for _ in 0..4000 {
+            counts[rng.below(4) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&c| c > 900 && c < 1100), "{:?}", counts);
+    }
+
+    #[test]
+    fn test_reservoir_sample() {
+        let fname = wordlist_fname("wordlist1.txt");
+        let sample = |n, seed| reservoir_sample(File::open(&fname).unwrap(), n, seed).unwrap();
+
+        let first = sample(3, 1);
+        assert_eq!(first.len(), 3);
+        assert_eq!(sample(3, 1), first);
+        assert!((2..20).any(|seed| sample(3, seed) != first));
+
+        // fewer lines than requested returns them all in order
+        let all = sample(100, 1);
+        assert_eq!(all.len(), 10);
+        assert_eq!(all[0], b"123456");
+
+        let lines = "a\n\nb\nc\n".as_bytes();
+        assert_eq!(
+            reservoir_sample(lines, 5, 7).unwrap(),
+            vec![b"a", b"b", b"c"]
+        );
+        assert!(reservoir_sample(lines, 0, 7).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reservoir_sample_uniform() {
+        let data: String = (0..10).map(|i| format!("{}\n", i)).collect();
+        let mut counts = [0; 10];
+        for seed in 0..2000 {
+            for line in reservoir_sample(data.as_bytes(), 2, seed).unwrap() {
+                counts[String::from_utf8(line).unwrap().parse::<usize>().unwrap()] += 1;
+            }
+        }
+        // each line is expected 400 times
+        assert!(counts.iter().all(|&c| c > 300 && c < 500), "{:?}", counts);
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+use std::io::{Error, Read, Write};
+
+use crate::helpers::RawFileReader;
+
+/// writes every substring of `len` bytes (a sliding window) of each line of `reader`, newline
+/// separated, in line order and by offset within a line. lines shorter than `len` are
+/// skipped. with `unique` only the first occurrence of each substring is written, keeping the
+/// written substrings in memory
+pub fn write_substrings<R: Read, W: Write>(
+    reader: R,
+    len: usize,
+    unique: bool,
+    out: &mut W,
+) -> Result<(), Error> {
+    assert!(len > 0, "substrings length must be positive");
+    let mut seen = HashSet::new();
+    for line in RawFileReader::new(reader) {
+        let line = line?;
+        for window in line.windows(len) {
+            if unique && !seen.insert(window.to_vec()) {
+                continue;
+            }
+            out.write_all(window)?;
+            out.write_all(b"\n")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use crate::test_util::wordlist_fname;
+
+    use super::write_substrings;
+
+    fn substrings(data: &[u8], len: usize, unique: bool) -> String {
+        let mut out = vec![];
+        write_substrings(data, len, unique, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_write_substrings() {
+        assert_eq!(
+            substrings(b"password\n", 4, false),
+            "pass\nassw\nsswo\nswor\nword\n"
+        );
+        assert_eq!(substrings(b"abc\nab\n\nabcd", 3, false), "abc\nabc\nbcd\n");
+        assert_eq!(substrings(b"abc\nab\n\nabcd", 3, true), "abc\nbcd\n");
+        assert_eq!(substrings(b"aaaa\n", 2, false), "aa\naa\naa\n");
+        assert_eq!(substrings(b"aaaa\n", 2, true), "aa\n");
+        assert_eq!(substrings(b"ab\n", 1, false), "a\nb\n");
+        assert_eq!(substrings(b"short\n", 10, false), "");
+
+        let mut out = vec![];
+        let fname = wordlist_fname("wordlist-3words.txt");
+        write_substrings(File::open(fname).unwrap(), 5, false, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "alpha\ngamma\n");
+    }
+}
@@ -1,8 +1,14 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::io::{Error, Read};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
+use crate::charsets::Charset;
+#[cfg(all(feature = "mmap", unix))]
+use crate::helpers::Compression;
+use crate::helpers::{open_input, NetstringReader, RawFileReader};
+#[cfg(all(feature = "mmap", unix))]
+use crate::helpers::{strip_record_end, Mmap};
 use crate::BoxResult;
 
 /// a buffer containing words of the same length
@@ -10,40 +16,255 @@ use crate::BoxResult;
 struct WordsBuf {
     len: usize,
     words: Vec<u8>,
+    /// offsets of the words on the memory mapped wordlist file, used instead of `words`.
+    /// kept as u32 so the index is smaller than copying the words themselves
+    #[cfg(all(feature = "mmap", unix))]
+    offsets: Vec<u32>,
 }
 
+/// a wordlist loaded into memory. words are iterated grouped by ascending length, and in
+/// file order (files order for directories) within the same length. the order is the same
+/// on every load, with or without the `mmap` feature - generated words depend on it
 #[derive(Debug)]
 pub struct Wordlist {
     words_bufs: Vec<WordsBuf>,
+    /// whether any word contains a newline byte, only possible with delimiters other than
+    /// newlines (e.g. `WordlistFormat::Nul`)
+    has_newlines: bool,
+    /// number of words skipped on load for being longer than `WordlistOptions::max_word_len`
+    skipped_words: usize,
+    #[cfg(all(feature = "mmap", unix))]
+    mmap: Option<Mmap>,
+}
+
+/// selects a single delimited field from each line (e.g. a csv or tsv column)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnSelector {
+    /// zero based index of the field to select
+    pub index: usize,
+    pub delimiter: u8,
+}
+
+/// how the words of a wordlist file are delimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WordlistFormat {
+    /// newline delimited words
+    #[default]
+    Lines,
+    /// NUL delimited words, which may contain newlines
+    Nul,
+    /// netstrings (`<length>:<bytes>,`), which may contain any byte
+    Netstring,
+}
+
+impl WordlistFormat {
+    /// the name of the format, as given to --wordlist-format
+    pub fn name(&self) -> &'static str {
+        match self {
+            WordlistFormat::Lines => "lines",
+            WordlistFormat::Nul => "nul",
+            WordlistFormat::Netstring => "netstring",
+        }
+    }
+}
+
+/// options controlling how wordlist files are parsed
+#[derive(Debug, Clone, Default)]
+pub struct WordlistOptions {
+    format: WordlistFormat,
+    column: Option<ColumnSelector>,
+    recursive: bool,
+    dedup: bool,
+    max_word_len: Option<usize>,
+}
+
+/// summary statistics of a wordlist, see `Wordlist::stats`
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordlistStats {
+    pub words: usize,
+    /// number of words of each length, sorted by length
+    pub len_counts: Vec<(usize, usize)>,
+    pub min_len: usize,
+    pub max_len: usize,
+    pub avg_len: f64,
+    /// number of words containing at least one char of each of the ?l ?u ?d ?s charsets
+    pub charset_words: Vec<(char, usize)>,
 }
 
 pub struct WordlistIterator<'a> {
     wordlist: &'a Wordlist,
     wordbuf_pos: usize,
     word_pos: usize,
+    yielded: usize,
+}
+
+impl ColumnSelector {
+    pub fn new(index: usize, delimiter: u8) -> ColumnSelector {
+        ColumnSelector { index, delimiter }
+    }
+
+    /// returns the selected field of `line`, or an empty slice if the column is out of range
+    #[inline]
+    pub fn select<'a>(&self, line: &'a [u8]) -> &'a [u8] {
+        line.split(|&ch| ch == self.delimiter)
+            .nth(self.index)
+            .unwrap_or(&[])
+    }
+}
+
+impl WordlistOptions {
+    pub fn new() -> WordlistOptions {
+        WordlistOptions::default()
+    }
+    pub fn column(mut self, column: Option<ColumnSelector>) -> Self {
+        self.column = column;
+        self
+    }
+
+    /// how the words of the wordlist files are delimited, newlines by default
+    pub fn format(mut self, format: WordlistFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn wordlist_format(&self) -> WordlistFormat {
+        self.format
+    }
+
+    /// when the wordlist is a directory, also load files of its subdirectories
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// removes duplicate words, e.g. when loading a directory of overlapping wordlists
+    pub fn dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    pub fn is_dedup(&self) -> bool {
+        self.dedup
+    }
+
+    /// skips the words longer than `max_word_len` bytes (e.g. malformed giant lines of
+    /// downloaded wordlists) instead of loading them
+    pub fn max_word_len(mut self, max_word_len: Option<usize>) -> Self {
+        self.max_word_len = max_word_len;
+        self
+    }
+
+    /// returns whether `word` is too long to load, counting it as a skipped line
+    #[inline]
+    fn is_too_long(&self, word: &[u8], skipped: &mut usize) -> bool {
+        let too_long = self
+            .max_word_len
+            .is_some_and(|max_len| word.len() > max_len);
+        *skipped += too_long as usize;
+        too_long
+    }
+
+    /// iterates the raw lines (records) of a wordlist file according to its format, without
+    /// their delimiters
+    pub fn records<'a, R: Read + 'a>(
+        &self,
+        reader: R,
+    ) -> Box<dyn Iterator<Item = Result<Vec<u8>, Error>> + 'a> {
+        match self.format {
+            WordlistFormat::Lines => Box::new(RawFileReader::new(reader)),
+            WordlistFormat::Nul => Box::new(RawFileReader::with_delimiter(reader, b'\0')),
+            WordlistFormat::Netstring => Box::new(NetstringReader::new(reader)),
+        }
+    }
+
+    /// extracts the word from a raw line according to the options
+    #[inline]
+    pub fn parse_line<'a>(&self, line: &'a [u8]) -> &'a [u8] {
+        match &self.column {
+            Some(column) => column.select(line),
+            None => line,
+        }
+    }
 }
 
 impl Wordlist {
     pub fn from_file<P: AsRef<Path>>(fname: P) -> BoxResult<Wordlist> {
-        let fp = BufReader::new(File::open(fname)?);
+        Self::from_file_with_options(fname, &WordlistOptions::default())
+    }
+
+    /// loads a wordlist file, or all the files of a directory sorted by name as a single
+    /// wordlist. with the `mmap` feature a single file is memory mapped instead of copied
+    /// into memory
+    pub fn from_file_with_options<P: AsRef<Path>>(
+        fname: P,
+        options: &WordlistOptions,
+    ) -> BoxResult<Wordlist> {
+        let path = fname.as_ref();
+        if path.is_dir() {
+            return Self::from_files_copy(&dir_files(path, options.recursive)?, options, None);
+        }
+
+        // fifos and devices cannot be memory mapped, their words are streamed instead
+        #[cfg(all(feature = "mmap", unix))]
+        if !options.dedup && crate::helpers::is_regular_file(path) {
+            return Self::from_file_mmap(path, options);
+        }
+        Self::from_files_copy(&[path], options, None)
+    }
+
+    /// loads only the words of the lines in `lines` (0-based, blank lines included) of a
+    /// single wordlist file
+    pub fn from_file_lines<P: AsRef<Path>>(
+        fname: P,
+        options: &WordlistOptions,
+        lines: Range<usize>,
+    ) -> BoxResult<Wordlist> {
+        Self::from_files_copy(&[fname], options, Some(lines))
+    }
+
+    /// loads wordlist files by copying their words into per length buffers, only the lines
+    /// in `lines` of the files concatenated if given
+    fn from_files_copy<P: AsRef<Path>>(
+        fnames: &[P],
+        options: &WordlistOptions,
+        lines: Option<Range<usize>>,
+    ) -> BoxResult<Wordlist> {
         let mut len2words = HashMap::new();
+        let mut seen = HashSet::new();
+        let mut lineno = 0;
+        let mut has_newlines = false;
+        let mut skipped_words = 0;
 
-        fp.split(b'\n')
-            .try_for_each::<_, Result<(), std::io::Error>>(|word| {
-                let mut word = word?;
-                if !word.is_empty() {
-                    if word.last() == Some(&b'\n') {
-                        word.pop();
+        for fname in fnames {
+            options
+                .records(open_input(fname)?)
+                .try_for_each::<_, Result<(), Error>>(|line| {
+                    let line = line?;
+                    lineno += 1;
+                    if lines
+                        .as_ref()
+                        .is_some_and(|lines| !lines.contains(&(lineno - 1)))
+                    {
+                        return Ok(());
                     }
+                    // records exclude their delimiter, blank ones (e.g. a trailing line) are skipped
+                    let word = options.parse_line(&line);
+                    if !word.is_empty() && !options.is_too_long(word, &mut skipped_words) {
+                        if options.dedup && !seen.insert(word.to_vec()) {
+                            return Ok(());
+                        }
+                        has_newlines |=
+                            options.format != WordlistFormat::Lines && word.contains(&b'\n');
 
-                    let lenvec = len2words.entry(word.len()).or_insert_with(Vec::new);
-                    lenvec.extend_from_slice(&word);
+                        let lenvec = len2words.entry(word.len()).or_insert_with(Vec::new);
+                        lenvec.extend_from_slice(word);
 
-                    // avoid small allocations of memory for large wordlists
-                    lenvec.reserve(word.len() * 1024 * 1024);
-                }
-                Ok(())
-            })?;
+                        // avoid small allocations of memory for large wordlists
+                        lenvec.reserve(word.len() * 1024 * 1024);
+                    }
+                    Ok(())
+                })?;
+        }
 
         len2words
             .iter_mut()
@@ -51,34 +272,239 @@ impl Wordlist {
 
         let mut words_bufs: Vec<_> = len2words
             .into_iter()
-            .map(|(len, words)| WordsBuf { len, words })
+            .map(|(len, words)| WordsBuf {
+                len,
+                words,
+                #[cfg(all(feature = "mmap", unix))]
+                offsets: vec![],
+            })
+            .collect();
+
+        // lengths are unique so the order does not depend on the map iteration order
+        words_bufs.sort_unstable_by_key(|a| a.len);
+        Ok(Wordlist {
+            words_bufs,
+            has_newlines,
+            skipped_words,
+            #[cfg(all(feature = "mmap", unix))]
+            mmap: None,
+        })
+    }
+
+    /// loads a wordlist file by memory mapping it and indexing the offsets of its words
+    #[cfg(all(feature = "mmap", unix))]
+    fn from_file_mmap<P: AsRef<Path>>(fname: P, options: &WordlistOptions) -> BoxResult<Wordlist> {
+        let delimiter = match options.format {
+            WordlistFormat::Lines => b'\n',
+            WordlistFormat::Nul => b'\0',
+            // netstrings are parsed while copying
+            WordlistFormat::Netstring => return Self::from_files_copy(&[fname], options, None),
+        };
+        let file = std::fs::File::open(&fname)?;
+        if file.metadata()?.len() > u32::MAX as u64 {
+            // offsets do not fit in u32 - fallback to copying
+            return Self::from_files_copy(&[fname], options, None);
+        }
+        let mmap = Mmap::open(&file)?;
+        if Compression::detect(&mmap) != Compression::None {
+            return Self::from_files_copy(&[fname], options, None);
+        }
+        let base = mmap.as_ptr() as usize;
+        let mut len2offsets: HashMap<usize, Vec<u32>> = HashMap::new();
+        let mut skipped_words = 0;
+        let mut has_newlines = false;
+
+        for line in mmap.split_inclusive(|&ch| ch == delimiter) {
+            let word = options.parse_line(strip_record_end(line, delimiter));
+            if !word.is_empty() && !options.is_too_long(word, &mut skipped_words) {
+                let offset = (word.as_ptr() as usize - base) as u32;
+                has_newlines |= delimiter != b'\n' && word.contains(&b'\n');
+                len2offsets.entry(word.len()).or_default().push(offset);
+            }
+        }
+
+        let mut words_bufs: Vec<_> = len2offsets
+            .into_iter()
+            .map(|(len, mut offsets)| {
+                offsets.shrink_to_fit();
+                WordsBuf {
+                    len,
+                    words: vec![],
+                    offsets,
+                }
+            })
             .collect();
 
-        words_bufs.sort_unstable_by(|a, b| a.len.cmp(&b.len));
-        Ok(Wordlist { words_bufs })
+        // lengths are unique so the order does not depend on the map iteration order
+        words_bufs.sort_unstable_by_key(|a| a.len);
+        Ok(Wordlist {
+            words_bufs,
+            has_newlines,
+            skipped_words,
+            mmap: Some(mmap),
+        })
     }
 
+    /// returns the word at byte position `pos` of the (virtual) concatenated words of `word_buf`
     #[inline]
-    pub fn iter(&self) -> WordlistIterator {
+    fn word_at<'a>(&'a self, word_buf: &'a WordsBuf, pos: usize) -> &'a [u8] {
+        #[cfg(all(feature = "mmap", unix))]
+        if let Some(mmap) = &self.mmap {
+            let offset = word_buf.offsets[pos / word_buf.len] as usize;
+            return &mmap[offset..offset + word_buf.len];
+        }
+        &word_buf.words[pos..pos + word_buf.len]
+    }
+
+    /// returns the word at `index` in iteration order, or `None` if out of range
+    pub fn word(&self, mut index: usize) -> Option<&[u8]> {
+        for word_buf in self.words_bufs.iter() {
+            let count = word_buf.size() / word_buf.len;
+            if index < count {
+                return Some(self.word_at(word_buf, index * word_buf.len));
+            }
+            index -= count;
+        }
+        None
+    }
+
+    #[inline]
+    pub fn iter(&self) -> WordlistIterator<'_> {
         WordlistIterator {
             wordlist: self,
             wordbuf_pos: 0,
             word_pos: 0,
+            yielded: 0,
         }
     }
 
     #[inline]
     pub fn len(&self) -> usize {
-        self.words_bufs
-            .iter()
-            .map(|wb| wb.words.len() / wb.len)
-            .sum()
+        self.words_bufs.iter().map(|wb| wb.size() / wb.len).sum()
     }
 
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// returns true if any word contains a newline byte
+    pub fn has_newlines(&self) -> bool {
+        self.has_newlines
+    }
+
+    /// returns the number of words skipped on load for being longer than the max word len
+    /// option
+    pub fn skipped_words(&self) -> usize {
+        self.skipped_words
+    }
+
+    /// returns the length of the longest word, or 0 if the wordlist is empty
+    pub fn max_word_len(&self) -> usize {
+        self.words_bufs.last().map_or(0, |wb| wb.len)
+    }
+
+    /// returns the number of words of each length, sorted by length
+    pub fn len_counts(&self) -> Vec<(usize, usize)> {
+        self.words_bufs
+            .iter()
+            .map(|wb| (wb.len, wb.size() / wb.len))
+            .collect()
+    }
+
+    pub fn stats(&self) -> WordlistStats {
+        let len_counts = self.len_counts();
+        let words = self.len();
+        let total_len: usize = len_counts.iter().map(|(len, count)| len * count).sum();
+        let charsets: Vec<_> = ['l', 'u', 'd', 's']
+            .iter()
+            .map(|&symbol| (symbol, Charset::from_symbol(symbol)))
+            .collect();
+
+        let mut charset_words = vec![0; charsets.len()];
+        for word_buf in self.words_bufs.iter() {
+            for pos in (0..word_buf.size()).step_by(word_buf.len) {
+                let word = self.word_at(word_buf, pos);
+                for (count, (_, charset)) in charset_words.iter_mut().zip(charsets.iter()) {
+                    if word.iter().any(|&ch| charset.contains(ch)) {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+
+        WordlistStats {
+            words,
+            min_len: len_counts.first().map_or(0, |&(len, _)| len),
+            max_len: self.max_word_len(),
+            avg_len: if words > 0 {
+                total_len as f64 / words as f64
+            } else {
+                0.0
+            },
+            charset_words: charsets
+                .iter()
+                .map(|&(symbol, _)| symbol)
+                .zip(charset_words)
+                .collect(),
+            len_counts,
+        }
+    }
+}
+
+impl WordlistStats {
+    /// returns the `n` most common word lengths with their counts, the shortest first on ties
+    pub fn top_lens(&self, n: usize) -> Vec<(usize, usize)> {
+        let mut len_counts = self.len_counts.clone();
+        len_counts.sort_by_key(|&(len, count)| (std::cmp::Reverse(count), len));
+        len_counts.truncate(n);
+        len_counts
+    }
+}
+
+/// returns the files a wordlist is loaded from - the file itself, or the files of a directory
+pub fn wordlist_files<P: AsRef<Path>>(
+    fname: P,
+    options: &WordlistOptions,
+) -> BoxResult<Vec<PathBuf>> {
+    let path = fname.as_ref();
+    if path.is_dir() {
+        dir_files(path, options.recursive)
+    } else {
+        Ok(vec![path.to_path_buf()])
+    }
+}
+
+/// lists the regular files of a directory sorted by name, descending into subdirectories
+/// only if `recursive` is set
+fn dir_files(dir: &Path, recursive: bool) -> BoxResult<Vec<PathBuf>> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let mut files = vec![];
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            if recursive {
+                files.extend(dir_files(&path, recursive)?);
+            }
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+impl WordsBuf {
+    /// returns the total bytes of the words of this buffer
+    #[inline]
+    fn size(&self) -> usize {
+        #[cfg(all(feature = "mmap", unix))]
+        if !self.offsets.is_empty() {
+            return self.offsets.len() * self.len;
+        }
+        self.words.len()
+    }
 }
 
 impl<'a> Iterator for WordlistIterator<'a> {
@@ -88,19 +514,21 @@ impl<'a> Iterator for WordlistIterator<'a> {
         let mut word_buf = &self.wordlist.words_bufs[self.wordbuf_pos];
 
         // advance next word
-        if self.word_pos < word_buf.words.len() {
+        if self.word_pos < word_buf.size() {
             let prev_word_pos = self.word_pos;
             self.word_pos += word_buf.len;
+            self.yielded += 1;
 
-            Some(&word_buf.words[prev_word_pos..self.word_pos])
+            Some(self.wordlist.word_at(word_buf, prev_word_pos))
 
         // finished the current wordbuf - advance to next one
         } else if self.wordbuf_pos < self.wordlist.words_bufs.len() - 1 {
             self.wordbuf_pos += 1;
             word_buf = &self.wordlist.words_bufs[self.wordbuf_pos];
             self.word_pos = word_buf.len;
+            self.yielded += 1;
 
-            Some(&word_buf.words[..self.word_pos])
+            Some(self.wordlist.word_at(word_buf, 0))
 
         // finished all wordbufs on the wordlist - no next item
         } else {
@@ -116,17 +544,23 @@ impl<'a> WordlistIterator<'a> {
         let word_buf = &self.wordlist.words_bufs[self.wordbuf_pos];
         word_buf.len
     }
+
+    /// returns the index on the wordlist of the last word returned by this iterator
+    #[inline]
+    pub fn current_index(&self) -> usize {
+        self.yielded.saturating_sub(1)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::test_util::wordlist_fname;
 
-    use super::Wordlist;
+    use super::{ColumnSelector, Wordlist, WordlistFormat, WordlistOptions, WordlistStats};
 
     #[test]
     fn test_wordlist_from_file() {
-        let wordlist = Wordlist::from_file(&wordlist_fname("wordlist1.txt")).unwrap();
+        let wordlist = Wordlist::from_file(wordlist_fname("wordlist1.txt")).unwrap();
 
         let words = wordlist
             .iter()
@@ -147,5 +581,249 @@ password
             .map(|s| s.to_owned())
             .collect();
         assert_eq!(words, expected);
+        assert_eq!(
+            wordlist.len_counts(),
+            vec![(5, 1), (6, 5), (7, 1), (8, 2), (9, 1)]
+        );
+        assert_eq!(wordlist.max_word_len(), 9);
+        for (i, word) in wordlist.iter().enumerate() {
+            assert_eq!(wordlist.word(i), Some(word));
+        }
+        assert_eq!(wordlist.word(10), None);
+    }
+
+    #[test]
+    fn test_wordlist_crlf() {
+        let wordlist = Wordlist::from_file(wordlist_fname("wordlist-crlf.txt")).unwrap();
+        let words: Vec<&[u8]> = wordlist.iter().collect();
+        let expected: Vec<&[u8]> = vec![b"beta", b"alpha", b"gamma"];
+        assert_eq!(words, expected);
+    }
+
+    #[test]
+    fn test_wordlist_iteration_order() {
+        let fname = wordlist_fname("wordlist1.txt");
+        let load = || {
+            Wordlist::from_file(&fname)
+                .unwrap()
+                .iter()
+                .map(|w| w.to_vec())
+                .collect::<Vec<_>>()
+        };
+        let words = load();
+        for _ in 0..10 {
+            assert_eq!(load(), words);
+        }
+
+        // grouped by length, in file order within each length
+        let mut expected: Vec<_> = std::fs::read(&fname)
+            .unwrap()
+            .split(|&ch| ch == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| line.to_vec())
+            .collect();
+        expected.sort_by_key(|word| word.len());
+        assert_eq!(words, expected);
+
+        let options = WordlistOptions::new().dedup(true);
+        let copied = Wordlist::from_file_with_options(&fname, &options).unwrap();
+        assert!(copied.iter().eq(words.iter().map(|w| &w[..])));
+    }
+
+    #[test]
+    fn test_wordlist_from_file_lines() {
+        let fname = wordlist_fname("wordlist1.txt");
+        let words = |lines| {
+            Wordlist::from_file_lines(&fname, &WordlistOptions::new(), lines)
+                .unwrap()
+                .iter()
+                .map(|w| String::from_utf8(w.to_vec()).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(words(0..3), vec!["123456", "password", "123456789"]);
+        assert_eq!(words(8..100), vec!["abc123", "1234567"]);
+        assert!(
+            Wordlist::from_file_lines(&fname, &WordlistOptions::new(), 10..20)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(words(0..100).len(), 10);
+    }
+
+    #[test]
+    fn test_wordlist_from_file_column() {
+        let options = WordlistOptions::new().column(Some(ColumnSelector::new(2, b',')));
+        let wordlist =
+            Wordlist::from_file_with_options(wordlist_fname("wordlist-columns.csv"), &options)
+                .unwrap();
+
+        let words = wordlist
+            .iter()
+            .map(|c| String::from_utf8(c.to_vec()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(words, vec!["secret", "hunter2", "letmein", "password"]);
+    }
+
+    #[test]
+    fn test_wordlist_last_line() {
+        for fname in [
+            "wordlist-3words.txt",
+            "wordlist-no-trailing-newline.txt",
+            "wordlist-trailing-blank.txt",
+        ] {
+            let wordlist = Wordlist::from_file(wordlist_fname(fname)).unwrap();
+            let words = wordlist
+                .iter()
+                .map(|c| String::from_utf8(c.to_vec()).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(words, vec!["beta", "alpha", "gamma"], "{}", fname);
+        }
+    }
+
+    #[test]
+    fn test_wordlist_from_dir() {
+        let to_strings = |wordlist: Wordlist| {
+            wordlist
+                .iter()
+                .map(|c| String::from_utf8(c.to_vec()).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let dirname = wordlist_fname("wordlist-dir");
+
+        let wordlist = Wordlist::from_file(&dirname).unwrap();
+        assert_eq!(to_strings(wordlist), vec!["one", "two", "two", "three"]);
+
+        let options = WordlistOptions::new().dedup(true);
+        let wordlist = Wordlist::from_file_with_options(&dirname, &options).unwrap();
+        assert_eq!(to_strings(wordlist), vec!["one", "two", "three"]);
+
+        let options = WordlistOptions::new().dedup(true).recursive(true);
+        let wordlist = Wordlist::from_file_with_options(&dirname, &options).unwrap();
+        assert_eq!(to_strings(wordlist), vec!["one", "two", "four", "three"]);
+    }
+
+    #[test]
+    fn test_wordlist_format() {
+        let expected: Vec<&[u8]> = vec![b"alpha", b"be\nta", b"gamma", b"pass word\r\n"];
+        for (fname, format) in [
+            ("wordlist-nul.txt", WordlistFormat::Nul),
+            ("wordlist-netstring.txt", WordlistFormat::Netstring),
+        ] {
+            let fname = wordlist_fname(fname);
+            let options = WordlistOptions::new().format(format);
+            let wordlist = Wordlist::from_file_with_options(&fname, &options).unwrap();
+            assert_eq!(
+                wordlist.iter().collect::<Vec<_>>(),
+                expected,
+                "{:?}",
+                format
+            );
+            assert!(wordlist.has_newlines());
+            let wordlist = Wordlist::from_file_lines(&fname, &options, 1..2).unwrap();
+            assert_eq!(wordlist.iter().collect::<Vec<_>>(), vec![b"be\nta"]);
+        }
+
+        // newlines split the words of the nul delimited wordlist by default
+        let wordlist = Wordlist::from_file(wordlist_fname("wordlist-nul.txt")).unwrap();
+        assert_eq!(wordlist.len(), 3);
+        assert!(!wordlist.has_newlines());
+
+        let options = WordlistOptions::new().format(WordlistFormat::Netstring);
+        assert!(
+            Wordlist::from_file_with_options(wordlist_fname("wordlist1.txt"), &options).is_err()
+        );
+    }
+
+    #[test]
+    fn test_wordlist_max_word_len() {
+        let fname = wordlist_fname("wordlist-long-line.txt");
+        let to_strings = |wordlist: Wordlist| {
+            wordlist
+                .iter()
+                .map(|c| String::from_utf8(c.to_vec()).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let wordlist = Wordlist::from_file(&fname).unwrap();
+        assert_eq!(wordlist.len(), 5);
+        assert_eq!(wordlist.max_word_len(), 600);
+
+        assert_eq!(wordlist.skipped_words(), 0);
+
+        let options = WordlistOptions::new().max_word_len(Some(511));
+        let wordlist = Wordlist::from_file_with_options(&fname, &options).unwrap();
+        assert_eq!(wordlist.skipped_words(), 2);
+        assert_eq!(to_strings(wordlist), vec!["beta", "alpha", "gamma"]);
+
+        // only the skipped words of the loaded lines are counted
+        let wordlist = Wordlist::from_file_lines(&fname, &options, 0..2).unwrap();
+        assert_eq!(wordlist.skipped_words(), 1);
+
+        let options = WordlistOptions::new().max_word_len(Some(4));
+        let wordlist = Wordlist::from_file_with_options(&fname, &options).unwrap();
+        assert_eq!(wordlist.skipped_words(), 4);
+        assert_eq!(to_strings(wordlist), vec!["beta"]);
+
+        // the skipped words of all the files of a directory
+        let options = WordlistOptions::new().max_word_len(Some(3));
+        let dirname = wordlist_fname("wordlist-dir");
+        let wordlist = Wordlist::from_file_with_options(dirname, &options).unwrap();
+        assert_eq!(wordlist.skipped_words(), 1);
+
+        let options = WordlistOptions::new().max_word_len(Some(10));
+        let wordlist =
+            Wordlist::from_file_with_options(wordlist_fname("wordlist1.txt"), &options).unwrap();
+        assert_eq!(wordlist.skipped_words(), 0);
+    }
+
+    #[test]
+    fn test_wordlist_compressed() {
+        #[cfg(feature = "gzip")]
+        {
+            let wordlist = Wordlist::from_file(wordlist_fname("wordlist1.txt")).unwrap();
+            let compressed = Wordlist::from_file(wordlist_fname("wordlist1.txt.gz")).unwrap();
+            assert!(compressed.iter().eq(wordlist.iter()));
+        }
+        #[cfg(not(feature = "gzip"))]
+        assert!(Wordlist::from_file(wordlist_fname("wordlist1.txt.gz")).is_err());
+        let wordlist = Wordlist::from_file(wordlist_fname("wordlist1.txt")).unwrap();
+        for (fname, supported) in [
+            ("wordlist1.txt.zst", cfg!(feature = "zstd")),
+            ("wordlist1.txt.xz", cfg!(feature = "xz")),
+        ] {
+            let compressed = Wordlist::from_file(wordlist_fname(fname));
+            if supported {
+                assert!(compressed.unwrap().iter().eq(wordlist.iter()));
+            } else {
+                assert!(compressed.is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_wordlist_stats() {
+        let wordlist = Wordlist::from_file(wordlist_fname("wordlist1.txt")).unwrap();
+        let stats = wordlist.stats();
+        assert_eq!(
+            stats,
+            WordlistStats {
+                words: 10,
+                len_counts: vec![(5, 1), (6, 5), (7, 1), (8, 2), (9, 1)],
+                min_len: 5,
+                max_len: 9,
+                avg_len: 6.7,
+                charset_words: vec![('l', 3), ('u', 0), ('d', 8), ('s', 0)],
+            }
+        );
+        assert_eq!(stats.top_lens(3), vec![(6, 5), (8, 2), (5, 1)]);
+        assert_eq!(stats.top_lens(10).len(), 5);
+    }
+
+    #[test]
+    fn test_column_selector() {
+        let column = ColumnSelector::new(1, b'\t');
+        assert_eq!(column.select(b"a\tb\tc"), b"b");
+        assert_eq!(column.select(b"a\t\tc"), b"");
+        assert_eq!(column.select(b"a"), b"");
     }
 }
@@ -0,0 +1,47 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// runs the cracken binary with `args`, writing `input` to its stdin
+fn run_with_stdin(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cracken"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_stdin_wordlist() {
+    let output = run_with_stdin(&["-w", "-", "?w1?d"], b"ab\r\ncd\r\n\r\n");
+    assert!(output.status.success(), "{:?}", output);
+    let expected: String = ["ab", "cd"]
+        .iter()
+        .flat_map(|word| (0..10).map(move |d| format!("{}{}\n", word, d)))
+        .collect();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+}
+
+#[test]
+fn test_stdin_masks_file() {
+    let output = run_with_stdin(&["-i", "-"], b"?d\r\na?d\n");
+    assert!(output.status.success(), "{:?}", output);
+    let expected: String = (0..10)
+        .map(|d| format!("{}\n", d))
+        .chain((0..10).map(|d| format!("a{}\n", d)))
+        .collect();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+}
+
+#[test]
+fn test_stdin_passwords_file() {
+    let output = run_with_stdin(&["entropy", "--mask-only", "-p", "-"], b"123456\r\n");
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "19.93,?d?d?d?d?d?d,123456\n"
+    );
+}